@@ -0,0 +1,295 @@
+use core::ops::Range;
+
+use crate::{
+    csr,
+    decode::U12,
+    error::Error,
+    hart::{Hart, Privilege},
+    mmu::{Access, Mmu},
+    num::As,
+    registers::{Register, Registers},
+};
+
+/// `xtval` of the software-check exception raised by a missing or mismatched landing pad.
+pub const LANDING_PAD_FAULT: usize = 2;
+/// `xtval` of the software-check exception raised by a mismatched shadow-stack return address.
+pub const SHADOW_STACK_FAULT: usize = 3;
+
+/// Landing pad enable of `menvcfg` and `senvcfg`.
+const LPE: u64 = 1 << 2;
+/// Shadow stack enable of `menvcfg` and `senvcfg`.
+const SSE: u64 = 1 << 3;
+/// Machine landing pad enable of `mseccfg`.
+const MLPE: u64 = 1 << 10;
+
+/// Control-flow integrity state of the Zicfilp and Zicfiss extensions.
+#[derive(Debug, Default)]
+pub struct Cfi<T> {
+    /// Shadow stack pointer, the `ssp` CSR.
+    pub ssp: T,
+    /// Expected landing pad, set by indirect jumps when landing pads are enforced.
+    pub elp: bool,
+    /// Memory regions of the shadow-stack type: ordinary stores and AMOs cannot write them and,
+    /// once any is defined, shadow-stack accesses must fall in one.
+    pub regions: Vec<Range<usize>>,
+}
+
+/// Shadow stack of the executing hart, handed to the instructions allowed to access it.
+#[derive(Debug)]
+pub struct ShadowStack<'a, T> {
+    pub ssp: &'a mut T,
+    pub regions: &'a [Range<usize>],
+    /// Translation of the shadow-stack accesses, the regions hold physical addresses.
+    pub mmu: &'a Mmu,
+}
+
+#[inline(always)]
+fn csr_bits<T: Copy + As<u64>>(hart: &Hart<T>, addr: U12) -> u64 {
+    hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as())
+}
+
+/// Whether landing pads are enforced in the current privilege mode.
+pub fn landing_pads<T: Copy + As<u64>>(hart: &Hart<T>) -> bool {
+    let enable = match hart.privilege {
+        Privilege::Machine => return csr_bits(hart, csr::MSECCFG) & MLPE != 0,
+        Privilege::Supervisor => csr_bits(hart, csr::MENVCFG),
+        Privilege::User => csr_bits(hart, csr::SENVCFG),
+    };
+    enable & LPE != 0
+}
+
+/// Whether shadow stacks are enabled in the current privilege mode, they never are in M-mode and
+/// U-mode also needs them enabled for S-mode.
+pub fn shadow_stacks<T: Copy + As<u64>>(hart: &Hart<T>) -> bool {
+    let enable = match hart.privilege {
+        Privilege::Machine => 0,
+        Privilege::Supervisor => csr_bits(hart, csr::MENVCFG),
+        Privilege::User => csr_bits(hart, csr::MENVCFG) & csr_bits(hart, csr::SENVCFG),
+    };
+    enable & SSE != 0
+}
+
+/// Whether the reserved `xwr = 010` PTE encoding marks shadow-stack pages.
+pub fn shadow_stack_pages<T: Copy + As<u64>>(hart: &Hart<T>) -> bool {
+    csr_bits(hart, csr::MENVCFG) & SSE != 0
+}
+
+/// Whether SSAMOSWAP can access the shadow stack, it always can in M-mode.
+pub fn shadow_stack_swaps<T: Copy + As<u64>>(hart: &Hart<T>) -> bool {
+    hart.privilege == Privilege::Machine || shadow_stacks(hart)
+}
+
+impl<T> Cfi<T> {
+    #[inline]
+    pub fn shadow_stack<'a>(&'a mut self, mmu: &'a Mmu) -> ShadowStack<'a, T> {
+        ShadowStack {
+            ssp: &mut self.ssp,
+            regions: &self.regions,
+            mmu,
+        }
+    }
+}
+
+/// Checks the instruction following an indirect jump: it must be a 4-byte aligned LPAD, the
+/// AUIPC writing x0, whose label is either zero or matches `x7[31:12]`.
+pub fn landing_pad<T>(encoded: u32, size: u8, pc: T, regs: &Registers<T>) -> Result<(), Error>
+where
+    T: Copy + As<u64>,
+{
+    const LPAD: u32 = 0b00000_0010111;
+
+    let label = encoded >> 12;
+    let expected = (As::<u64>::r#as(regs.get(Register::X7)) >> 12) as u32 & 0xf_ffff;
+    if size == 4
+        && encoded & 0xfff == LPAD
+        && As::<u64>::r#as(pc) & 0b11 == 0
+        && (label == 0 || label == expected)
+    {
+        Ok(())
+    } else {
+        Err(Error::SoftwareCheck(LANDING_PAD_FAULT))
+    }
+}
+
+/// Ordinary stores and AMOs of `size` bytes at `addr` cannot write shadow-stack memory.
+pub fn check_store(regions: &[Range<usize>], addr: usize, size: usize) -> Result<(), Error> {
+    if regions
+        .iter()
+        .any(|region| addr < region.end && region.start < addr.saturating_add(size))
+    {
+        Err(Error::StoreAccessFault(addr))
+    } else {
+        Ok(())
+    }
+}
+
+impl<T> ShadowStack<'_, T>
+where
+    T: Copy + PartialEq + As<u128> + As<usize>,
+    u128: As<T>,
+    usize: As<T>,
+{
+    const SIZE: usize = core::mem::size_of::<T>();
+
+    /// Shadow-stack accesses must be naturally aligned and fall in the shadow-stack memory, they
+    /// report access faults otherwise.
+    fn slot<'m>(
+        &self,
+        addr: usize,
+        size: usize,
+        memory: &'m mut [u8],
+    ) -> Result<&'m mut [u8], Error> {
+        if !addr.is_multiple_of(size) {
+            return Err(Error::StoreAccessFault(addr));
+        }
+        let paddr = self
+            .mmu
+            .translate(addr as u64, size, Access::ShadowStack, memory)?;
+        let inside = self.regions.is_empty()
+            || self
+                .regions
+                .iter()
+                .any(|region| region.start <= paddr && paddr + size <= region.end);
+        if !inside {
+            return Err(Error::StoreAccessFault(addr));
+        }
+        Ok(&mut memory[paddr..paddr + size])
+    }
+
+    #[inline]
+    pub fn push(&mut self, value: T, memory: &mut [u8]) -> Result<(), Error> {
+        let addr = As::<usize>::r#as(*self.ssp).wrapping_sub(Self::SIZE);
+        self.swap(addr, value.r#as(), Self::SIZE, memory)?;
+        *self.ssp = addr.r#as();
+        Ok(())
+    }
+
+    /// Pops the top of the shadow stack if it matches `expected`, raises a software-check
+    /// exception leaving the stack untouched otherwise.
+    #[inline]
+    pub fn pop(&mut self, expected: T, memory: &mut [u8]) -> Result<(), Error> {
+        let addr: usize = (*self.ssp).r#as();
+        let mut bytes = [0u8; 16];
+        bytes[..Self::SIZE].copy_from_slice(self.slot(addr, Self::SIZE, memory)?);
+        if As::<T>::r#as(u128::from_le_bytes(bytes)) != expected {
+            return Err(Error::SoftwareCheck(SHADOW_STACK_FAULT));
+        }
+        *self.ssp = addr.wrapping_add(Self::SIZE).r#as();
+        Ok(())
+    }
+
+    /// Atomically replaces the `size` bytes wide slot at `addr`, returning its previous value.
+    #[inline]
+    pub fn swap(
+        &self,
+        addr: usize,
+        value: u128,
+        size: usize,
+        memory: &mut [u8],
+    ) -> Result<u128, Error> {
+        let slot = self.slot(addr, size, memory)?;
+        let mut bytes = [0u8; 16];
+        bytes[..size].copy_from_slice(slot);
+        slot.copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(u128::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn landing_pad_checks() {
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X7) = 0x1234_5000;
+        let fault = Err(Error::SoftwareCheck(LANDING_PAD_FAULT));
+
+        // lpad 0 and lpad 0x12345
+        assert_eq!(landing_pad(0x0000_0017, 4, 0x100u64, &regs), Ok(()));
+        assert_eq!(landing_pad(0x1234_5017, 4, 0x100u64, &regs), Ok(()));
+        // mismatched label
+        assert_eq!(landing_pad(0x1234_6017, 4, 0x100u64, &regs), fault);
+        // misaligned landing pad
+        assert_eq!(landing_pad(0x0000_0017, 4, 0x102u64, &regs), fault);
+        // auipc x1, 0 and a compressed instruction are not landing pads
+        assert_eq!(landing_pad(0x0000_0097, 4, 0x100u64, &regs), fault);
+        assert_eq!(landing_pad(0x0000_0013, 2, 0x100u64, &regs), fault);
+    }
+
+    #[test]
+    fn enables() {
+        let mut hart = Hart::<u64>::default();
+        assert!(!landing_pads(&hart));
+        assert!(!shadow_stacks(&hart));
+
+        csr::write(&mut hart, csr::MSECCFG, MLPE).unwrap();
+        csr::write(&mut hart, csr::MENVCFG, SSE).unwrap();
+        assert!(landing_pads(&hart));
+        // M-mode has no shadow stack, but can still use SSAMOSWAP
+        assert!(!shadow_stacks(&hart));
+        assert!(shadow_stack_swaps(&hart));
+
+        hart.privilege = Privilege::Supervisor;
+        assert!(!landing_pads(&hart));
+        assert!(shadow_stacks(&hart));
+        assert_eq!(csr::check_state(&hart, csr::SSP), Ok(()));
+
+        hart.privilege = Privilege::User;
+        assert!(!shadow_stacks(&hart));
+        assert!(!shadow_stack_swaps(&hart));
+        assert_eq!(csr::check_state(&hart, csr::SSP), Err(Error::InvalidOpCode));
+        csr::write(&mut hart, csr::SENVCFG, SSE | LPE).unwrap();
+        assert!(landing_pads(&hart));
+        assert!(shadow_stacks(&hart));
+        csr::write(&mut hart, csr::MENVCFG, 0).unwrap();
+        assert!(!shadow_stacks(&hart));
+    }
+
+    #[test]
+    fn shadow_stack_accesses() {
+        let mut memory = [0u8; 64];
+        let mut cfi = Cfi::<u64> {
+            ssp: 32,
+            ..Default::default()
+        };
+
+        let mut ss = cfi.shadow_stack(&Mmu::BARE);
+        ss.push(0x1234, &mut memory).unwrap();
+        assert_eq!(*ss.ssp, 24);
+        assert_eq!(memory[24..32], 0x1234u64.to_le_bytes());
+        assert_eq!(
+            ss.pop(0x1235, &mut memory),
+            Err(Error::SoftwareCheck(SHADOW_STACK_FAULT))
+        );
+        assert_eq!(*ss.ssp, 24);
+        ss.pop(0x1234, &mut memory).unwrap();
+        assert_eq!(*ss.ssp, 32);
+
+        assert_eq!(ss.swap(28, 0xaabb, 4, &mut memory), Ok(0));
+        assert_eq!(ss.swap(28, 0, 4, &mut memory), Ok(0xaabb));
+
+        // misaligned and out of memory accesses
+        *ss.ssp = 30;
+        assert_eq!(ss.push(1, &mut memory), Err(Error::StoreAccessFault(22)));
+        *ss.ssp = 72;
+        assert_eq!(ss.push(1, &mut memory), Err(Error::StoreAccessFault(64)));
+
+        // once defined, shadow-stack accesses must stay in the shadow-stack memory
+        cfi.regions.push(16..32);
+        cfi.ssp = 16;
+        assert_eq!(
+            cfi.shadow_stack(&Mmu::BARE).push(1, &mut memory),
+            Err(Error::StoreAccessFault(8))
+        );
+        cfi.ssp = 32;
+        cfi.shadow_stack(&Mmu::BARE).push(1, &mut memory).unwrap();
+
+        assert_eq!(
+            check_store(&cfi.regions, 12, 8),
+            Err(Error::StoreAccessFault(12))
+        );
+        assert_eq!(check_store(&cfi.regions, 8, 8), Ok(()));
+        assert_eq!(check_store(&cfi.regions, 32, 1), Ok(()));
+    }
+}
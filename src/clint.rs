@@ -0,0 +1,196 @@
+use crate::{
+    error::Error,
+    hart::Hart,
+    mem,
+    num::As,
+    trap::{self, MACHINE_SOFTWARE, MACHINE_TIMER},
+};
+
+/// Offsets of the `msip`, `mtimecmp` and `mtime` registers of hart 0, as laid out by the SiFive
+/// CLINT.
+pub const MSIP: usize = 0x0;
+pub const MTIMECMP: usize = 0x4000;
+pub const MTIME: usize = 0xbff8;
+/// Size in bytes of the CLINT region.
+pub const SIZE: usize = 0x1_0000;
+
+/// Core-local interruptor of a single hart, its registers live in the memory at `base` and are
+/// synchronized with the hart between instructions. `mtime` follows the `time` of the hart, which
+/// by default counts the retired instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clint {
+    base: usize,
+    /// Last `mtime` published in memory, a different value was written by the software.
+    mtime: u64,
+}
+
+impl Clint {
+    /// Maps the CLINT at `base`, `mtimecmp` is reset to its maximum so that the timer only
+    /// fires once programmed.
+    pub fn new(base: usize, memory: &mut [u8]) -> Result<Self, Error> {
+        if base.saturating_add(SIZE) > memory.len() {
+            return Err(Error::StoreAccessFault(base));
+        }
+        mem::write(&mem::U64::new(u64::MAX), memory, base + MTIMECMP)?;
+        mem::write(&mem::U32::new(0), memory, base + MSIP)?;
+        mem::write(&mem::U64::new(0), memory, base + MTIME)?;
+        Ok(Self { base, mtime: 0 })
+    }
+
+    /// Publishes `mtime`, after taking the software writes to it, and updates MSIP and MTIP in
+    /// `mip`. Only bit 0 of `msip` is implemented.
+    pub fn update<T>(&mut self, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+    where
+        T: Copy + As<u64>,
+        u64: As<T>,
+    {
+        let written = mem::read::<mem::U64>(memory, self.base + MTIME)?.as_u64();
+        if written != self.mtime {
+            hart.counters.set_time(written);
+        }
+        self.mtime = hart.counters.time();
+        mem::write(&mem::U64::new(self.mtime), memory, self.base + MTIME)?;
+
+        let msip = mem::read::<mem::U32>(memory, self.base + MSIP)?.as_u32() & 1;
+        mem::write(&mem::U32::new(msip), memory, self.base + MSIP)?;
+        let mtimecmp = mem::read::<mem::U64>(memory, self.base + MTIMECMP)?.as_u64();
+        trap::set_pending(hart, MACHINE_SOFTWARE, msip != 0);
+        trap::set_pending(hart, MACHINE_TIMER, self.mtime >= mtimecmp);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{csr, hart::Privilege, isa, registers::Register, trap::MIE};
+
+    const BASE: usize = 0x1_0000;
+
+    fn store<const N: usize>(memory: &mut [u8], addr: usize, bytes: [u8; N]) {
+        memory[addr..addr + N].copy_from_slice(&bytes);
+    }
+
+    #[test]
+    fn registers() {
+        let mut memory = vec![0u8; BASE + SIZE];
+        let mut hart = Hart::<u64>::new(0);
+        let mut clint = Clint::new(BASE, &mut memory).unwrap();
+        let mip = |hart: &mut Hart<u64>| csr::read(hart, csr::MIP).unwrap();
+
+        clint.update(&mut hart, &mut memory).unwrap();
+        assert_eq!(mip(&mut hart), 0);
+
+        // mtime shadows time, and the software can set it
+        hart.counters.retire(&[]);
+        clint.update(&mut hart, &mut memory).unwrap();
+        assert_eq!(memory[BASE + MTIME..][..8], 1u64.to_le_bytes());
+        store(&mut memory, BASE + MTIME, 500u64.to_le_bytes());
+        clint.update(&mut hart, &mut memory).unwrap();
+        hart.counters.retire(&[]);
+        assert_eq!(hart.counters.time(), 501);
+
+        // MTIP follows mtimecmp
+        store(&mut memory, BASE + MTIMECMP, 501u64.to_le_bytes());
+        clint.update(&mut hart, &mut memory).unwrap();
+        assert_eq!(mip(&mut hart), 1 << MACHINE_TIMER);
+        store(&mut memory, BASE + MTIMECMP, u64::MAX.to_le_bytes());
+        clint.update(&mut hart, &mut memory).unwrap();
+        assert_eq!(mip(&mut hart), 0);
+
+        // only bit 0 of msip is kept
+        store(&mut memory, BASE + MSIP, 0xffu32.to_le_bytes());
+        clint.update(&mut hart, &mut memory).unwrap();
+        assert_eq!(memory[BASE + MSIP..][..4], 1u32.to_le_bytes());
+        assert_eq!(mip(&mut hart), 1 << MACHINE_SOFTWARE);
+        // the CSR cannot clear it
+        csr::write(&mut hart, csr::MIP, 0).unwrap();
+        assert_eq!(mip(&mut hart), 1 << MACHINE_SOFTWARE);
+
+        assert_eq!(
+            Clint::new(0, &mut [0u8; 0x100]),
+            Err(Error::StoreAccessFault(0))
+        );
+    }
+
+    #[test]
+    fn timer_interrupt() {
+        let mut memory = vec![0u8; BASE + SIZE];
+        // addi x1, x1, 1 and j -4, the handler is at 0x100
+        store(&mut memory, 0, 0x0010_8093u32.to_le_bytes());
+        store(&mut memory, 4, 0xffdf_f06fu32.to_le_bytes());
+        let mut hart = Hart::<u32>::new(0);
+        let mut clint = Clint::new(BASE, &mut memory).unwrap();
+        csr::write(&mut hart, csr::MTVEC, 0x101).unwrap();
+        csr::write(&mut hart, csr::MIE, 1 << MACHINE_TIMER).unwrap();
+        store(&mut memory, BASE + MTIMECMP, 10u64.to_le_bytes());
+
+        // masked by mstatus.MIE in M-mode
+        for _ in 0..20 {
+            clint.update(&mut hart, &mut memory).unwrap();
+            isa::step(&mut hart, &mut memory);
+        }
+        assert_eq!(hart.regs.get(Register::X1), 10);
+        assert_eq!(hart.privilege, Privilege::Machine);
+
+        // taken before the next instruction once enabled, vectored to the cause
+        csr::write(&mut hart, csr::MSTATUS, MIE as u32).unwrap();
+        let pc = hart.pc;
+        clint.update(&mut hart, &mut memory).unwrap();
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x100 + 4 * MACHINE_TIMER as u32);
+        assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(pc));
+        assert_eq!(
+            csr::read(&mut hart, csr::MCAUSE),
+            Ok((1 << 31) | MACHINE_TIMER as u32)
+        );
+        assert_eq!(
+            csr::read(&mut hart, csr::MSTATUS),
+            Ok(trap::MPIE as u32 | trap::MPP as u32)
+        );
+        // the interrupted instruction did not retire
+        assert_eq!(hart.regs.get(Register::X1), 10);
+        assert_eq!(hart.counters.instret, 20);
+    }
+
+    #[test]
+    fn priority_and_delegation() {
+        let mut hart = Hart::<u64>::new(0x200);
+        csr::write(&mut hart, csr::MTVEC, 0x100).unwrap();
+        csr::write(&mut hart, csr::STVEC, 0x80).unwrap();
+        csr::write(&mut hart, csr::MIDELEG, 0x222).unwrap();
+        csr::write(&mut hart, csr::MIE, 0xaaa).unwrap();
+        // STIP and SSIP pending, SSIP goes first
+        csr::write(&mut hart, csr::MIP, 0x22).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::SIP), Ok(0x22));
+        assert_eq!(csr::read(&mut hart, csr::SIE), Ok(0x222));
+
+        // delegated interrupts are never taken in M-mode, nor in S-mode without SIE
+        assert!(!trap::interrupt(&mut hart));
+        hart.privilege = Privilege::Supervisor;
+        assert!(!trap::interrupt(&mut hart));
+        hart.privilege = Privilege::User;
+        assert!(trap::interrupt(&mut hart));
+        assert_eq!(hart.pc, 0x80);
+        assert_eq!(hart.privilege, Privilege::Supervisor);
+        assert_eq!(
+            csr::read(&mut hart, csr::SCAUSE),
+            Ok((1 << 63) | trap::SUPERVISOR_SOFTWARE)
+        );
+
+        // S-mode software clears SSIP through sip, STIP is read-only there
+        csr::write(&mut hart, csr::SIP, 0).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::SIP), Ok(0x20));
+
+        // machine interrupts preempt S-mode whatever mstatus.MIE
+        trap::set_pending(&mut hart, MACHINE_TIMER, true);
+        trap::set_pending(&mut hart, MACHINE_SOFTWARE, true);
+        assert!(trap::interrupt(&mut hart));
+        assert_eq!(hart.pc, 0x100);
+        assert_eq!(hart.privilege, Privilege::Machine);
+        assert_eq!(
+            csr::read(&mut hart, csr::MCAUSE),
+            Ok((1 << 63) | MACHINE_SOFTWARE)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Instant;
+
+/// Number of programmable counters, `hpmcounter3` to `hpmcounter31`.
+pub const HPM_COUNTERS: usize = 29;
+
+/// Events selectable through `mhpmevent3..31`, `0` means the counter does not count.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    Load = 1,
+    Store,
+    Branch,
+    TakenBranch,
+    Jump,
+    Atomic,
+    Float,
+}
+
+impl Event {
+    pub const fn from_bits(bits: u64) -> Option<Self> {
+        Some(match bits {
+            1 => Self::Load,
+            2 => Self::Store,
+            3 => Self::Branch,
+            4 => Self::TakenBranch,
+            5 => Self::Jump,
+            6 => Self::Atomic,
+            7 => Self::Float,
+            _ => return None,
+        })
+    }
+}
+
+/// Where the `time` CSR takes its value from.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeSource {
+    /// One tick per retired instruction, runs are fully reproducible.
+    Ticks,
+    /// Wall clock elapsed since `start`, in ticks of `frequency` Hz.
+    Host { start: Instant, frequency: u64 },
+}
+
+impl TimeSource {
+    #[inline]
+    pub fn host(frequency: u64) -> Self {
+        Self::Host {
+            start: Instant::now(),
+            frequency,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hpm {
+    pub event: Option<Event>,
+    pub value: u64,
+}
+
+/// Zicntr and Zihpm state of a hart, kept at 64 bits whatever the XLEN.
+#[derive(Debug, Clone)]
+pub struct Counters {
+    pub cycle: u64,
+    pub instret: u64,
+    pub hpm: [Hpm; HPM_COUNTERS],
+    /// `mcountinhibit`, bit `n` stops the counter at CSR offset `n`.
+    pub inhibit: u32,
+    /// Counters written by the executing instruction, laid out as `inhibit`: they skip its
+    /// retirement so that the next instruction reads the written value.
+    pub written: u32,
+    pub time_source: TimeSource,
+    ticks: u64,
+    /// Difference between `time` and its source, set by the writes to `mtime`.
+    offset: u64,
+}
+
+impl Counters {
+    #[inline]
+    pub fn new(time_source: TimeSource) -> Self {
+        Self {
+            cycle: 0,
+            instret: 0,
+            hpm: [Hpm::default(); HPM_COUNTERS],
+            inhibit: 0,
+            written: 0,
+            time_source,
+            ticks: 0,
+            offset: 0,
+        }
+    }
+
+    /// Current value of the `time` CSR, a shadow of `mtime`.
+    pub fn time(&self) -> u64 {
+        let source = match self.time_source {
+            TimeSource::Ticks => self.ticks,
+            TimeSource::Host { start, frequency } => {
+                (start.elapsed().as_nanos() * frequency as u128 / 1_000_000_000) as u64
+            }
+        };
+        source.wrapping_add(self.offset)
+    }
+
+    /// Sets `time`, which keeps counting from `time` on.
+    pub fn set_time(&mut self, time: u64) {
+        self.offset = time.wrapping_sub(self.time().wrapping_sub(self.offset));
+    }
+
+    /// Accounts for an instruction that retired raising `events`.
+    pub fn retire(&mut self, events: &[Event]) {
+        let stopped = self.inhibit | core::mem::take(&mut self.written);
+        self.ticks = self.ticks.wrapping_add(1);
+        if stopped & 0b001 == 0 {
+            self.cycle = self.cycle.wrapping_add(1);
+        }
+        if stopped & 0b100 == 0 {
+            self.instret = self.instret.wrapping_add(1);
+        }
+        for (i, hpm) in self.hpm.iter_mut().enumerate() {
+            if stopped & (1 << (i + 3)) == 0
+                && hpm.event.is_some_and(|event| events.contains(&event))
+            {
+                hpm.value = hpm.value.wrapping_add(1);
+            }
+        }
+    }
+}
+
+impl Default for Counters {
+    #[inline]
+    fn default() -> Self {
+        Self::new(TimeSource::Ticks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retire() {
+        let mut counters = Counters::default();
+        counters.hpm[0].event = Some(Event::Load);
+        counters.hpm[1].event = Some(Event::TakenBranch);
+        counters.retire(&[Event::Load]);
+        counters.retire(&[Event::Branch]);
+        counters.retire(&[Event::Branch, Event::TakenBranch]);
+        assert_eq!(counters.cycle, 3);
+        assert_eq!(counters.instret, 3);
+        assert_eq!(counters.time(), 3);
+        assert_eq!(counters.hpm[0].value, 1);
+        assert_eq!(counters.hpm[1].value, 1);
+        assert_eq!(counters.hpm[2].value, 0);
+
+        counters.inhibit = 0b1101;
+        counters.retire(&[Event::Load]);
+        assert_eq!(counters.cycle, 3);
+        assert_eq!(counters.instret, 3);
+        assert_eq!(counters.time(), 4);
+        assert_eq!(counters.hpm[0].value, 1);
+
+        counters.set_time(100);
+        counters.retire(&[]);
+        assert_eq!(counters.time(), 101);
+    }
+
+    #[test]
+    fn event_bits() {
+        assert_eq!(Event::from_bits(0), None);
+        assert_eq!(Event::from_bits(Event::Float as u64), Some(Event::Float));
+        assert_eq!(Event::from_bits(8), None);
+    }
+}
@@ -0,0 +1,876 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    counters::Event,
+    decode::U12,
+    error::Error,
+    hart::{Hart, Privilege},
+    instructions::{CBCFE, CBIE, CBZE},
+    mmu::Mode,
+    num::{As, Zero},
+    pmp::{MML, MMWP, RLB},
+    pointer_mask::PMM,
+    trap::{self, MPELP, MPIE, MPP, MPRV, SSTATUS_MASK, TSR, TVM, TW},
+};
+
+pub const FFLAGS: U12 = U12::new_truncate(0x001);
+pub const FRM: U12 = U12::new_truncate(0x002);
+pub const FCSR: U12 = U12::new_truncate(0x003);
+pub const VSTART: U12 = U12::new_truncate(0x008);
+pub const VXSAT: U12 = U12::new_truncate(0x009);
+pub const VXRM: U12 = U12::new_truncate(0x00a);
+pub const VCSR: U12 = U12::new_truncate(0x00f);
+pub const SSP: U12 = U12::new_truncate(0x011);
+pub const SSTATUS: U12 = U12::new_truncate(0x100);
+pub const SIE: U12 = U12::new_truncate(0x104);
+pub const STVEC: U12 = U12::new_truncate(0x105);
+pub const SCOUNTEREN: U12 = U12::new_truncate(0x106);
+pub const SENVCFG: U12 = U12::new_truncate(0x10a);
+pub const SSCRATCH: U12 = U12::new_truncate(0x140);
+pub const SEPC: U12 = U12::new_truncate(0x141);
+pub const SCAUSE: U12 = U12::new_truncate(0x142);
+pub const STVAL: U12 = U12::new_truncate(0x143);
+pub const SIP: U12 = U12::new_truncate(0x144);
+pub const SATP: U12 = U12::new_truncate(0x180);
+pub const MSTATUS: U12 = U12::new_truncate(0x300);
+pub const MEDELEG: U12 = U12::new_truncate(0x302);
+pub const MIDELEG: U12 = U12::new_truncate(0x303);
+pub const MIE: U12 = U12::new_truncate(0x304);
+pub const MTVEC: U12 = U12::new_truncate(0x305);
+pub const MCOUNTEREN: U12 = U12::new_truncate(0x306);
+pub const MENVCFG: U12 = U12::new_truncate(0x30a);
+pub const MSTATUSH: U12 = U12::new_truncate(0x310);
+pub const MCOUNTINHIBIT: U12 = U12::new_truncate(0x320);
+pub const MHPMEVENT3: U12 = U12::new_truncate(0x323);
+pub const MSCRATCH: U12 = U12::new_truncate(0x340);
+pub const MEPC: U12 = U12::new_truncate(0x341);
+pub const MCAUSE: U12 = U12::new_truncate(0x342);
+pub const MTVAL: U12 = U12::new_truncate(0x343);
+pub const MIP: U12 = U12::new_truncate(0x344);
+pub const PMPCFG0: U12 = U12::new_truncate(0x3a0);
+pub const PMPADDR0: U12 = U12::new_truncate(0x3b0);
+pub const MSECCFG: U12 = U12::new_truncate(0x747);
+pub const MCYCLE: U12 = U12::new_truncate(0xb00);
+pub const MCYCLEH: U12 = U12::new_truncate(0xb80);
+pub const CYCLE: U12 = U12::new_truncate(0xc00);
+pub const VL: U12 = U12::new_truncate(0xc20);
+pub const VTYPE: U12 = U12::new_truncate(0xc21);
+pub const VLENB: U12 = U12::new_truncate(0xc22);
+pub const CYCLEH: U12 = U12::new_truncate(0xc80);
+pub const MVENDORID: U12 = U12::new_truncate(0xf11);
+pub const MARCHID: U12 = U12::new_truncate(0xf12);
+pub const MIMPID: U12 = U12::new_truncate(0xf13);
+pub const MHARTID: U12 = U12::new_truncate(0xf14);
+
+/// Produces the value of a CSR whose state lives outside of the CSR file.
+pub type ReadHook<T> = fn(&mut Hart<T>, U12) -> T;
+
+/// Runs after a write with the new, already masked, value.
+pub type WriteHook<T> = fn(&mut Hart<T>, U12, T);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<T> {
+    pub value: T,
+    /// Bits changed by writes, the others keep their value.
+    pub mask: T,
+    pub read: Option<ReadHook<T>>,
+    pub write: Option<WriteHook<T>>,
+}
+
+/// Control and status registers of a hart, indexed by their 12-bit address.
+#[derive(Debug)]
+pub struct Csrs<T>(BTreeMap<U12, Entry<T>>);
+
+impl<T> Entry<T>
+where
+    T: Copy + Zero + core::ops::Not<Output = T>,
+{
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            mask: !T::zero(),
+            read: None,
+            write: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_mask(self, mask: T) -> Self {
+        Self { mask, ..self }
+    }
+
+    #[inline]
+    pub fn on_read(self, hook: ReadHook<T>) -> Self {
+        Self {
+            read: Some(hook),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn on_write(self, hook: WriteHook<T>) -> Self {
+        Self {
+            write: Some(hook),
+            ..self
+        }
+    }
+}
+
+impl<T> Csrs<T> {
+    #[inline]
+    pub const fn empty() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    #[inline]
+    pub fn insert(&mut self, addr: U12, entry: Entry<T>) {
+        self.0.insert(addr, entry);
+    }
+
+    #[inline]
+    pub fn get(&self, addr: U12) -> Option<&Entry<T>> {
+        self.0.get(&addr)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, addr: U12) -> Option<&mut Entry<T>> {
+        self.0.get_mut(&addr)
+    }
+
+    /// Checks that `addr` exists and is accessible from `privilege`, `addr[9:8]` encodes the
+    /// lowest privilege level allowed and `addr[11:10] == 0b11` marks read-only CSRs.
+    pub fn check(&self, addr: U12, privilege: Privilege, write: bool) -> Result<(), Error> {
+        let addr_bits = addr.as_u16();
+        if !self.0.contains_key(&addr)
+            || (privilege as u16) < ((addr_bits >> 8) & 0b11)
+            || (write && (addr_bits >> 10) == 0b11)
+        {
+            Err(Error::InvalidOpCode)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T> Csrs<T>
+where
+    T: Copy + Zero + core::ops::Not<Output = T> + As<u8> + As<u64>,
+    u8: As<T>,
+    u64: As<T>,
+{
+    /// CSR file with the floating-point, vector, control-flow integrity, pointer masking, trap,
+    /// address translation and protection, counter and machine information registers. The high
+    /// halves of `mstatus` and the counters, and the odd `pmpcfg`, exist only when `T` is 32 bits
+    /// wide.
+    pub fn new() -> Self {
+        let mut csrs = Self::empty();
+        let fp = Entry::new(T::zero())
+            .on_read(fcsr_read::<T>)
+            .on_write(fcsr_write::<T>);
+        csrs.insert(FFLAGS, fp.with_mask(0b11111_u8.r#as()));
+        csrs.insert(FRM, fp.with_mask(0b111_u8.r#as()));
+        csrs.insert(FCSR, fp.with_mask(0xff_u8.r#as()));
+        let vector = Entry::new(T::zero())
+            .on_read(vector_read::<T>)
+            .on_write(vector_write::<T>);
+        csrs.insert(VSTART, vector);
+        csrs.insert(VXSAT, vector.with_mask(0b1_u8.r#as()));
+        csrs.insert(VXRM, vector.with_mask(0b11_u8.r#as()));
+        csrs.insert(VCSR, vector.with_mask(0b111_u8.r#as()));
+        for addr in [VL, VTYPE, VLENB] {
+            csrs.insert(addr, vector.with_mask(T::zero()));
+        }
+        csrs.insert(
+            SSP,
+            Entry::new(T::zero())
+                .on_read(ssp_read::<T>)
+                .on_write(ssp_write::<T>),
+        );
+        // only the landing pad, shadow stack, cache-block and pointer masking controls are
+        // implemented
+        let envcfg = Entry::new(T::zero())
+            .with_mask((PMM | CBZE | CBCFE | CBIE | 0b1100).r#as())
+            .on_write(envcfg_write::<T>);
+        csrs.insert(MENVCFG, envcfg);
+        csrs.insert(SENVCFG, envcfg);
+        csrs.insert(
+            MSECCFG,
+            Entry::new(T::zero())
+                .with_mask((PMM | 0x400 | MML | MMWP | RLB).r#as())
+                .on_write(mseccfg_write::<T>),
+        );
+        csrs.insert(
+            MSTATUS,
+            Entry::new(T::zero())
+                .with_mask(
+                    (SSTATUS_MASK | trap::MIE | MPIE | MPP | MPRV | TVM | TW | TSR | MPELP).r#as(),
+                )
+                .on_write(mstatus_write::<T>),
+        );
+        csrs.insert(
+            SSTATUS,
+            Entry::new(T::zero())
+                .with_mask(SSTATUS_MASK.r#as())
+                .on_read(sstatus_read::<T>)
+                .on_write(sstatus_write::<T>),
+        );
+        // ECALL from M-mode cannot be delegated, nor the reserved causes
+        csrs.insert(
+            MEDELEG,
+            Entry::new(T::zero()).with_mask(0x4_b3ff_u64.r#as()),
+        );
+        // only the supervisor interrupts can be delegated, and only their pending bits are
+        // writable: the machine ones follow the interrupt controllers
+        csrs.insert(MIDELEG, Entry::new(T::zero()).with_mask(0x222_u64.r#as()));
+        csrs.insert(MIP, Entry::new(T::zero()).with_mask(0x222_u64.r#as()));
+        csrs.insert(MIE, Entry::new(T::zero()).with_mask(0xaaa_u64.r#as()));
+        let interrupts = Entry::new(T::zero())
+            .on_read(interrupts_read::<T>)
+            .on_write(interrupts_write::<T>);
+        csrs.insert(SIP, interrupts);
+        csrs.insert(SIE, interrupts);
+        // vectored is the only mode besides direct, xepc is aligned to the compressed encodings
+        for (tvec, epc, cause, tval) in [(MTVEC, MEPC, MCAUSE, MTVAL), (STVEC, SEPC, SCAUSE, STVAL)]
+        {
+            csrs.insert(tvec, Entry::new(T::zero()).with_mask(!2_u8.r#as()));
+            csrs.insert(epc, Entry::new(T::zero()).with_mask(!1_u8.r#as()));
+            csrs.insert(cause, Entry::new(T::zero()));
+            csrs.insert(tval, Entry::new(T::zero()));
+        }
+        csrs.insert(SSCRATCH, Entry::new(T::zero()));
+        csrs.insert(
+            SATP,
+            Entry::new(T::zero())
+                .on_read(satp_read::<T>)
+                .on_write(satp_write::<T>),
+        );
+        csrs.insert(MSCRATCH, Entry::new(T::zero()));
+        for addr in [MVENDORID, MARCHID, MIMPID, MHARTID] {
+            csrs.insert(addr, Entry::new(T::zero()).with_mask(T::zero()));
+        }
+
+        let rv32 = core::mem::size_of::<T>() == 4;
+        if rv32 {
+            csrs.insert(
+                MSTATUSH,
+                Entry::new(T::zero()).with_mask((MPELP >> 32).r#as()),
+            );
+        }
+        let pmpcfg = Entry::new(T::zero())
+            .on_read(pmpcfg_read::<T>)
+            .on_write(pmpcfg_write::<T>);
+        for offset in (0..16).filter(|offset| rv32 || offset % 2 == 0) {
+            csrs.insert(at(PMPCFG0, offset), pmpcfg);
+        }
+        // physical addresses are 34 bits wide on RV32 and 56 bits wide otherwise
+        let pmpaddr_mask = if rv32 { u32::MAX as u64 } else { (1 << 54) - 1 };
+        for offset in 0..64 {
+            csrs.insert(
+                at(PMPADDR0, offset),
+                Entry::new(T::zero())
+                    .with_mask(pmpaddr_mask.r#as())
+                    .on_read(pmpaddr_read::<T>)
+                    .on_write(pmpaddr_write::<T>),
+            );
+        }
+
+        let counter = Entry::new(T::zero())
+            .on_read(counter_read::<T>)
+            .on_write(counter_write::<T>);
+        for offset in 0..32 {
+            // There is no mtime CSR, the machine timer is memory mapped.
+            if offset != 1 {
+                csrs.insert(at(MCYCLE, offset), counter);
+                if rv32 {
+                    csrs.insert(at(MCYCLEH, offset), counter);
+                }
+            }
+            csrs.insert(at(CYCLE, offset), counter);
+            if rv32 {
+                csrs.insert(at(CYCLEH, offset), counter);
+            }
+        }
+        for offset in 3..32 {
+            csrs.insert(
+                at(MHPMEVENT3, offset - 3),
+                Entry::new(T::zero())
+                    .on_read(hpmevent_read::<T>)
+                    .on_write(hpmevent_write::<T>),
+            );
+        }
+        // every counter exists, so every enable bit is writable
+        csrs.insert(
+            MCOUNTEREN,
+            Entry::new(T::zero()).with_mask(0xffff_ffff_u64.r#as()),
+        );
+        csrs.insert(
+            SCOUNTEREN,
+            Entry::new(T::zero()).with_mask(0xffff_ffff_u64.r#as()),
+        );
+        csrs.insert(
+            MCOUNTINHIBIT,
+            Entry::new(T::zero())
+                .with_mask(0xffff_fffd_u64.r#as())
+                .on_write(mcountinhibit_write::<T>),
+        );
+        csrs
+    }
+}
+
+impl<T> Default for Csrs<T>
+where
+    T: Copy + Zero + core::ops::Not<Output = T> + As<u8> + As<u64>,
+    u8: As<T>,
+    u64: As<T>,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads `addr` running its read hook, access must have been validated with [`Csrs::check`].
+pub fn read<T: Copy>(hart: &mut Hart<T>, addr: U12) -> Result<T, Error> {
+    let entry = *hart.csrs.get(addr).ok_or(Error::InvalidOpCode)?;
+    Ok(match entry.read {
+        Some(hook) => hook(hart, addr),
+        None => entry.value,
+    })
+}
+
+/// Writes the bits of `value` selected by the CSR mask and runs its write hook, access must have
+/// been validated with [`Csrs::check`].
+pub fn write<T>(hart: &mut Hart<T>, addr: U12, value: T) -> Result<(), Error>
+where
+    T: Copy
+        + core::ops::BitAnd<Output = T>
+        + core::ops::BitOr<Output = T>
+        + core::ops::Not<Output = T>,
+{
+    let entry = hart.csrs.get_mut(addr).ok_or(Error::InvalidOpCode)?;
+    let value = (entry.value & !entry.mask) | (value & entry.mask);
+    entry.value = value;
+    if let Some(hook) = entry.write {
+        hook(hart, addr, value);
+    }
+    Ok(())
+}
+
+/// Checks the access conditions depending on the hart state: below M-mode `ssp` is only
+/// accessible when shadow stacks are enabled, `satp` is illegal in S-mode when TVM is set, and the
+/// counters need their `mcounteren` bit in S-mode, and their `scounteren` bit too in U-mode.
+pub fn check_state<T: Copy + As<u64>>(hart: &Hart<T>, addr: U12) -> Result<(), Error> {
+    let mstatus: u64 = hart.csrs.get(MSTATUS).map_or(0, |entry| entry.value.r#as());
+    match addr {
+        SSP if hart.privilege != Privilege::Machine && !crate::cfi::shadow_stacks(hart) => {
+            Err(Error::InvalidOpCode)
+        }
+        SATP if hart.privilege == Privilege::Supervisor && mstatus & TVM != 0 => {
+            Err(Error::InvalidOpCode)
+        }
+        _ if hart.privilege != Privilege::Machine && is_counter(addr) => {
+            let bit = 1 << counter_index(addr).0;
+            let enabled = |addr| hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as()) & bit;
+            if enabled(MCOUNTEREN) != 0
+                && (hart.privilege == Privilege::Supervisor || enabled(SCOUNTEREN) != 0)
+            {
+                Ok(())
+            } else {
+                Err(Error::InvalidOpCode)
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+fn fcsr_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u8: As<T>,
+{
+    match addr {
+        FFLAGS => hart.fcsr.fflags.bits(),
+        FRM => hart.fcsr.frm,
+        _ => hart.fcsr.bits(),
+    }
+    .r#as()
+}
+
+fn fcsr_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u8>,
+{
+    let value: u8 = value.r#as();
+    match addr {
+        FFLAGS => hart.fcsr.set_bits((hart.fcsr.frm << 5) | value),
+        FRM => hart.fcsr.frm = value,
+        _ => hart.fcsr.set_bits(value),
+    }
+}
+
+fn vector_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u64: As<T>,
+{
+    let vector = &hart.vector;
+    match addr {
+        VSTART => vector.vstart as u64,
+        VXSAT => vector.vxsat as u64,
+        VXRM => vector.vxrm as u64,
+        VCSR => ((vector.vxrm << 1) | vector.vxsat as u8) as u64,
+        VL => vector.vl as u64,
+        VTYPE => vector.vtype.bits((core::mem::size_of::<T>() * 8) as u32),
+        _ => vector.regs.vlenb() as u64,
+    }
+    .r#as()
+}
+
+fn vector_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+{
+    let value: u64 = value.r#as();
+    let vector = &mut hart.vector;
+    match addr {
+        // vstart only holds element indices of a single register group
+        VSTART => vector.vstart = value as usize & (vector.vlen() - 1),
+        VXSAT => vector.vxsat = value & 1 != 0,
+        VXRM => vector.vxrm = value as u8,
+        VCSR => {
+            vector.vxrm = (value >> 1) as u8;
+            vector.vxsat = value & 1 != 0;
+        }
+        _ => {}
+    }
+}
+
+fn ssp_read<T: Copy>(hart: &mut Hart<T>, _: U12) -> T {
+    hart.cfi.ssp
+}
+
+fn ssp_write<T>(hart: &mut Hart<T>, _: U12, value: T) {
+    hart.cfi.ssp = value;
+}
+
+/// PMM is WARL, the reserved encoding `0b01` is legalized to disabled.
+fn pmm_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+    u64: As<T>,
+{
+    let value: u64 = value.r#as();
+    if value & PMM == 0b01 << 32 {
+        if let Some(entry) = hart.csrs.get_mut(addr) {
+            entry.value = (value & !PMM).r#as();
+        }
+    }
+}
+
+/// CBIE is WARL too, its reserved encoding `0b10` is legalized to disabled.
+fn envcfg_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    pmm_write(hart, addr, value);
+    if let Some(entry) = hart.csrs.get_mut(addr) {
+        let value: u64 = entry.value.r#as();
+        if value & CBIE == 0b10 << 4 {
+            entry.value = (value & !CBIE).r#as();
+        }
+    }
+}
+
+/// `sip` and `sie` are the views of `mip` and `mie` restricted to the interrupts in `mideleg`.
+fn interrupts_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    let bits = |addr| -> u64 {
+        hart.csrs
+            .get(addr)
+            .map_or(T::zero(), |entry| entry.value)
+            .r#as()
+    };
+    let view = if addr == SIP { MIP } else { MIE };
+    (bits(view) & bits(MIDELEG)).r#as()
+}
+
+/// Only SSIP is writable through `sip`, the other pending bits are set by the interrupt
+/// controllers.
+fn interrupts_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    let mideleg: u64 = hart
+        .csrs
+        .get(MIDELEG)
+        .map_or(T::zero(), |entry| entry.value)
+        .r#as();
+    let (view, writable) = if addr == SIP {
+        (MIP, 1 << trap::SUPERVISOR_SOFTWARE)
+    } else {
+        (MIE, 0x222)
+    };
+    if let Some(entry) = hart.csrs.get_mut(view) {
+        let writable = writable & mideleg;
+        let bits: u64 = entry.value.r#as();
+        let value: u64 = value.r#as();
+        entry.value = ((bits & !writable) | (value & writable)).r#as();
+    }
+}
+
+/// The Smepmp bits live in the PMP state, which legalizes them.
+fn mseccfg_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    pmm_write(hart, addr, value);
+    if let Some(entry) = hart.csrs.get_mut(addr) {
+        let value: u64 = entry.value.r#as();
+        hart.pmp.set_mseccfg(value);
+        entry.value = ((value & !(MML | MMWP | RLB)) | hart.pmp.mseccfg()).r#as();
+    }
+}
+
+/// `pmpcfg` holds the configuration bytes of 4 entries per 32 bits.
+fn pmpcfg_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u64: As<T>,
+{
+    let first = (addr.as_u16() - PMPCFG0.as_u16()) as usize * 4;
+    (0..core::mem::size_of::<T>().min(8))
+        .fold(0, |value, i| {
+            value | (hart.pmp.cfg(first + i) as u64) << (8 * i)
+        })
+        .r#as()
+}
+
+fn pmpcfg_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+{
+    let first = (addr.as_u16() - PMPCFG0.as_u16()) as usize * 4;
+    let value: u64 = value.r#as();
+    for i in 0..core::mem::size_of::<T>().min(8) {
+        hart.pmp.set_cfg(first + i, (value >> (8 * i)) as u8);
+    }
+}
+
+fn pmpaddr_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u64: As<T>,
+{
+    let i = (addr.as_u16() - PMPADDR0.as_u16()) as usize;
+    hart.pmp.addr(i).r#as()
+}
+
+fn pmpaddr_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+{
+    let i = (addr.as_u16() - PMPADDR0.as_u16()) as usize;
+    hart.pmp.set_addr(i, value.r#as());
+}
+
+/// MPP is WARL, the reserved encoding `0b10` is legalized to U-mode.
+fn mstatus_write<T>(hart: &mut Hart<T>, _: U12, value: T)
+where
+    T: As<u64>,
+    u64: As<T>,
+{
+    let value: u64 = value.r#as();
+    if value & MPP == 0b10 << 11 {
+        if let Some(entry) = hart.csrs.get_mut(MSTATUS) {
+            entry.value = (value & !MPP).r#as();
+        }
+    }
+}
+
+fn sstatus_read<T>(hart: &mut Hart<T>, _: U12) -> T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    let mstatus: u64 = hart
+        .csrs
+        .get(MSTATUS)
+        .map_or(T::zero(), |entry| entry.value)
+        .r#as();
+    (mstatus & SSTATUS_MASK).r#as()
+}
+
+/// `sstatus` is a view of `mstatus`, the write goes to the S-mode fields of the latter.
+fn sstatus_write<T>(hart: &mut Hart<T>, _: U12, value: T)
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    if let Some(entry) = hart.csrs.get_mut(MSTATUS) {
+        let mstatus: u64 = entry.value.r#as();
+        let value: u64 = value.r#as();
+        entry.value = ((mstatus & !SSTATUS_MASK) | (value & SSTATUS_MASK)).r#as();
+    }
+}
+
+fn satp_read<T: Copy>(hart: &mut Hart<T>, _: U12) -> T {
+    hart.satp
+}
+
+/// Writes selecting an unsupported translation mode have no effect.
+fn satp_write<T>(hart: &mut Hart<T>, _: U12, value: T)
+where
+    T: Copy + As<u64>,
+{
+    let xlen = (core::mem::size_of::<T>() * 8) as u32;
+    if Mode::from_satp(value.r#as(), xlen).is_some() {
+        hart.satp = value;
+    }
+}
+
+#[inline(always)]
+const fn at(base: U12, offset: u16) -> U12 {
+    U12::new_truncate(base.as_u16() + offset)
+}
+
+/// Whether `addr` is one of the unprivileged counters, or the high half of one.
+#[inline(always)]
+const fn is_counter(addr: U12) -> bool {
+    let addr = addr.as_u16();
+    (addr >= CYCLE.as_u16() && addr < CYCLE.as_u16() + 32)
+        || (addr >= CYCLEH.as_u16() && addr < CYCLEH.as_u16() + 32)
+}
+
+/// Offset of a counter CSR in its bank and whether it is the high half of an RV32 counter.
+#[inline(always)]
+fn counter_index(addr: U12) -> (usize, bool) {
+    let bits = addr.as_u16();
+    ((bits & 0x1f) as usize, bits & 0x80 != 0)
+}
+
+fn counter_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u64: As<T>,
+{
+    let counters = &hart.counters;
+    let value = match counter_index(addr) {
+        (0, _) => counters.cycle,
+        (1, _) => counters.time(),
+        (2, _) => counters.instret,
+        (n, _) => counters.hpm[n - 3].value,
+    };
+    if counter_index(addr).1 {
+        value >> 32
+    } else {
+        value
+    }
+    .r#as()
+}
+
+fn counter_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+{
+    let value: u64 = value.r#as();
+    let (offset, high) = counter_index(addr);
+    hart.counters.written |= 1 << offset;
+    let counter = match offset {
+        0 => &mut hart.counters.cycle,
+        2 => &mut hart.counters.instret,
+        n => &mut hart.counters.hpm[n - 3].value,
+    };
+    *counter = if high {
+        (*counter & 0xffff_ffff) | (value << 32)
+    } else if core::mem::size_of::<T>() == 4 {
+        (*counter & !0xffff_ffff) | value
+    } else {
+        value
+    };
+}
+
+fn hpmevent_read<T>(hart: &mut Hart<T>, addr: U12) -> T
+where
+    u64: As<T>,
+{
+    let (offset, _) = counter_index(addr);
+    hart.counters.hpm[offset - 3]
+        .event
+        .map_or(0, |event| event as u64)
+        .r#as()
+}
+
+fn hpmevent_write<T>(hart: &mut Hart<T>, addr: U12, value: T)
+where
+    T: As<u64>,
+{
+    let (offset, _) = counter_index(addr);
+    hart.counters.hpm[offset - 3].event = Event::from_bits(value.r#as());
+}
+
+fn mcountinhibit_write<T>(hart: &mut Hart<T>, _: U12, value: T)
+where
+    T: As<u64>,
+{
+    hart.counters.inhibit = As::<u64>::r#as(value) as u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fcsr_views() {
+        let mut hart = Hart::<u32>::default();
+        write(&mut hart, FCSR, 0x1ff).unwrap();
+        assert_eq!(read(&mut hart, FCSR), Ok(0xff));
+        assert_eq!(read(&mut hart, FFLAGS), Ok(0x1f));
+        assert_eq!(read(&mut hart, FRM), Ok(0b111));
+
+        write(&mut hart, FRM, 0b001).unwrap();
+        write(&mut hart, FFLAGS, 0b00100).unwrap();
+        assert_eq!(read(&mut hart, FCSR), Ok(0b001_00100));
+        assert_eq!(hart.fcsr.frm, 0b001);
+    }
+
+    #[test]
+    fn vector_views() {
+        let mut hart = Hart::<u32>::default();
+        assert_eq!(read(&mut hart, VTYPE), Ok(0x8000_0000));
+        assert_eq!(read(&mut hart, VLENB), Ok(16));
+        assert_eq!(
+            hart.csrs.check(VL, Privilege::User, true),
+            Err(Error::InvalidOpCode)
+        );
+
+        write(&mut hart, VCSR, 0b1101).unwrap();
+        assert_eq!(read(&mut hart, VXRM), Ok(0b10));
+        assert_eq!(read(&mut hart, VXSAT), Ok(1));
+        write(&mut hart, VXSAT, 0).unwrap();
+        assert_eq!(read(&mut hart, VCSR), Ok(0b100));
+        write(&mut hart, VSTART, 0x1_0003).unwrap();
+        assert_eq!(hart.vector.vstart, 3);
+
+        let mut hart = Hart::<u64>::default().with_vector(256, 64);
+        hart.vector.vtype = crate::vector::VType::new(0b1101_0001, 64);
+        hart.vector.vl = 9;
+        assert_eq!(read(&mut hart, VTYPE), Ok(0b1101_0001));
+        assert_eq!(read(&mut hart, VL), Ok(9));
+        assert_eq!(read(&mut hart, VLENB), Ok(32));
+    }
+
+    #[test]
+    fn access_checks() {
+        let mut hart = Hart::<u64>::default();
+        assert_eq!(hart.csrs.check(MSCRATCH, Privilege::Machine, true), Ok(()));
+        assert_eq!(
+            hart.csrs.check(MSCRATCH, Privilege::User, false),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(hart.csrs.check(MHARTID, Privilege::Machine, false), Ok(()));
+        assert_eq!(
+            hart.csrs.check(MHARTID, Privilege::Machine, true),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(hart.csrs.check(FCSR, Privilege::User, true), Ok(()));
+        assert_eq!(
+            hart.csrs
+                .check(U12::new_truncate(0x7ff), Privilege::Machine, false),
+            Err(Error::InvalidOpCode)
+        );
+
+        hart.csrs
+            .insert(MSCRATCH, Entry::new(0xffff_0000).with_mask(0x0000_ffff));
+        write(&mut hart, MSCRATCH, 0x1234_5678).unwrap();
+        assert_eq!(read(&mut hart, MSCRATCH), Ok(0xffff_5678));
+    }
+
+    #[test]
+    fn counters() {
+        let mut hart = Hart::<u32>::default();
+        hart.counters.cycle = 0x1_0000_0002;
+        assert_eq!(read(&mut hart, CYCLE), Ok(2));
+        assert_eq!(read(&mut hart, CYCLEH), Ok(1));
+        write(&mut hart, MCYCLE, 0xffff_ffff).unwrap();
+        write(&mut hart, MCYCLEH, 7).unwrap();
+        assert_eq!(hart.counters.cycle, 0x7_ffff_ffff);
+        assert_eq!(
+            hart.csrs.check(at(CYCLE, 2), Privilege::User, false),
+            Ok(())
+        );
+        assert_eq!(
+            hart.csrs.check(at(CYCLE, 2), Privilege::User, true),
+            Err(Error::InvalidOpCode)
+        );
+
+        // mhpmevent4 selects loads, unknown events are not retained
+        write(&mut hart, at(MHPMEVENT3, 1), Event::Load as u32).unwrap();
+        write(&mut hart, at(MHPMEVENT3, 2), 0xff).unwrap();
+        assert_eq!(hart.counters.hpm[1].event, Some(Event::Load));
+        assert_eq!(read(&mut hart, at(MHPMEVENT3, 2)), Ok(0));
+        hart.counters.retire(&[Event::Load]);
+        assert_eq!(read(&mut hart, at(CYCLE, 4)), Ok(1));
+        assert_eq!(read(&mut hart, at(MCYCLE, 4)), Ok(1));
+
+        // mcountinhibit.TM is read-only zero
+        write(&mut hart, MCOUNTINHIBIT, 0b111).unwrap();
+        assert_eq!(read(&mut hart, MCOUNTINHIBIT), Ok(0b101));
+        assert_eq!(hart.counters.inhibit, 0b101);
+
+        let mut hart = Hart::<u64>::default();
+        assert!(hart.csrs.get(CYCLEH).is_none());
+        write(&mut hart, at(MCYCLE, 2), 0x1_0000_0000).unwrap();
+        assert_eq!(read(&mut hart, at(CYCLE, 2)), Ok(0x1_0000_0000));
+        hart.counters.retire(&[]);
+        assert_eq!(read(&mut hart, at(CYCLE, 1)), Ok(1));
+    }
+
+    #[test]
+    fn counter_enables() {
+        let mut hart = Hart::<u32>::new(0);
+        hart.privilege = Privilege::Supervisor;
+        assert_eq!(check_state(&hart, CYCLE), Err(Error::InvalidOpCode));
+        // mcounteren.IR enables instreth in S-mode
+        write(&mut hart, MCOUNTEREN, 0b100).unwrap();
+        assert_eq!(check_state(&hart, at(CYCLEH, 2)), Ok(()));
+        assert_eq!(check_state(&hart, at(CYCLE, 1)), Err(Error::InvalidOpCode));
+        // scounteren has no effect on S-mode and is writable without any counter enabled
+        write(&mut hart, SCOUNTEREN, u32::MAX).unwrap();
+        assert_eq!(read(&mut hart, SCOUNTEREN), Ok(u32::MAX));
+        assert_eq!(check_state(&hart, CYCLE), Err(Error::InvalidOpCode));
+        // M-mode always has access
+        hart.privilege = Privilege::Machine;
+        assert_eq!(check_state(&hart, CYCLE), Ok(()));
+    }
+
+    #[test]
+    fn user_counter_enables() {
+        let mut hart = Hart::<u64>::new(0);
+        hart.privilege = Privilege::User;
+        // U-mode needs the bit in both registers
+        write(&mut hart, MCOUNTEREN, 0b1000_0011).unwrap();
+        write(&mut hart, SCOUNTEREN, 0b1000_0110).unwrap();
+        assert_eq!(check_state(&hart, at(CYCLE, 1)), Ok(()));
+        assert_eq!(check_state(&hart, at(CYCLE, 7)), Ok(()));
+        assert_eq!(check_state(&hart, CYCLE), Err(Error::InvalidOpCode));
+        assert_eq!(check_state(&hart, at(CYCLE, 2)), Err(Error::InvalidOpCode));
+        // the other CSRs of the same range are not counters
+        assert_eq!(check_state(&hart, VL), Ok(()));
+    }
+
+    #[test]
+    fn counter_write_retires() {
+        use crate::{isa::Isa, registers::Register};
+
+        let mut hart = Hart::<u64>::default();
+        *hart.regs.get_mut(Register::X1) = 100;
+        // csrw minstret, x1 and csrw mcycle, x1, the write is not followed by an increment
+        u64::execute(0xb0209073, &mut hart, &mut []);
+        assert_eq!(read(&mut hart, at(MCYCLE, 2)), Ok(100));
+        u64::execute(0xb0009073, &mut hart, &mut []);
+        assert_eq!(read(&mut hart, MCYCLE), Ok(100));
+        assert_eq!(read(&mut hart, at(MCYCLE, 2)), Ok(101));
+    }
+}
@@ -52,6 +52,42 @@ pub struct Fence {
     pub rd: U5,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amo {
+    pub funct5: U5,
+    pub aq: bool,
+    pub rl: bool,
+    pub rs2: U5,
+    pub rs1: U5,
+    pub funct3: U3,
+    pub rd: U5,
+}
+
+/// OP-V arithmetic and configuration format, `vm` clear means masked by `v0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct V {
+    pub funct6: U6,
+    pub vm: bool,
+    pub vs2: U5,
+    pub vs1: U5,
+    pub funct3: U3,
+    pub vd: U5,
+}
+
+/// Vector load and store format, `rs2` also holds the lumop and sumop fields of the unit-stride
+/// accesses and `width` selects the element width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VMem {
+    pub nf: U3,
+    pub mew: bool,
+    pub mop: U2,
+    pub vm: bool,
+    pub rs2: U5,
+    pub rs1: U5,
+    pub width: U3,
+    pub vd: U5,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct S {
     pub imm: U12,
@@ -96,6 +132,16 @@ impl R {
     pub const fn id(&self) -> U10 {
         unsafe { U10::new_unchecked((self.funct7.as_u16() << 3) | self.funct3.as_u16()) }
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        (self.funct7.as_u32() << 25)
+            | (self.rs2.as_u32() << 20)
+            | (self.rs1.as_u32() << 15)
+            | (self.funct3.as_u32() << 12)
+            | (self.rd.as_u32() << 7)
+            | opcode as u32
+    }
 }
 
 impl From<u32> for R {
@@ -141,6 +187,15 @@ impl I {
     pub const fn id(&self) -> U3 {
         self.funct3
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        (self.imm.as_u32() << 20)
+            | (self.rs1.as_u32() << 15)
+            | (self.funct3.as_u32() << 12)
+            | (self.rd.as_u32() << 7)
+            | opcode as u32
+    }
 }
 
 impl From<u32> for I {
@@ -161,7 +216,7 @@ impl Shift {
         }: I,
     ) -> Self {
         Self {
-            prefix: unsafe { U7::new_unchecked((imm.as_u16() >> 7) as u8) },
+            prefix: unsafe { U7::new_unchecked((imm.as_u16() >> 5) as u8) },
             shamt: U5::new_truncate(imm.as_u16() as u8),
             rs1,
             funct3,
@@ -174,9 +229,16 @@ impl Shift {
         Self::from_i(I::from_u32(value))
     }
 
+    /// Splits the immediate for `bits`-wide shift amounts (5, 6 or 7), the low bits of `prefix`
+    /// become the high bits of the shift amount and are cleared from the returned id.
     #[inline(always)]
-    pub const fn id(&self) -> U10 {
-        unsafe { U10::new_unchecked((self.prefix.as_u16() << 3) | self.funct3.as_u16()) }
+    pub const fn split(&self, bits: u32) -> (U10, U7) {
+        let high = (1u8 << (bits - U5::BITS)) - 1;
+        let prefix = self.prefix.as_u16() & !(high as u16);
+        (
+            unsafe { U10::new_unchecked((prefix << 3) | self.funct3.as_u16()) },
+            U7::new_truncate(((self.prefix.as_u8() & high) << U5::BITS) | self.shamt.as_u8()),
+        )
     }
 }
 
@@ -201,11 +263,16 @@ impl Fence {
             fm: unsafe { U4::new_unchecked((value >> 28) as u8) },
             pred: U4::new_truncate((value >> 24) as u8),
             succ: U4::new_truncate((value >> 20) as u8),
-            rs1: U5::new_truncate((value >> 16) as u8),
+            rs1: U5::new_truncate((value >> 15) as u8),
             funct3: U3::new_truncate((value >> 12) as u8),
             rd: U5::new_truncate((value >> 7) as u8),
         }
     }
+
+    #[inline(always)]
+    pub const fn id(&self) -> U3 {
+        self.funct3
+    }
 }
 
 impl From<u32> for Fence {
@@ -215,6 +282,77 @@ impl From<u32> for Fence {
     }
 }
 
+impl Amo {
+    #[inline(always)]
+    pub const fn from_u32(value: u32) -> Self {
+        Self {
+            funct5: U5::new_truncate((value >> 27) as u8),
+            aq: value & (1 << 26) != 0,
+            rl: value & (1 << 25) != 0,
+            rs2: U5::new_truncate((value >> 20) as u8),
+            rs1: U5::new_truncate((value >> 15) as u8),
+            funct3: U3::new_truncate((value >> 12) as u8),
+            rd: U5::new_truncate((value >> 7) as u8),
+        }
+    }
+
+    #[inline(always)]
+    pub const fn id(&self) -> U10 {
+        unsafe { U10::new_unchecked((self.funct5.as_u16() << 3) | self.funct3.as_u16()) }
+    }
+}
+
+impl From<u32> for Amo {
+    #[inline(always)]
+    fn from(value: u32) -> Self {
+        Self::from_u32(value)
+    }
+}
+
+impl V {
+    #[inline(always)]
+    pub const fn from_u32(value: u32) -> Self {
+        Self {
+            funct6: U6::new_truncate((value >> 26) as u8),
+            vm: value & (1 << 25) != 0,
+            vs2: U5::new_truncate((value >> 20) as u8),
+            vs1: U5::new_truncate((value >> 15) as u8),
+            funct3: U3::new_truncate((value >> 12) as u8),
+            vd: U5::new_truncate((value >> 7) as u8),
+        }
+    }
+}
+
+impl From<u32> for V {
+    #[inline(always)]
+    fn from(value: u32) -> Self {
+        Self::from_u32(value)
+    }
+}
+
+impl VMem {
+    #[inline(always)]
+    pub const fn from_u32(value: u32) -> Self {
+        Self {
+            nf: U3::new_truncate((value >> 29) as u8),
+            mew: value & (1 << 28) != 0,
+            mop: U2::new_truncate((value >> 26) as u8),
+            vm: value & (1 << 25) != 0,
+            rs2: U5::new_truncate((value >> 20) as u8),
+            rs1: U5::new_truncate((value >> 15) as u8),
+            width: U3::new_truncate((value >> 12) as u8),
+            vd: U5::new_truncate((value >> 7) as u8),
+        }
+    }
+}
+
+impl From<u32> for VMem {
+    #[inline(always)]
+    fn from(value: u32) -> Self {
+        Self::from_u32(value)
+    }
+}
+
 impl S {
     #[inline(always)]
     pub const fn from_u32(value: u32) -> Self {
@@ -234,6 +372,17 @@ impl S {
     pub const fn id(&self) -> U3 {
         self.funct3
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        let imm = self.imm.as_u32();
+        ((imm >> 5) << 25)
+            | (self.rs2.as_u32() << 20)
+            | (self.rs1.as_u32() << 15)
+            | (self.funct3.as_u32() << 12)
+            | ((imm & B5_MASK) << 7)
+            | opcode as u32
+    }
 }
 
 impl From<u32> for S {
@@ -271,6 +420,19 @@ impl B {
     pub const fn id(&self) -> U3 {
         self.funct3
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        let imm = self.imm.as_u32();
+        (((imm >> 12) & 1) << 31)
+            | (((imm >> 5) & B6_MASK) << 25)
+            | (self.rs2.as_u32() << 20)
+            | (self.rs1.as_u32() << 15)
+            | (self.funct3.as_u32() << 12)
+            | (((imm >> 1) & B4_MASK) << 8)
+            | (((imm >> 11) & 1) << 7)
+            | opcode as u32
+    }
 }
 
 impl From<u32> for B {
@@ -288,6 +450,11 @@ impl U {
             rd: U5::new_truncate((value >> 7) as u8),
         }
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        (self.imm & !B12_MASK) | (self.rd.as_u32() << 7) | opcode as u32
+    }
 }
 
 impl From<u32> for U {
@@ -316,6 +483,17 @@ impl J {
             rd: U5::new_truncate((value >> 7) as u8),
         }
     }
+
+    #[inline(always)]
+    pub const fn encode(&self, opcode: u8) -> u32 {
+        let imm = self.imm.as_u32();
+        (((imm >> 20) & 1) << 31)
+            | (((imm >> 1) & bitmask(10)) << 21)
+            | (((imm >> 11) & 1) << 20)
+            | (imm & (0b11111111 << 12))
+            | (self.rd.as_u32() << 7)
+            | opcode as u32
+    }
 }
 
 impl From<u32> for J {
@@ -325,6 +503,342 @@ impl From<u32> for J {
     }
 }
 
+/// Base opcodes targeted by the compressed instruction expansion.
+mod opcode {
+    pub const LUI: u8 = 0b0110111;
+    pub const JAL: u8 = 0b1101111;
+    pub const JALR: u8 = 0b1100111;
+    pub const BRANCH: u8 = 0b1100011;
+    pub const LOAD: u8 = 0b0000011;
+    pub const STORE: u8 = 0b0100011;
+    pub const MATHI: u8 = 0b0010011;
+    pub const MATH: u8 = 0b0110011;
+    pub const SYSTEM: u8 = 0b1110011;
+    pub const MATHIW: u8 = 0b0011011;
+    pub const MATHW: u8 = 0b0111011;
+    pub const LOAD_FP: u8 = 0b0000111;
+    pub const STORE_FP: u8 = 0b0100111;
+}
+
+const ZERO: U5 = U5::new_truncate(0);
+const RA: U5 = U5::new_truncate(1);
+const SP: U5 = U5::new_truncate(2);
+
+/// Instructions whose two lowest bits are not `0b11` are 16 bits long.
+#[inline(always)]
+pub const fn is_compressed(value: u32) -> bool {
+    value & 0b11 != 0b11
+}
+
+/// Expands a compressed instruction into its 32-bit base equivalent, `None` for reserved and
+/// illegal encodings. `xlen` selects between the RV32 and RV64 meaning of the overlapping
+/// quadrant slots (C.JAL/C.ADDIW, C.FLW/C.LD, ...).
+pub fn expand_compressed(value: u16, xlen: u32) -> Option<u32> {
+    let value = value as u32;
+    let rv32 = xlen == 32;
+
+    let rd = U5::new_truncate(field(value, 7, 5) as u8);
+    let rs2 = U5::new_truncate(field(value, 2, 5) as u8);
+    // rd'/rs2' at 4:2 and rs1'/rd' at 9:7 address x8-x15
+    let rd_low = U5::new_truncate(8 + field(value, 2, 3) as u8);
+    let rd_high = U5::new_truncate(8 + field(value, 7, 3) as u8);
+    // imm[5] at 12, imm[4:0] at 6:2
+    let imm6 = (field(value, 12, 1) << 5) | field(value, 2, 5);
+    // uimm[5:3] at 12:10 and uimm[2|6] or uimm[7:6] at 6:5
+    let uimm_w = (field(value, 10, 3) << 3) | (field(value, 6, 1) << 2) | (field(value, 5, 1) << 6);
+    let uimm_d = (field(value, 10, 3) << 3) | (field(value, 5, 2) << 6);
+
+    Some(match (value & 0b11, field(value, 13, 3)) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => {
+            let imm = (field(value, 11, 2) << 4)
+                | (field(value, 7, 4) << 6)
+                | (field(value, 6, 1) << 2)
+                | (field(value, 5, 1) << 3);
+            if imm == 0 {
+                return None;
+            }
+            i(imm, SP, 0b000, rd_low, opcode::MATHI)
+        }
+        // C.FLD
+        (0b00, 0b001) => i(uimm_d, rd_high, 0b011, rd_low, opcode::LOAD_FP),
+        // C.LW
+        (0b00, 0b010) => i(uimm_w, rd_high, 0b010, rd_low, opcode::LOAD),
+        // C.FLW
+        (0b00, 0b011) if rv32 => i(uimm_w, rd_high, 0b010, rd_low, opcode::LOAD_FP),
+        // C.LD
+        (0b00, 0b011) => i(uimm_d, rd_high, 0b011, rd_low, opcode::LOAD),
+        // C.FSD
+        (0b00, 0b101) => s(uimm_d, rd_low, rd_high, 0b011, opcode::STORE_FP),
+        // C.SW
+        (0b00, 0b110) => s(uimm_w, rd_low, rd_high, 0b010, opcode::STORE),
+        // C.FSW
+        (0b00, 0b111) if rv32 => s(uimm_w, rd_low, rd_high, 0b010, opcode::STORE_FP),
+        // C.SD
+        (0b00, 0b111) => s(uimm_d, rd_low, rd_high, 0b011, opcode::STORE),
+
+        // C.NOP, C.ADDI
+        (0b01, 0b000) => i(sign_extend(imm6, 6), rd, 0b000, rd, opcode::MATHI),
+        // C.JAL
+        (0b01, 0b001) if rv32 => j(cj_offset(value), RA),
+        // C.ADDIW
+        (0b01, 0b001) => {
+            if rd.as_u8() == 0 {
+                return None;
+            }
+            i(sign_extend(imm6, 6), rd, 0b000, rd, opcode::MATHIW)
+        }
+        // C.LI
+        (0b01, 0b010) => i(sign_extend(imm6, 6), ZERO, 0b000, rd, opcode::MATHI),
+        // C.ADDI16SP
+        (0b01, 0b011) if rd.as_u8() == 2 => {
+            let imm = (field(value, 12, 1) << 9)
+                | (field(value, 6, 1) << 4)
+                | (field(value, 5, 1) << 6)
+                | (field(value, 3, 2) << 7)
+                | (field(value, 2, 1) << 5);
+            if imm == 0 {
+                return None;
+            }
+            i(sign_extend(imm, 10), SP, 0b000, SP, opcode::MATHI)
+        }
+        // C.MOP.n, with C.SSPUSH x1 and C.SSPOPCHK x5
+        (0b01, 0b011) if imm6 == 0 && rd.as_u8() % 2 == 1 && rd.as_u8() < 16 => mop(rd),
+        // C.LUI
+        (0b01, 0b011) => {
+            if imm6 == 0 {
+                return None;
+            }
+            U {
+                imm: sign_extend(imm6, 6) << 12,
+                rd,
+            }
+            .encode(opcode::LUI)
+        }
+        (0b01, 0b100) => match field(value, 10, 2) {
+            // C.SRLI, C.SRAI
+            funct2 @ (0b00 | 0b01) => {
+                if rv32 && imm6 & (1 << 5) != 0 {
+                    return None;
+                }
+                i(
+                    imm6 | (funct2 << 10),
+                    rd_high,
+                    0b101,
+                    rd_high,
+                    opcode::MATHI,
+                )
+            }
+            // C.ANDI
+            0b10 => i(sign_extend(imm6, 6), rd_high, 0b111, rd_high, opcode::MATHI),
+            _ => {
+                let (funct7, funct3, opcode) = match (field(value, 12, 1), field(value, 5, 2)) {
+                    // C.SUB
+                    (0, 0b00) => (0b0100000, 0b000, opcode::MATH),
+                    // C.XOR
+                    (0, 0b01) => (0b0000000, 0b100, opcode::MATH),
+                    // C.OR
+                    (0, 0b10) => (0b0000000, 0b110, opcode::MATH),
+                    // C.AND
+                    (0, _) => (0b0000000, 0b111, opcode::MATH),
+                    // C.SUBW
+                    (_, 0b00) if !rv32 => (0b0100000, 0b000, opcode::MATHW),
+                    // C.ADDW
+                    (_, 0b01) if !rv32 => (0b0000000, 0b000, opcode::MATHW),
+                    _ => return None,
+                };
+                R {
+                    funct7: U7::new_truncate(funct7),
+                    rs2: rd_low,
+                    rs1: rd_high,
+                    funct3: U3::new_truncate(funct3),
+                    rd: rd_high,
+                }
+                .encode(opcode)
+            }
+        },
+        // C.J
+        (0b01, 0b101) => j(cj_offset(value), ZERO),
+        // C.BEQZ, C.BNEZ
+        (0b01, funct3 @ (0b110 | 0b111)) => {
+            let imm = (field(value, 12, 1) << 8)
+                | (field(value, 10, 2) << 3)
+                | (field(value, 5, 2) << 6)
+                | (field(value, 3, 2) << 1)
+                | (field(value, 2, 1) << 5);
+            B {
+                imm: U13::new_truncate(sign_extend(imm, 9) as u16),
+                rs2: ZERO,
+                rs1: rd_high,
+                funct3: U3::new_truncate(funct3 as u8 & 0b001),
+            }
+            .encode(opcode::BRANCH)
+        }
+
+        // C.SLLI
+        (0b10, 0b000) => {
+            if rv32 && imm6 & (1 << 5) != 0 {
+                return None;
+            }
+            i(imm6, rd, 0b001, rd, opcode::MATHI)
+        }
+        // C.FLDSP
+        (0b10, 0b001) => i(uimm_dsp(value), SP, 0b011, rd, opcode::LOAD_FP),
+        // C.LWSP
+        (0b10, 0b010) => {
+            if rd.as_u8() == 0 {
+                return None;
+            }
+            i(uimm_wsp(value), SP, 0b010, rd, opcode::LOAD)
+        }
+        // C.FLWSP
+        (0b10, 0b011) if rv32 => i(uimm_wsp(value), SP, 0b010, rd, opcode::LOAD_FP),
+        // C.LDSP
+        (0b10, 0b011) => {
+            if rd.as_u8() == 0 {
+                return None;
+            }
+            i(uimm_dsp(value), SP, 0b011, rd, opcode::LOAD)
+        }
+        (0b10, 0b100) => match (field(value, 12, 1), rd.as_u8(), rs2.as_u8()) {
+            // C.JR
+            (0, 0, 0) => return None,
+            (0, _, 0) => i(0, rd, 0b000, ZERO, opcode::JALR),
+            // C.MV
+            (0, _, _) => r(rs2, ZERO, rd),
+            // C.EBREAK
+            (_, 0, 0) => i(1, ZERO, 0b000, ZERO, opcode::SYSTEM),
+            // C.JALR
+            (_, _, 0) => i(0, rd, 0b000, RA, opcode::JALR),
+            // C.ADD
+            _ => r(rs2, rd, rd),
+        },
+        // C.FSDSP
+        (0b10, 0b101) => s(uimm_sdsp(value), rs2, SP, 0b011, opcode::STORE_FP),
+        // C.SWSP
+        (0b10, 0b110) => s(uimm_swsp(value), rs2, SP, 0b010, opcode::STORE),
+        // C.FSWSP
+        (0b10, 0b111) if rv32 => s(uimm_swsp(value), rs2, SP, 0b010, opcode::STORE_FP),
+        // C.SDSP
+        (0b10, 0b111) => s(uimm_sdsp(value), rs2, SP, 0b011, opcode::STORE),
+
+        _ => return None,
+    })
+}
+
+#[inline(always)]
+const fn i(imm: u32, rs1: U5, funct3: u8, rd: U5, opcode: u8) -> u32 {
+    I {
+        imm: U12::new_truncate(imm as u16),
+        rs1,
+        funct3: U3::new_truncate(funct3),
+        rd,
+    }
+    .encode(opcode)
+}
+
+#[inline(always)]
+const fn s(imm: u32, rs2: U5, rs1: U5, funct3: u8, opcode: u8) -> u32 {
+    S {
+        imm: U12::new_truncate(imm as u16),
+        rs2,
+        rs1,
+        funct3: U3::new_truncate(funct3),
+    }
+    .encode(opcode)
+}
+
+/// `add rd, rs1, rs2`
+#[inline(always)]
+const fn r(rs2: U5, rs1: U5, rd: U5) -> u32 {
+    R {
+        funct7: U7::new_truncate(0),
+        rs2,
+        rs1,
+        funct3: U3::new_truncate(0),
+        rd,
+    }
+    .encode(opcode::MATH)
+}
+
+/// `c.mop.n`, SSPOPCHK x5 for n = 5 and MOP.RR.7 reading `xn` otherwise, i.e. SSPUSH x1 for
+/// n = 1. Neither writes a register.
+#[inline(always)]
+const fn mop(n: U5) -> u32 {
+    let (funct7, rs2, rs1) = if n.as_u8() == 5 {
+        (0b1100110, U5::new_truncate(0b11100), n)
+    } else {
+        (0b1100111, n, ZERO)
+    };
+    R {
+        funct7: U7::new_truncate(funct7),
+        rs2,
+        rs1,
+        funct3: U3::new_truncate(0b100),
+        rd: ZERO,
+    }
+    .encode(opcode::SYSTEM)
+}
+
+#[inline(always)]
+const fn j(imm: u32, rd: U5) -> u32 {
+    J {
+        imm: U21::new_truncate(imm),
+        rd,
+    }
+    .encode(opcode::JAL)
+}
+
+/// C.J/C.JAL offset[11|4|9:8|10|6|7|3:1|5] at 12:2, sign-extended.
+#[inline(always)]
+const fn cj_offset(value: u32) -> u32 {
+    sign_extend(
+        (field(value, 12, 1) << 11)
+            | (field(value, 11, 1) << 4)
+            | (field(value, 9, 2) << 8)
+            | (field(value, 8, 1) << 10)
+            | (field(value, 7, 1) << 6)
+            | (field(value, 6, 1) << 7)
+            | (field(value, 3, 3) << 1)
+            | (field(value, 2, 1) << 5),
+        12,
+    )
+}
+
+/// C.LWSP/C.FLWSP uimm[5] at 12, uimm[4:2|7:6] at 6:2.
+#[inline(always)]
+const fn uimm_wsp(value: u32) -> u32 {
+    (field(value, 12, 1) << 5) | (field(value, 4, 3) << 2) | (field(value, 2, 2) << 6)
+}
+
+/// C.LDSP/C.FLDSP uimm[5] at 12, uimm[4:3|8:6] at 6:2.
+#[inline(always)]
+const fn uimm_dsp(value: u32) -> u32 {
+    (field(value, 12, 1) << 5) | (field(value, 5, 2) << 3) | (field(value, 2, 3) << 6)
+}
+
+/// C.SWSP/C.FSWSP uimm[5:2|7:6] at 12:7.
+#[inline(always)]
+const fn uimm_swsp(value: u32) -> u32 {
+    (field(value, 9, 4) << 2) | (field(value, 7, 2) << 6)
+}
+
+/// C.SDSP/C.FSDSP uimm[5:3|8:6] at 12:7.
+#[inline(always)]
+const fn uimm_sdsp(value: u32) -> u32 {
+    (field(value, 10, 3) << 3) | (field(value, 7, 3) << 6)
+}
+
+#[inline(always)]
+const fn field(value: u32, lsb: u32, bits: u32) -> u32 {
+    (value >> lsb) & bitmask(bits)
+}
+
+#[inline(always)]
+const fn sign_extend(value: u32, bits: u32) -> u32 {
+    (((value << (32 - bits)) as i32) >> (32 - bits)) as u32
+}
+
 macro_rules! impl_base {
     (@def $t:ident, $base:ty) => {
         #[repr(transparent)]
@@ -365,7 +879,7 @@ macro_rules! impl_base {
         #[inline(always)]
         pub const fn sign_extend(&self) -> <$base as Unsigned>::Signed {
             const OTHER_BITS: u32 = <$base as Unsigned>::Signed::BITS - <$t>::BITS;
-            unsafe { core::mem::transmute::<$base, <$base as Unsigned>::Signed>(self.0) }
+            (self.0 as <$base as Unsigned>::Signed)
                 .wrapping_shl(OTHER_BITS).wrapping_shr(OTHER_BITS)
         }
     };
@@ -474,14 +988,16 @@ macro_rules! impl_u8 {
             impl_base!(@const_into u8  => as_u8,
                                    u16 => as_u16,
                                    u32 => as_u32,
-                                   u64 => as_u64);
+                                   u64 => as_u64,
+                                   u128 => as_u128);
         }
 
         impl_base!(@from $t, u8, $($tt)*);
         impl_base!(@into $t, u8  => as_u8,
                              u16 => as_u16,
                              u32 => as_u32,
-                             u64 => as_u64);
+                             u64 => as_u64,
+                             u128 => as_u128);
     };
 }
 
@@ -505,7 +1021,8 @@ macro_rules! impl_u16 {
 
             impl_base!(@const_into u16 => as_u16,
                                    u32 => as_u32,
-                                   u64 => as_u64);
+                                   u64 => as_u64,
+                                   u128 => as_u128);
         }
 
         impl_base!(@from $t, u16,
@@ -514,7 +1031,8 @@ macro_rules! impl_u16 {
 
         impl_base!(@into $t, u16 => as_u16,
                              u32 => as_u32,
-                             u64 => as_u64);
+                             u64 => as_u64,
+                             u128 => as_u128);
     };
 }
 
@@ -538,7 +1056,8 @@ macro_rules! impl_u32 {
                        $($tt)*);
 
             impl_base!(@const_into u32 => as_u32,
-                                   u64 => as_u64);
+                                   u64 => as_u64,
+                                   u128 => as_u128);
         }
 
         impl_base!(@from $t, u32,
@@ -547,7 +1066,8 @@ macro_rules! impl_u32 {
                    $($tt)*);
 
         impl_base!(@into $t, u32 => as_u32,
-                             u64 => as_u64);
+                             u64 => as_u64,
+                             u128 => as_u128);
     };
 }
 
@@ -561,6 +1081,11 @@ impl_u8!( U5, 5,
           U2  > as_u8  => from_u2,
           U3  > as_u8  => from_u3,
           U4  > as_u8  => from_u4);
+impl_u8!( U6, 6,
+          U2  > as_u8  => from_u2,
+          U3  > as_u8  => from_u3,
+          U4  > as_u8  => from_u4,
+          U5  > as_u8  => from_u5);
 impl_u8!( U7, 7,
           U2  > as_u8  => from_u2,
           U3  > as_u8  => from_u3,
@@ -611,11 +1136,11 @@ mod tests {
     fn sign_extend() {
         assert_eq!(
             U13::new_truncate(0b1111111111110u16).sign_extend(),
-            unsafe { core::mem::transmute(0b1111111111111110u16) }
+            0b1111111111111110u16 as i16
         );
         assert_eq!(
             U13::new_truncate(0b0111111111110u16).sign_extend(),
-            unsafe { core::mem::transmute(0b0000111111111110u16) }
+            0b0000111111111110u16 as i16
         );
     }
 
@@ -660,6 +1185,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn split_shift() {
+        // srai x2, x1, 35
+        let shift = Shift::from(0b010000_100011_00001_101_00010_0010011);
+        assert_eq!(
+            shift.split(5),
+            (U10::new_truncate(0b0100001_101), U7::new_truncate(3))
+        );
+        assert_eq!(
+            shift.split(6),
+            (U10::new_truncate(0b0100000_101), U7::new_truncate(35))
+        );
+    }
+
+    #[test]
+    fn decode_fence() {
+        assert_eq!(
+            Fence::from(0b1000_0011_0011_00001_000_00010_0001111),
+            Fence {
+                fm: U4::new_truncate(0b1000),
+                pred: U4::new_truncate(0b0011),
+                succ: U4::new_truncate(0b0011),
+                rs1: U5::new_truncate(1),
+                funct3: U3::new_truncate(0),
+                rd: U5::new_truncate(2),
+            }
+        );
+    }
+
+    #[test]
+    fn decode_amo() {
+        assert_eq!(
+            Amo::from(0b00010_1_0_00000_00001_010_00010_0101111),
+            Amo {
+                funct5: U5::new_truncate(0b00010),
+                aq: true,
+                rl: false,
+                rs2: U5::new_truncate(0),
+                rs1: U5::new_truncate(1),
+                funct3: U3::new_truncate(0b010),
+                rd: U5::new_truncate(2),
+            }
+        );
+    }
+
     #[test]
     fn decode_s() {
         assert_eq!(
@@ -754,4 +1324,94 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn encode() {
+        for value in [
+            0b0100000_00001_00010_101_00100_0110011u32,
+            0b1010101_10101_01010_110_11111_0010011,
+            0b1000000_00001_00010_011_10101_0100011,
+            0b1_000000_00110_00111_001_1111_1_1100011,
+            0b10000000000100000000_10100_0110111,
+            0b1_1111111111_0_11111111_00001_1101111,
+        ] {
+            let opcode = (value & 0b1111111) as u8;
+            match opcode {
+                0b0110011 => assert_eq!(R::from(value).encode(opcode), value),
+                0b0010011 => assert_eq!(I::from(value).encode(opcode), value),
+                0b0100011 => assert_eq!(S::from(value).encode(opcode), value),
+                0b1100011 => assert_eq!(B::from(value).encode(opcode), value),
+                0b0110111 => assert_eq!(U::from(value).encode(opcode), value),
+                _ => assert_eq!(J::from(value).encode(opcode), value),
+            }
+        }
+    }
+
+    #[test]
+    fn expand_compressed_rv32() {
+        assert!(is_compressed(0x0001));
+        assert!(!is_compressed(0x00000013));
+        // c.addi4spn s0, sp, 16
+        assert_eq!(expand_compressed(0x0800, 32), Some(0x01010413));
+        // c.nop
+        assert_eq!(expand_compressed(0x0001, 32), Some(0x00000013));
+        // c.li a0, -1
+        assert_eq!(expand_compressed(0x557d, 32), Some(0xfff00513));
+        // c.j -2
+        assert_eq!(expand_compressed(0xbffd, 32), Some(0xfffff06f));
+        // c.jal -2
+        assert_eq!(expand_compressed(0x3ffd, 32), Some(0xfffff0ef));
+        // c.jr ra
+        assert_eq!(expand_compressed(0x8082, 32), Some(0x00008067));
+        // c.jalr a0
+        assert_eq!(expand_compressed(0x9502, 32), Some(0x000500e7));
+        // c.mv a0, a1
+        assert_eq!(expand_compressed(0x852e, 32), Some(0x00b00533));
+        // c.lwsp a0, 12(sp)
+        assert_eq!(expand_compressed(0x4532, 32), Some(0x00c12503));
+        // c.beqz a0, 8
+        assert_eq!(expand_compressed(0xc501, 32), Some(0x00050463));
+        // c.ebreak
+        assert_eq!(expand_compressed(0x9002, 32), Some(0x00100073));
+        // c.srai s0, 1
+        assert_eq!(expand_compressed(0x8405, 32), Some(0x40145413));
+        // c.sub s0, s1
+        assert_eq!(expand_compressed(0x8c05, 32), Some(0x40940433));
+        // c.sspush x1
+        assert_eq!(expand_compressed(0x6081, 32), Some(0xce104073));
+        // c.sspopchk x5
+        assert_eq!(expand_compressed(0x6281, 32), Some(0xcdc2c073));
+        // c.mop.3
+        assert_eq!(expand_compressed(0x6181, 32), Some(0xce304073));
+    }
+
+    #[test]
+    fn expand_compressed_rv64() {
+        // c.addiw a0, 1
+        assert_eq!(expand_compressed(0x2505, 64), Some(0x0015051b));
+        // c.sdsp ra, 8(sp)
+        assert_eq!(expand_compressed(0xe406, 64), Some(0x00113423));
+        // c.ld a0, 8(s0)
+        assert_eq!(expand_compressed(0x6408, 64), Some(0x00843503));
+        // c.slli a0, 32
+        assert_eq!(expand_compressed(0x1502, 64), Some(0x02051513));
+        // c.addw s0, s1
+        assert_eq!(expand_compressed(0x9c25, 64), Some(0x0094043b));
+    }
+
+    #[test]
+    fn expand_compressed_illegal() {
+        // all-zero parcel
+        assert_eq!(expand_compressed(0x0000, 32), None);
+        // c.lwsp with rd = x0
+        assert_eq!(expand_compressed(0x4002, 32), None);
+        // c.jr with rs1 = x0
+        assert_eq!(expand_compressed(0x8002, 32), None);
+        // c.slli with shamt[5] set on RV32
+        assert_eq!(expand_compressed(0x1502, 32), None);
+        // c.addiw with rd = x0
+        assert_eq!(expand_compressed(0x2005, 64), None);
+        // c.addw is RV64 only
+        assert_eq!(expand_compressed(0x9c25, 32), None);
+    }
 }
@@ -1,4 +1,20 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Error {
     InvalidOpCode,
+    /// A jump or branch target that is not IALIGN-aligned, with the target address.
+    InstructionAddressMisaligned(usize),
+    InstructionAccessFault(usize),
+    /// EBREAK, including C.EBREAK.
+    Breakpoint,
+    LoadAddressMisaligned(usize),
+    LoadAccessFault(usize),
+    StoreAddressMisaligned(usize),
+    StoreAccessFault(usize),
+    /// ECALL, its cause depends on the privilege mode it was executed from.
+    EnvironmentCall,
+    InstructionPageFault(usize),
+    LoadPageFault(usize),
+    StorePageFault(usize),
+    /// Zicfilp and Zicfiss violations, with the `xtval` of the exception.
+    SoftwareCheck(usize),
 }
@@ -0,0 +1,1322 @@
+use crate::{decode::U3, error::Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Flags(u8);
+
+impl Flags {
+    /// Inexact.
+    pub const NX: Self = Self(0b00001);
+    /// Underflow.
+    pub const UF: Self = Self(0b00010);
+    /// Overflow.
+    pub const OF: Self = Self(0b00100);
+    /// Divide by zero.
+    pub const DZ: Self = Self(0b01000);
+    /// Invalid operation.
+    pub const NV: Self = Self(0b10000);
+
+    const MASK: u8 = 0b11111;
+
+    #[inline(always)]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn from_bits_truncate(bits: u8) -> Self {
+        Self(bits & Self::MASK)
+    }
+}
+
+impl core::ops::BitOr for Flags {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl core::ops::BitOrAssign for Flags {
+    #[inline(always)]
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+    /// Round to nearest, ties to even.
+    Rne,
+    /// Round towards zero.
+    Rtz,
+    /// Round down (towards -inf).
+    Rdn,
+    /// Round up (towards +inf).
+    Rup,
+    /// Round to nearest, ties to max magnitude.
+    Rmm,
+}
+
+impl RoundingMode {
+    #[inline(always)]
+    pub const fn from_u3(value: U3) -> Option<Self> {
+        match value.as_u8() {
+            0b000 => Some(Self::Rne),
+            0b001 => Some(Self::Rtz),
+            0b010 => Some(Self::Rdn),
+            0b011 => Some(Self::Rup),
+            0b100 => Some(Self::Rmm),
+            _ => None,
+        }
+    }
+}
+
+/// Floating-point control and status register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Fcsr {
+    pub fflags: Flags,
+    pub frm: u8,
+}
+
+impl Fcsr {
+    const DYN: u8 = 0b111;
+
+    /// Value of the `fcsr` CSR, `frm` in bits 7:5 and `fflags` in bits 4:0.
+    #[inline(always)]
+    pub const fn bits(&self) -> u8 {
+        (self.frm << 5) | self.fflags.bits()
+    }
+
+    #[inline(always)]
+    pub const fn set_bits(&mut self, bits: u8) {
+        self.frm = bits >> 5;
+        self.fflags = Flags::from_bits_truncate(bits);
+    }
+
+    /// Resolves the `rm` field of an instruction, falling back to `frm` for the dynamic mode.
+    #[inline]
+    pub fn rounding_mode(&self, rm: U3) -> Result<RoundingMode, Error> {
+        let rm = if rm.as_u8() == Self::DYN {
+            U3::new_truncate(self.frm)
+        } else {
+            rm
+        };
+        RoundingMode::from_u3(rm).ok_or(Error::InvalidOpCode)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Zero,
+    /// The value is `sig * 2^exp`.
+    Finite {
+        exp: i32,
+        sig: u64,
+    },
+    Infinite,
+    Nan {
+        signaling: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unpacked {
+    sign: bool,
+    class: Class,
+}
+
+impl Unpacked {
+    #[inline(always)]
+    const fn is_nan(&self) -> bool {
+        matches!(self.class, Class::Nan { .. })
+    }
+
+    #[inline(always)]
+    const fn is_signaling(&self) -> bool {
+        matches!(self.class, Class::Nan { signaling: true })
+    }
+}
+
+/// Width of an integer operand of a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntFormat {
+    I32,
+    U32,
+    I64,
+    U64,
+}
+
+impl IntFormat {
+    #[inline(always)]
+    const fn bounds(self) -> (i128, i128) {
+        match self {
+            Self::I32 => (i32::MIN as i128, i32::MAX as i128),
+            Self::U32 => (0, u32::MAX as i128),
+            Self::I64 => (i64::MIN as i128, i64::MAX as i128),
+            Self::U64 => (0, u64::MAX as i128),
+        }
+    }
+}
+
+/// IEEE 754 binary interchange format, values are carried in the low bits of a `u64`.
+///
+/// All the operations follow the RISC-V conventions: NaN results are always the canonical
+/// NaN, tininess is detected after rounding and the accrued exceptions are or-ed in `flags`.
+pub trait Float {
+    const EXP_BITS: u32;
+    const FRAC_BITS: u32;
+
+    const BITS: u32 = 1 + Self::EXP_BITS + Self::FRAC_BITS;
+    const MASK: u64 = u64::MAX >> (64 - Self::BITS);
+    const SIGN: u64 = 1 << (Self::BITS - 1);
+    const FRAC_MASK: u64 = (1 << Self::FRAC_BITS) - 1;
+    const EXP_MAX: u64 = (1 << Self::EXP_BITS) - 1;
+    const BIAS: i32 = (1 << (Self::EXP_BITS - 1)) - 1;
+    const INFINITY: u64 = Self::EXP_MAX << Self::FRAC_BITS;
+    const CANONICAL_NAN: u64 = Self::INFINITY | (1 << (Self::FRAC_BITS - 1));
+
+    /// Extracts a value from a register, improperly NaN-boxed values read as the canonical NaN.
+    #[inline(always)]
+    fn unbox(reg: u64) -> u64 {
+        if reg | Self::MASK == u64::MAX {
+            reg & Self::MASK
+        } else {
+            Self::CANONICAL_NAN
+        }
+    }
+
+    /// NaN-boxes a value to be written in a register.
+    #[inline(always)]
+    fn nan_box(value: u64) -> u64 {
+        value | !Self::MASK
+    }
+
+    #[inline]
+    fn add(a: u64, b: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        match (ua.class, ub.class) {
+            (Class::Nan { .. }, _) | (_, Class::Nan { .. }) => nan::<Self>(flags, &[ua, ub]),
+            (Class::Infinite, Class::Infinite) if ua.sign != ub.sign => invalid::<Self>(flags),
+            (Class::Infinite, _) => infinity::<Self>(ua.sign),
+            (_, Class::Infinite) => infinity::<Self>(ub.sign),
+            (Class::Zero, Class::Zero) => zero::<Self>(zero_sum_sign(ua.sign, ub.sign, rm)),
+            (Class::Zero, _) => b,
+            (_, Class::Zero) => a,
+            (Class::Finite { exp: ea, sig: sa }, Class::Finite { exp: eb, sig: sb }) => {
+                add_terms::<Self>(
+                    (ua.sign, ea, sa as u128),
+                    (ub.sign, eb, sb as u128),
+                    rm,
+                    flags,
+                )
+            }
+        }
+    }
+
+    #[inline]
+    fn sub(a: u64, b: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        Self::add(a, b ^ Self::SIGN, rm, flags)
+    }
+
+    #[inline]
+    fn mul(a: u64, b: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        let sign = ua.sign ^ ub.sign;
+        match (ua.class, ub.class) {
+            (Class::Nan { .. }, _) | (_, Class::Nan { .. }) => nan::<Self>(flags, &[ua, ub]),
+            (Class::Infinite, Class::Zero) | (Class::Zero, Class::Infinite) => {
+                invalid::<Self>(flags)
+            }
+            (Class::Infinite, _) | (_, Class::Infinite) => infinity::<Self>(sign),
+            (Class::Zero, _) | (_, Class::Zero) => zero::<Self>(sign),
+            (Class::Finite { exp: ea, sig: sa }, Class::Finite { exp: eb, sig: sb }) => {
+                round_pack::<Self>(sign, ea + eb, sa as u128 * sb as u128, rm, flags)
+            }
+        }
+    }
+
+    #[inline]
+    fn div(a: u64, b: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        let sign = ua.sign ^ ub.sign;
+        match (ua.class, ub.class) {
+            (Class::Nan { .. }, _) | (_, Class::Nan { .. }) => nan::<Self>(flags, &[ua, ub]),
+            (Class::Infinite, Class::Infinite) | (Class::Zero, Class::Zero) => {
+                invalid::<Self>(flags)
+            }
+            (Class::Infinite, _) => infinity::<Self>(sign),
+            (_, Class::Infinite) | (Class::Zero, _) => zero::<Self>(sign),
+            (_, Class::Zero) => {
+                *flags |= Flags::DZ;
+                infinity::<Self>(sign)
+            }
+            (Class::Finite { exp: ea, sig: sa }, Class::Finite { exp: eb, sig: sb }) => {
+                let (ea, sa) = normalize(ea, sa);
+                let (eb, sb) = normalize(eb, sb);
+                let dividend = (sa as u128) << 64;
+                let quotient = dividend / sb as u128;
+                let sticky = !dividend.is_multiple_of(sb as u128);
+                round_pack::<Self>(sign, ea - 64 - eb, quotient | sticky as u128, rm, flags)
+            }
+        }
+    }
+
+    #[inline]
+    fn sqrt(a: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let ua = unpack::<Self>(a);
+        match ua.class {
+            Class::Nan { .. } => nan::<Self>(flags, &[ua]),
+            Class::Zero => a,
+            _ if ua.sign => invalid::<Self>(flags),
+            Class::Infinite => a,
+            Class::Finite { exp, sig } => {
+                let (exp, sig) = normalize(exp, sig);
+                // the radicand exponent has to be even
+                let shift = 64 - (exp & 1);
+                let radicand = (sig as u128) << shift;
+                let root = isqrt(radicand);
+                let sticky = root * root != radicand;
+                round_pack::<Self>(false, (exp - shift) / 2, root | sticky as u128, rm, flags)
+            }
+        }
+    }
+
+    /// Computes `a * b + c` with a single rounding.
+    #[inline]
+    fn mul_add(a: u64, b: u64, c: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let (ua, ub, uc) = (unpack::<Self>(a), unpack::<Self>(b), unpack::<Self>(c));
+        let sign = ua.sign ^ ub.sign;
+        let inf_times_zero = matches!(
+            (ua.class, ub.class),
+            (Class::Infinite, Class::Zero) | (Class::Zero, Class::Infinite)
+        );
+        if ua.is_nan() || ub.is_nan() || uc.is_nan() {
+            // the invalid multiplication is signaled even when the addend is a quiet NaN
+            if inf_times_zero {
+                *flags |= Flags::NV;
+            }
+            return nan::<Self>(flags, &[ua, ub, uc]);
+        }
+        if inf_times_zero {
+            return invalid::<Self>(flags);
+        }
+        match (ua.class, ub.class, uc.class) {
+            (Class::Infinite, _, Class::Infinite) | (_, Class::Infinite, Class::Infinite)
+                if sign != uc.sign =>
+            {
+                invalid::<Self>(flags)
+            }
+            (Class::Infinite, _, _) | (_, Class::Infinite, _) => infinity::<Self>(sign),
+            (_, _, Class::Infinite) => c,
+            (Class::Zero, _, Class::Zero) | (_, Class::Zero, Class::Zero) => {
+                zero::<Self>(zero_sum_sign(sign, uc.sign, rm))
+            }
+            (Class::Zero, _, _) | (_, Class::Zero, _) => c,
+            (
+                Class::Finite { exp: ea, sig: sa },
+                Class::Finite { exp: eb, sig: sb },
+                Class::Zero,
+            ) => round_pack::<Self>(sign, ea + eb, sa as u128 * sb as u128, rm, flags),
+            (
+                Class::Finite { exp: ea, sig: sa },
+                Class::Finite { exp: eb, sig: sb },
+                Class::Finite { exp: ec, sig: sc },
+            ) => add_terms::<Self>(
+                (sign, ea + eb, sa as u128 * sb as u128),
+                (uc.sign, ec, sc as u128),
+                rm,
+                flags,
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn min(a: u64, b: u64, flags: &mut Flags) -> u64 {
+        min_max::<Self>(a, b, false, flags)
+    }
+
+    #[inline]
+    fn max(a: u64, b: u64, flags: &mut Flags) -> u64 {
+        min_max::<Self>(a, b, true, flags)
+    }
+
+    /// Quiet comparison, only signaling NaNs raise the invalid operation exception.
+    #[inline]
+    fn eq(a: u64, b: u64, flags: &mut Flags) -> bool {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            if ua.is_signaling() || ub.is_signaling() {
+                *flags |= Flags::NV;
+            }
+            false
+        } else {
+            key::<Self>(a) == key::<Self>(b)
+        }
+    }
+
+    #[inline]
+    fn lt(a: u64, b: u64, flags: &mut Flags) -> bool {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            *flags |= Flags::NV;
+            false
+        } else {
+            key::<Self>(a) < key::<Self>(b)
+        }
+    }
+
+    #[inline]
+    fn le(a: u64, b: u64, flags: &mut Flags) -> bool {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            *flags |= Flags::NV;
+            false
+        } else {
+            key::<Self>(a) <= key::<Self>(b)
+        }
+    }
+
+    #[inline]
+    fn sgnj(a: u64, b: u64) -> u64 {
+        (a & !Self::SIGN) | (b & Self::SIGN)
+    }
+
+    #[inline]
+    fn sgnjn(a: u64, b: u64) -> u64 {
+        (a & !Self::SIGN) | (!b & Self::SIGN)
+    }
+
+    #[inline]
+    fn sgnjx(a: u64, b: u64) -> u64 {
+        a ^ (b & Self::SIGN)
+    }
+
+    /// Returns the FCLASS mask of the value.
+    #[inline]
+    fn classify(a: u64) -> u64 {
+        let ua = unpack::<Self>(a);
+        let subnormal = (a >> Self::FRAC_BITS) & Self::EXP_MAX == 0;
+        let bit = match (ua.class, ua.sign) {
+            (Class::Infinite, true) => 0,
+            (Class::Finite { .. }, true) if !subnormal => 1,
+            (Class::Finite { .. }, true) => 2,
+            (Class::Zero, true) => 3,
+            (Class::Zero, false) => 4,
+            (Class::Finite { .. }, false) if subnormal => 5,
+            (Class::Finite { .. }, false) => 6,
+            (Class::Infinite, false) => 7,
+            (Class::Nan { signaling: true }, _) => 8,
+            (Class::Nan { signaling: false }, _) => 9,
+        };
+        1 << bit
+    }
+
+    /// Converts to an integer, returning its two's complement representation.
+    #[inline]
+    fn to_int(a: u64, format: IntFormat, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let ua = unpack::<Self>(a);
+        let (min, max) = format.bounds();
+        let value = match ua.class {
+            Class::Nan { .. } => {
+                *flags |= Flags::NV;
+                return max as u64;
+            }
+            Class::Infinite => {
+                *flags |= Flags::NV;
+                return (if ua.sign { min } else { max }) as u64;
+            }
+            Class::Zero => return 0,
+            // any such value is out of range
+            Class::Finite { exp, .. } if exp > 64 => None,
+            Class::Finite { exp, sig } if exp >= 0 => Some(((sig as u128) << exp, false)),
+            Class::Finite { exp, sig } => {
+                Some(round_shift(sig as u128, exp.unsigned_abs(), ua.sign, rm))
+            }
+        };
+        match value {
+            Some((magnitude, inexact)) => {
+                let value = if ua.sign {
+                    -(magnitude as i128)
+                } else {
+                    magnitude as i128
+                };
+                if value < min || value > max {
+                    *flags |= Flags::NV;
+                    (if ua.sign { min } else { max }) as u64
+                } else {
+                    if inexact {
+                        *flags |= Flags::NX;
+                    }
+                    value as u64
+                }
+            }
+            None => {
+                *flags |= Flags::NV;
+                (if ua.sign { min } else { max }) as u64
+            }
+        }
+    }
+
+    /// Converts from an integer given its two's complement representation.
+    #[inline]
+    fn from_int(value: u64, format: IntFormat, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let value = match format {
+            IntFormat::I32 => value as i32 as i128,
+            IntFormat::U32 => value as u32 as i128,
+            IntFormat::I64 => value as i64 as i128,
+            IntFormat::U64 => value as i128,
+        };
+        if value == 0 {
+            zero::<Self>(false)
+        } else {
+            round_pack::<Self>(value < 0, 0, value.unsigned_abs(), rm, flags)
+        }
+    }
+
+    /// Converts a value of format `F` to this format.
+    #[inline]
+    fn convert<F: Float + ?Sized>(a: u64, rm: RoundingMode, flags: &mut Flags) -> u64 {
+        let ua = unpack::<F>(a);
+        match ua.class {
+            Class::Nan { .. } => nan::<Self>(flags, &[ua]),
+            Class::Infinite => infinity::<Self>(ua.sign),
+            Class::Zero => zero::<Self>(ua.sign),
+            Class::Finite { exp, sig } => round_pack::<Self>(ua.sign, exp, sig as u128, rm, flags),
+        }
+    }
+
+    /// Returns the `index`-th entry of the FLI constant table.
+    #[inline]
+    fn constant(index: u8) -> u64 {
+        match FLI[index as usize & 0b11111] {
+            Constant::MinNormal => 1 << Self::FRAC_BITS,
+            Constant::Infinity => infinity::<Self>(false),
+            Constant::Nan => Self::CANONICAL_NAN,
+            // the value is always exact, but 2^16 overflows to infinity in half precision
+            Constant::Value(sign, exp, frac) => round_pack::<Self>(
+                sign,
+                exp - 2,
+                0b100 | frac as u128,
+                RoundingMode::Rne,
+                &mut Flags::default(),
+            ),
+        }
+    }
+
+    /// Like `min`, but any NaN operand produces the canonical NaN.
+    #[inline]
+    fn minm(a: u64, b: u64, flags: &mut Flags) -> u64 {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            nan::<Self>(flags, &[ua, ub])
+        } else {
+            min_max::<Self>(a, b, false, flags)
+        }
+    }
+
+    /// Like `max`, but any NaN operand produces the canonical NaN.
+    #[inline]
+    fn maxm(a: u64, b: u64, flags: &mut Flags) -> u64 {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            nan::<Self>(flags, &[ua, ub])
+        } else {
+            min_max::<Self>(a, b, true, flags)
+        }
+    }
+
+    /// Quiet version of `lt`.
+    #[inline]
+    fn ltq(a: u64, b: u64, flags: &mut Flags) -> bool {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            if ua.is_signaling() || ub.is_signaling() {
+                *flags |= Flags::NV;
+            }
+            false
+        } else {
+            key::<Self>(a) < key::<Self>(b)
+        }
+    }
+
+    /// Quiet version of `le`.
+    #[inline]
+    fn leq(a: u64, b: u64, flags: &mut Flags) -> bool {
+        let (ua, ub) = (unpack::<Self>(a), unpack::<Self>(b));
+        if ua.is_nan() || ub.is_nan() {
+            if ua.is_signaling() || ub.is_signaling() {
+                *flags |= Flags::NV;
+            }
+            false
+        } else {
+            key::<Self>(a) <= key::<Self>(b)
+        }
+    }
+
+    /// Rounds to an integral value in the same format, the inexact exception is raised only when
+    /// `exact` is set.
+    #[inline]
+    fn round_to_int(a: u64, rm: RoundingMode, exact: bool, flags: &mut Flags) -> u64 {
+        let ua = unpack::<Self>(a);
+        match ua.class {
+            Class::Nan { .. } => nan::<Self>(flags, &[ua]),
+            Class::Finite { exp, sig } if exp < 0 => {
+                let (magnitude, inexact) =
+                    round_shift(sig as u128, exp.unsigned_abs(), ua.sign, rm);
+                if inexact && exact {
+                    *flags |= Flags::NX;
+                }
+                if magnitude == 0 {
+                    zero::<Self>(ua.sign)
+                } else {
+                    round_pack::<Self>(ua.sign, 0, magnitude, rm, &mut Flags::default())
+                }
+            }
+            Class::Finite { .. } | Class::Zero | Class::Infinite => a,
+        }
+    }
+
+    /// Converts to a 32-bit signed integer rounding towards zero and wrapping modulo 2^32,
+    /// returning its sign-extended representation.
+    #[inline]
+    fn to_int_modular(a: u64, flags: &mut Flags) -> u64 {
+        let ua = unpack::<Self>(a);
+        let (magnitude, inexact) = match ua.class {
+            Class::Nan { .. } | Class::Infinite => {
+                *flags |= Flags::NV;
+                return 0;
+            }
+            Class::Zero => return 0,
+            // only the bits below 2^32 matter
+            Class::Finite { exp, .. } if exp >= 32 => (1 << 32, false),
+            Class::Finite { exp, sig } if exp >= 0 => ((sig as u128) << exp, false),
+            Class::Finite { exp, sig } => {
+                round_shift(sig as u128, exp.unsigned_abs(), ua.sign, RoundingMode::Rtz)
+            }
+        };
+        let value = if ua.sign {
+            -(magnitude as i128)
+        } else {
+            magnitude as i128
+        };
+        if value < i32::MIN as i128 || value > i32::MAX as i128 {
+            *flags |= Flags::NV;
+        } else if inexact {
+            *flags |= Flags::NX;
+        }
+        value as i32 as u64
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Constant {
+    MinNormal,
+    Infinity,
+    Nan,
+    /// `(-1)^sign * 1.frac * 2^exp` with a 2-bit fraction.
+    Value(bool, i32, u8),
+}
+
+/// Values loadable by FLI, indexed by `rs1`.
+const FLI: [Constant; 32] = {
+    use Constant::*;
+    [
+        Value(true, 0, 0b00),
+        MinNormal,
+        Value(false, -16, 0b00),
+        Value(false, -15, 0b00),
+        Value(false, -8, 0b00),
+        Value(false, -7, 0b00),
+        Value(false, -4, 0b00),
+        Value(false, -3, 0b00),
+        Value(false, -2, 0b00),
+        Value(false, -2, 0b01),
+        Value(false, -2, 0b10),
+        Value(false, -2, 0b11),
+        Value(false, -1, 0b00),
+        Value(false, -1, 0b01),
+        Value(false, -1, 0b10),
+        Value(false, -1, 0b11),
+        Value(false, 0, 0b00),
+        Value(false, 0, 0b01),
+        Value(false, 0, 0b10),
+        Value(false, 0, 0b11),
+        Value(false, 1, 0b00),
+        Value(false, 1, 0b01),
+        Value(false, 1, 0b10),
+        Value(false, 2, 0b00),
+        Value(false, 3, 0b00),
+        Value(false, 4, 0b00),
+        Value(false, 7, 0b00),
+        Value(false, 8, 0b00),
+        Value(false, 15, 0b00),
+        Value(false, 16, 0b00),
+        Infinity,
+        Nan,
+    ]
+};
+
+/// IEEE 754 binary16.
+pub enum F16 {}
+
+/// IEEE 754 binary32.
+pub enum F32 {}
+
+/// IEEE 754 binary64.
+pub enum F64 {}
+
+impl Float for F16 {
+    const EXP_BITS: u32 = 5;
+    const FRAC_BITS: u32 = 10;
+}
+
+impl Float for F32 {
+    const EXP_BITS: u32 = 8;
+    const FRAC_BITS: u32 = 23;
+}
+
+impl Float for F64 {
+    const EXP_BITS: u32 = 11;
+    const FRAC_BITS: u32 = 52;
+}
+
+#[inline(always)]
+fn unpack<F: Float + ?Sized>(bits: u64) -> Unpacked {
+    let sign = bits & F::SIGN != 0;
+    let exp = (bits >> F::FRAC_BITS) & F::EXP_MAX;
+    let frac = bits & F::FRAC_MASK;
+    let class = match (exp, frac) {
+        (0, 0) => Class::Zero,
+        (0, _) => Class::Finite {
+            exp: 1 - F::BIAS - F::FRAC_BITS as i32,
+            sig: frac,
+        },
+        (e, 0) if e == F::EXP_MAX => Class::Infinite,
+        (e, _) if e == F::EXP_MAX => Class::Nan {
+            signaling: frac & (1 << (F::FRAC_BITS - 1)) == 0,
+        },
+        (e, _) => Class::Finite {
+            exp: e as i32 - F::BIAS - F::FRAC_BITS as i32,
+            sig: frac | (1 << F::FRAC_BITS),
+        },
+    };
+    Unpacked { sign, class }
+}
+
+/// Shifts the significand so that its most significant bit is bit 63.
+#[inline(always)]
+fn normalize(exp: i32, sig: u64) -> (i32, u64) {
+    let shift = sig.leading_zeros();
+    (exp - shift as i32, sig << shift)
+}
+
+#[inline(always)]
+fn zero<F: Float + ?Sized>(sign: bool) -> u64 {
+    if sign {
+        F::SIGN
+    } else {
+        0
+    }
+}
+
+#[inline(always)]
+fn infinity<F: Float + ?Sized>(sign: bool) -> u64 {
+    zero::<F>(sign) | F::INFINITY
+}
+
+#[inline(always)]
+fn invalid<F: Float + ?Sized>(flags: &mut Flags) -> u64 {
+    *flags |= Flags::NV;
+    F::CANONICAL_NAN
+}
+
+#[inline(always)]
+fn nan<F: Float + ?Sized>(flags: &mut Flags, operands: &[Unpacked]) -> u64 {
+    if operands.iter().any(Unpacked::is_signaling) {
+        *flags |= Flags::NV;
+    }
+    F::CANONICAL_NAN
+}
+
+/// Sign of an exact zero sum.
+#[inline(always)]
+fn zero_sum_sign(a: bool, b: bool, rm: RoundingMode) -> bool {
+    if a == b {
+        a
+    } else {
+        rm == RoundingMode::Rdn
+    }
+}
+
+/// Maps a non-NaN value to an integer with the same ordering, both zeroes map to 0.
+#[inline(always)]
+fn key<F: Float + ?Sized>(bits: u64) -> i128 {
+    let magnitude = (bits & !F::SIGN & F::MASK) as i128;
+    if bits & F::SIGN != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+#[inline(always)]
+fn min_max<F: Float + ?Sized>(a: u64, b: u64, max: bool, flags: &mut Flags) -> u64 {
+    let (ua, ub) = (unpack::<F>(a), unpack::<F>(b));
+    if ua.is_signaling() || ub.is_signaling() {
+        *flags |= Flags::NV;
+    }
+    match (ua.is_nan(), ub.is_nan()) {
+        (true, true) => F::CANONICAL_NAN,
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => match key::<F>(a).cmp(&key::<F>(b)) {
+            core::cmp::Ordering::Less => {
+                if max {
+                    b
+                } else {
+                    a
+                }
+            }
+            core::cmp::Ordering::Greater => {
+                if max {
+                    a
+                } else {
+                    b
+                }
+            }
+            // -0 is considered less than +0
+            core::cmp::Ordering::Equal => {
+                if ua.sign != max {
+                    a
+                } else {
+                    b
+                }
+            }
+        },
+    }
+}
+
+#[inline(always)]
+fn shift_right_jam(value: u128, shift: u32) -> u128 {
+    match shift {
+        0 => value,
+        1..=127 => (value >> shift) | (value & ((1 << shift) - 1) != 0) as u128,
+        _ => (value != 0) as u128,
+    }
+}
+
+/// Exact sum of two non-zero terms `sig * 2^exp` with a single rounding.
+#[inline(always)]
+fn add_terms<F: Float + ?Sized>(
+    a: (bool, i32, u128),
+    b: (bool, i32, u128),
+    rm: RoundingMode,
+    flags: &mut Flags,
+) -> u64 {
+    // Both terms are aligned with their leading bit on bit 125: they are at most 106 bits wide, so
+    // short alignments are exact and the long ones leave plenty of guard bits above the sticky.
+    let align = |(sign, exp, sig): (bool, i32, u128)| {
+        let shift = sig.leading_zeros() as i32 - 2;
+        (sign, exp - shift, sig << shift)
+    };
+    let (a, b) = (align(a), align(b));
+    let (big, small) = if a.1 >= b.1 { (a, b) } else { (b, a) };
+    let small_sig = shift_right_jam(small.2, (big.1 - small.1) as u32);
+    let exp = big.1;
+
+    if big.0 == small.0 {
+        round_pack::<F>(big.0, exp, big.2 + small_sig, rm, flags)
+    } else {
+        match big.2.cmp(&small_sig) {
+            core::cmp::Ordering::Greater => {
+                round_pack::<F>(big.0, exp, big.2 - small_sig, rm, flags)
+            }
+            core::cmp::Ordering::Less => {
+                round_pack::<F>(small.0, exp, small_sig - big.2, rm, flags)
+            }
+            core::cmp::Ordering::Equal => zero::<F>(rm == RoundingMode::Rdn),
+        }
+    }
+}
+
+/// Shifts right `value` rounding the result, returns it along with the inexact flag.
+#[inline(always)]
+fn round_shift(value: u128, shift: u32, sign: bool, rm: RoundingMode) -> (u128, bool) {
+    let (result, half, sticky) = match shift {
+        0 => (value, false, false),
+        1..=127 => (
+            value >> shift,
+            (value >> (shift - 1)) & 1 != 0,
+            value & ((1 << (shift - 1)) - 1) != 0,
+        ),
+        128 => (0, value >> 127 != 0, value & (u128::MAX >> 1) != 0),
+        _ => (0, false, value != 0),
+    };
+    let inexact = half || sticky;
+    let up = match rm {
+        RoundingMode::Rne => half && (sticky || result & 1 != 0),
+        RoundingMode::Rtz => false,
+        RoundingMode::Rdn => inexact && sign,
+        RoundingMode::Rup => inexact && !sign,
+        RoundingMode::Rmm => half,
+    };
+    (result + up as u128, inexact)
+}
+
+/// Rounds and encodes the non-zero value `sig * 2^exp`.
+fn round_pack<F: Float + ?Sized>(
+    sign: bool,
+    exp: i32,
+    sig: u128,
+    rm: RoundingMode,
+    flags: &mut Flags,
+) -> u64 {
+    debug_assert_ne!(sig, 0);
+    let frac_bits = F::FRAC_BITS as i32;
+    let emin = 1 - F::BIAS;
+    // exponent of the most significant bit
+    let top = exp + 127 - sig.leading_zeros() as i32;
+    // exponent of the least significant bit of the result
+    let mut lsb = top.max(emin) - frac_bits;
+
+    let (mut result, inexact) = if lsb >= exp {
+        round_shift(sig, (lsb - exp) as u32, sign, rm)
+    } else {
+        (sig << (exp - lsb), false)
+    };
+    if result >> (frac_bits + 1) != 0 {
+        result >>= 1;
+        lsb += 1;
+    }
+
+    if inexact {
+        *flags |= Flags::NX;
+        // tininess is detected after rounding, as if the exponent range was unbounded
+        if top < emin {
+            let shift = top - frac_bits - exp;
+            let carries =
+                shift > 0 && round_shift(sig, shift as u32, sign, rm).0 >> (frac_bits + 1) != 0;
+            if top + 1 < emin || !carries {
+                *flags |= Flags::UF;
+            }
+        }
+    }
+
+    let biased = if result >> frac_bits == 0 {
+        0
+    } else {
+        (lsb + frac_bits + F::BIAS) as u64
+    };
+    if biased >= F::EXP_MAX {
+        *flags |= Flags::OF | Flags::NX;
+        let to_infinity = match rm {
+            RoundingMode::Rne | RoundingMode::Rmm => true,
+            RoundingMode::Rtz => false,
+            RoundingMode::Rdn => sign,
+            RoundingMode::Rup => !sign,
+        };
+        return if to_infinity {
+            infinity::<F>(sign)
+        } else {
+            zero::<F>(sign) | (F::INFINITY - 1)
+        };
+    }
+    zero::<F>(sign) | (biased << F::FRAC_BITS) | (result as u64 & F::FRAC_MASK)
+}
+
+#[inline(always)]
+fn isqrt(value: u128) -> u128 {
+    let mut rem = value;
+    let mut root = 0u128;
+    let mut bit = 1u128 << 126;
+    while bit > value {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u64 = 0x3f80_0000;
+    const TWO: u64 = 0x4000_0000;
+    const MAX: u64 = 0x7f7f_ffff;
+    const NEG_ZERO: u64 = 0x8000_0000;
+    const SNAN: u64 = 0x7f80_0001;
+
+    fn op(f: impl FnOnce(&mut Flags) -> u64) -> (u64, Flags) {
+        let mut flags = Flags::default();
+        (f(&mut flags), flags)
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            op(|fl| F32::add(ONE, ONE, RoundingMode::Rne, fl)),
+            (TWO, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::div(ONE, 0x4040_0000, RoundingMode::Rne, fl)),
+            (0x3eaa_aaab, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::div(ONE, 0x4040_0000, RoundingMode::Rtz, fl)),
+            (0x3eaa_aaaa, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::sqrt(0x4080_0000, RoundingMode::Rne, fl)),
+            (TWO, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::mul_add(TWO, TWO, ONE | F32::SIGN, RoundingMode::Rne, fl)),
+            (0x4040_0000, Flags::default())
+        );
+    }
+
+    #[test]
+    fn exceptions() {
+        assert_eq!(
+            op(|fl| F32::div(ONE, 0, RoundingMode::Rne, fl)),
+            (F32::INFINITY, Flags::DZ)
+        );
+        assert_eq!(
+            op(|fl| F32::div(0, 0, RoundingMode::Rne, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F32::sqrt(ONE | F32::SIGN, RoundingMode::Rne, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F32::add(MAX, MAX, RoundingMode::Rne, fl)),
+            (F32::INFINITY, Flags::OF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::add(MAX, MAX, RoundingMode::Rtz, fl)),
+            (MAX, Flags::OF | Flags::NX)
+        );
+        // 2^-126 * (1 - 2^-26) rounds up to the smallest normal, which is not tiny after rounding
+        assert_eq!(
+            op(|fl| F32::mul(0x0080_0400, 0x3f7f_f800, RoundingMode::Rne, fl)),
+            (0x0080_0000, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::mul(0x0080_0400, 0x3f7f_f800, RoundingMode::Rtz, fl)),
+            (0x007f_ffff, Flags::UF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::mul_add(F32::INFINITY, 0, F32::CANONICAL_NAN, RoundingMode::Rne, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+    }
+
+    #[test]
+    fn signed_zero() {
+        assert_eq!(
+            op(|fl| F32::sub(ONE, ONE, RoundingMode::Rne, fl)),
+            (0, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::sub(ONE, ONE, RoundingMode::Rdn, fl)),
+            (NEG_ZERO, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::min(0, NEG_ZERO, fl)),
+            (NEG_ZERO, Flags::default())
+        );
+        assert_eq!(op(|fl| F32::max(NEG_ZERO, 0, fl)), (0, Flags::default()));
+    }
+
+    #[test]
+    fn nan() {
+        assert_eq!(
+            op(|fl| F32::add(SNAN, ONE, RoundingMode::Rne, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+        assert_eq!(op(|fl| F32::min(SNAN, ONE, fl)), (ONE, Flags::NV));
+        assert_eq!(
+            op(|fl| F32::max(F32::CANONICAL_NAN, F32::CANONICAL_NAN, fl)),
+            (F32::CANONICAL_NAN, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::eq(F32::CANONICAL_NAN, ONE, fl) as u64),
+            (0, Flags::default())
+        );
+        assert_eq!(op(|fl| F32::eq(SNAN, ONE, fl) as u64), (0, Flags::NV));
+        assert_eq!(
+            op(|fl| F32::le(F32::CANONICAL_NAN, ONE, fl) as u64),
+            (0, Flags::NV)
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let two_and_half = 0x4020_0000;
+        for (rm, expected) in [
+            (RoundingMode::Rne, 2),
+            (RoundingMode::Rtz, 2),
+            (RoundingMode::Rdn, 2),
+            (RoundingMode::Rup, 3),
+            (RoundingMode::Rmm, 3),
+        ] {
+            assert_eq!(
+                op(|fl| F32::to_int(two_and_half, IntFormat::I32, rm, fl)),
+                (expected, Flags::NX)
+            );
+        }
+        assert_eq!(
+            op(|fl| F32::to_int(F32::CANONICAL_NAN, IntFormat::I32, RoundingMode::Rne, fl)),
+            (i32::MAX as u64, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F32::to_int(ONE | F32::SIGN, IntFormat::U32, RoundingMode::Rne, fl)),
+            (0, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F32::to_int(0xbf00_0000, IntFormat::U32, RoundingMode::Rtz, fl)),
+            (0, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::from_int(u64::MAX, IntFormat::I64, RoundingMode::Rne, fl)),
+            (ONE | F32::SIGN, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::from_int(0x0100_0001, IntFormat::U32, RoundingMode::Rne, fl)),
+            (0x4b80_0000, Flags::NX)
+        );
+    }
+
+    #[test]
+    fn double() {
+        const DOUBLE_ONE: u64 = 0x3ff0_0000_0000_0000;
+        assert_eq!(
+            op(|fl| F64::add(DOUBLE_ONE, DOUBLE_ONE, RoundingMode::Rne, fl)),
+            (0x4000_0000_0000_0000, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F64::div(DOUBLE_ONE, 0x4008_0000_0000_0000, RoundingMode::Rne, fl)),
+            (0x3fd5_5555_5555_5555, Flags::NX)
+        );
+        assert_eq!(F64::unbox(0x3ff0_0000), 0x3ff0_0000);
+        assert_eq!(F64::classify(F64::CANONICAL_NAN), 1 << 9);
+        assert_eq!(F64::CANONICAL_NAN, 0x7ff8_0000_0000_0000);
+    }
+
+    #[test]
+    fn format_conversions() {
+        assert_eq!(
+            op(|fl| F64::convert::<F32>(ONE, RoundingMode::Rne, fl)),
+            (0x3ff0_0000_0000_0000, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F64::convert::<F32>(SNAN, RoundingMode::Rne, fl)),
+            (F64::CANONICAL_NAN, Flags::NV)
+        );
+        // 1 + 2^-52 does not fit in a single
+        assert_eq!(
+            op(|fl| F32::convert::<F64>(0x3ff0_0000_0000_0001, RoundingMode::Rup, fl)),
+            (0x3f80_0001, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::convert::<F64>(0x47ef_ffff_f000_0000, RoundingMode::Rne, fl)),
+            (F32::INFINITY, Flags::OF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::convert::<F64>(0x3680_0000_0000_0000, RoundingMode::Rne, fl)),
+            (0, Flags::UF | Flags::NX)
+        );
+    }
+
+    #[test]
+    fn half() {
+        assert_eq!(
+            op(|fl| F16::add(0x7bff, 0x4c00, RoundingMode::Rne, fl)),
+            (F16::INFINITY, Flags::OF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F16::mul(0x0001, 0x3800, RoundingMode::Rne, fl)),
+            (0, Flags::UF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F16::mul(0x0001, 0x3800, RoundingMode::Rup, fl)),
+            (0x0001, Flags::UF | Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F16::convert::<F32>(0x3dcc_cccd, RoundingMode::Rne, fl)),
+            (0x2e66, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F64::convert::<F16>(0x7c01, RoundingMode::Rne, fl)),
+            (F64::CANONICAL_NAN, Flags::NV)
+        );
+        assert_eq!(F16::CANONICAL_NAN, 0x7e00);
+        assert_eq!(F16::unbox(0xffff_ffff_3f80_0000), F16::CANONICAL_NAN);
+    }
+
+    #[test]
+    fn classify() {
+        assert_eq!(F32::classify(F32::INFINITY | F32::SIGN), 1 << 0);
+        assert_eq!(F32::classify(ONE | F32::SIGN), 1 << 1);
+        assert_eq!(F32::classify(0x8000_0001), 1 << 2);
+        assert_eq!(F32::classify(NEG_ZERO), 1 << 3);
+        assert_eq!(F32::classify(0), 1 << 4);
+        assert_eq!(F32::classify(1), 1 << 5);
+        assert_eq!(F32::classify(ONE), 1 << 6);
+        assert_eq!(F32::classify(F32::INFINITY), 1 << 7);
+        assert_eq!(F32::classify(SNAN), 1 << 8);
+        assert_eq!(F32::classify(F32::CANONICAL_NAN), 1 << 9);
+    }
+
+    #[test]
+    fn nan_boxing() {
+        assert_eq!(F32::nan_box(ONE), 0xffff_ffff_3f80_0000);
+        assert_eq!(F32::unbox(0xffff_ffff_3f80_0000), ONE);
+        assert_eq!(F32::unbox(0xffff_fffe_3f80_0000), F32::CANONICAL_NAN);
+        assert_eq!(F32::unbox(ONE), F32::CANONICAL_NAN);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(F32::constant(0), ONE | F32::SIGN);
+        assert_eq!(F32::constant(1), 0x0080_0000);
+        assert_eq!(F32::constant(2), 0x3780_0000);
+        assert_eq!(F32::constant(9), 0x3ea0_0000);
+        assert_eq!(F32::constant(16), ONE);
+        assert_eq!(F32::constant(22), 0x4040_0000);
+        assert_eq!(F32::constant(29), 0x4780_0000);
+        assert_eq!(F32::constant(30), F32::INFINITY);
+        assert_eq!(F32::constant(31), F32::CANONICAL_NAN);
+        assert_eq!(F64::constant(0), 0xbff0_0000_0000_0000);
+        assert_eq!(F64::constant(1), 0x0010_0000_0000_0000);
+        assert_eq!(F64::constant(15), 0x3fec_0000_0000_0000);
+        // the smallest entries are subnormal and the largest one overflows in half precision
+        assert_eq!(F16::constant(1), 0x0400);
+        assert_eq!(F16::constant(2), 0x0100);
+        assert_eq!(F16::constant(3), 0x0200);
+        assert_eq!(F16::constant(28), 0x7800);
+        assert_eq!(F16::constant(29), F16::INFINITY);
+    }
+
+    #[test]
+    fn rounding_to_integer() {
+        const TWO_AND_HALF: u64 = 0x4020_0000;
+        assert_eq!(
+            op(|fl| F32::round_to_int(TWO_AND_HALF, RoundingMode::Rne, false, fl)),
+            (TWO, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(TWO_AND_HALF, RoundingMode::Rne, true, fl)),
+            (TWO, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(TWO_AND_HALF, RoundingMode::Rup, true, fl)),
+            (0x4040_0000, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(0xbf00_0000, RoundingMode::Rne, false, fl)),
+            (NEG_ZERO, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(0xbf00_0000, RoundingMode::Rmm, false, fl)),
+            (ONE | F32::SIGN, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(MAX, RoundingMode::Rne, true, fl)),
+            (MAX, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(1, RoundingMode::Rup, true, fl)),
+            (ONE, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F32::round_to_int(SNAN, RoundingMode::Rne, false, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+    }
+
+    #[test]
+    fn zfa_min_max_and_comparisons() {
+        let qnan = F32::CANONICAL_NAN;
+        assert_eq!(op(|fl| F32::min(ONE, qnan, fl)), (ONE, Flags::default()));
+        assert_eq!(
+            op(|fl| F32::minm(ONE, qnan, fl)),
+            (F32::CANONICAL_NAN, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F32::maxm(SNAN, ONE, fl)),
+            (F32::CANONICAL_NAN, Flags::NV)
+        );
+        assert_eq!(op(|fl| F32::maxm(NEG_ZERO, 0, fl)), (0, Flags::default()));
+        assert_eq!(
+            op(|fl| F32::ltq(qnan, ONE, fl) as u64),
+            (0, Flags::default())
+        );
+        assert_eq!(op(|fl| F32::lt(qnan, ONE, fl) as u64), (0, Flags::NV));
+        assert_eq!(op(|fl| F32::leq(SNAN, ONE, fl) as u64), (0, Flags::NV));
+        assert_eq!(
+            op(|fl| F32::leq(ONE, ONE, fl) as u64),
+            (1, Flags::default())
+        );
+    }
+
+    #[test]
+    fn modular_conversion() {
+        assert_eq!(
+            op(|fl| F64::to_int_modular(0x400c_0000_0000_0000, fl)),
+            (3, Flags::NX)
+        );
+        assert_eq!(
+            op(|fl| F64::to_int_modular(0xbff8_0000_0000_0000, fl)),
+            (u64::MAX, Flags::NX)
+        );
+        // 2^32 + 5
+        assert_eq!(
+            op(|fl| F64::to_int_modular(0x41f0_0000_0050_0000, fl)),
+            (5, Flags::NV)
+        );
+        // 2^31
+        assert_eq!(
+            op(|fl| F64::to_int_modular(0x41e0_0000_0000_0000, fl)),
+            (0xffff_ffff_8000_0000, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F64::to_int_modular(0xc1e0_0000_0000_0000, fl)),
+            (0xffff_ffff_8000_0000, Flags::default())
+        );
+        assert_eq!(
+            op(|fl| F64::to_int_modular(F64::INFINITY, fl)),
+            (0, Flags::NV)
+        );
+        assert_eq!(
+            op(|fl| F64::to_int_modular(F64::CANONICAL_NAN, fl)),
+            (0, Flags::NV)
+        );
+    }
+
+    #[test]
+    fn dynamic_rounding_mode() {
+        let mut fcsr = Fcsr::default();
+        let dynamic = U3::new_truncate(0b111);
+        assert_eq!(fcsr.rounding_mode(dynamic), Ok(RoundingMode::Rne));
+        fcsr.frm = 0b011;
+        assert_eq!(fcsr.rounding_mode(dynamic), Ok(RoundingMode::Rup));
+        fcsr.frm = 0b101;
+        assert_eq!(fcsr.rounding_mode(dynamic), Err(Error::InvalidOpCode));
+        assert_eq!(
+            fcsr.rounding_mode(U3::new_truncate(0b110)),
+            Err(Error::InvalidOpCode)
+        );
+    }
+}
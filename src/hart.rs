@@ -0,0 +1,139 @@
+use crate::{
+    cfi::Cfi,
+    counters::Counters,
+    csr::Csrs,
+    float::Fcsr,
+    num::{As, Zero},
+    pmp::Pmp,
+    registers::{FRegisters, Registers},
+    vector::Vector,
+};
+
+const DEFAULT_CACHE_BLOCK: usize = 64;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Privilege {
+    User = 0,
+    Supervisor = 1,
+    Machine = 3,
+}
+
+/// Reservation set registered by a load-reserved and consumed by the next store-conditional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reservation {
+    pub addr: usize,
+    pub size: usize,
+}
+
+#[derive(Debug)]
+pub struct Hart<T> {
+    pub regs: Registers<T>,
+    pub pc: T,
+    pub reservation: Option<Reservation>,
+    /// Floating-point register file, Zfinx harts have none.
+    pub fregs: Option<FRegisters>,
+    pub fcsr: Fcsr,
+    pub privilege: Privilege,
+    pub csrs: Csrs<T>,
+    pub counters: Counters,
+    pub vector: Vector,
+    /// Size in bytes of the blocks operated on by the CBO instructions.
+    pub cache_block: usize,
+    pub cfi: Cfi<T>,
+    /// Address translation and protection, the `satp` CSR.
+    pub satp: T,
+    pub pmp: Pmp,
+}
+
+impl<T> Hart<T>
+where
+    T: Copy + Default + Zero + core::ops::Not<Output = T> + As<u8> + As<u64>,
+    u8: As<T>,
+    u64: As<T>,
+{
+    #[inline]
+    pub fn new(pc: T) -> Self {
+        Self {
+            regs: Registers::new(),
+            pc,
+            reservation: None,
+            fregs: Some(FRegisters::new()),
+            fcsr: Fcsr::default(),
+            privilege: Privilege::Machine,
+            csrs: Csrs::new(),
+            counters: Counters::default(),
+            vector: Vector::default(),
+            cache_block: DEFAULT_CACHE_BLOCK,
+            cfi: Cfi::default(),
+            satp: T::zero(),
+            pmp: Pmp::NONE,
+        }
+    }
+
+    /// Hart of the RV32E and RV64E bases, instructions naming `x16` to `x31` are illegal.
+    #[inline]
+    pub fn embedded(pc: T) -> Self {
+        Self {
+            regs: Registers::embedded(),
+            ..Self::new(pc)
+        }
+    }
+
+    /// Enables Zfinx: floating-point instructions operate on the integer registers, doubles on
+    /// even/odd register pairs on RV32, and the FP load, store and move instructions are illegal.
+    #[inline]
+    pub fn with_zfinx(self) -> Self {
+        Self {
+            fregs: None,
+            ..self
+        }
+    }
+
+    /// Sets the cache-block size, a power of two.
+    #[inline]
+    pub fn with_cache_block(self, size: usize) -> Self {
+        assert!(
+            size.is_power_of_two(),
+            "cache block size is not a power of two"
+        );
+        Self {
+            cache_block: size,
+            ..self
+        }
+    }
+
+    /// Implements `entries` PMP entries, 16 or 64. Without any, PMP allows all the accesses.
+    #[inline]
+    pub fn with_pmp(self, entries: usize) -> Self {
+        assert!(
+            matches!(entries, 0 | 16 | 64),
+            "unsupported number of PMP entries"
+        );
+        Self {
+            pmp: Pmp::new(entries),
+            ..self
+        }
+    }
+
+    /// Replaces the vector unit with one of `vlen` bits registers and `elen` bits elements.
+    #[inline]
+    pub fn with_vector(self, vlen: usize, elen: u32) -> Self {
+        Self {
+            vector: Vector::new(vlen, elen),
+            ..self
+        }
+    }
+}
+
+impl<T> Default for Hart<T>
+where
+    T: Copy + Default + Zero + core::ops::Not<Output = T> + As<u8> + As<u64>,
+    u8: As<T>,
+    u64: As<T>,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
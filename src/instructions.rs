@@ -1,12 +1,15 @@
 use crate::{
-    decode::{Shift, B, I, J, R, S, U, U10, U12, U3, U5},
+    cfi::ShadowStack,
+    decode::{Amo, Shift, VMem, B, I, J, R, R4, S, U, U10, U12, U2, U3, U5, U7, V},
     error::Error,
+    float::Fcsr,
+    hart::{Hart, Privilege, Reservation},
+    mmu::{Access, Mmu},
     num::{As, Bitcast, One, Unsigned, Zero},
-    registers::{Registers, ZeroOrRegister},
+    registers::{FRegisters, Registers, ZeroOrRegister},
+    vector::Vector,
 };
 
-const OPCODE_SIZE: u8 = 4;
-
 macro_rules! def_uconst {
     ($($(#[$meta:meta])* $v:vis const $name:ident: $t:ty = $n:expr;)*) => {
         $(
@@ -32,6 +35,58 @@ def_uconst! {
     const SRA: U10 = 0b0100000_101;
     const OR: U10 = 0b0000000_110;
     const AND: U10 = 0b0000000_111;
+    const MUL: U10 = 0b0000001_000;
+    const MULH: U10 = 0b0000001_001;
+    const MULHSU: U10 = 0b0000001_010;
+    const MULHU: U10 = 0b0000001_011;
+    const DIV: U10 = 0b0000001_100;
+    const DIVU: U10 = 0b0000001_101;
+    const REM: U10 = 0b0000001_110;
+    const REMU: U10 = 0b0000001_111;
+    const SH1ADD: U10 = 0b0010000_010;
+    const SH2ADD: U10 = 0b0010000_100;
+    const SH3ADD: U10 = 0b0010000_110;
+    const ANDN: U10 = 0b0100000_111;
+    const ORN: U10 = 0b0100000_110;
+    const XNOR: U10 = 0b0100000_100;
+    const MAX: U10 = 0b0000101_110;
+    const MAXU: U10 = 0b0000101_111;
+    const MIN: U10 = 0b0000101_100;
+    const MINU: U10 = 0b0000101_101;
+    const ROL: U10 = 0b0110000_001;
+    const ROR: U10 = 0b0110000_101;
+    const ZEXT_H: U10 = 0b0000100_100;
+    const CLMUL: U10 = 0b0000101_001;
+    const CLMULH: U10 = 0b0000101_011;
+    const CLMULR: U10 = 0b0000101_010;
+    const BCLR: U10 = 0b0100100_001;
+    const BEXT: U10 = 0b0100100_101;
+    const BINV: U10 = 0b0110100_001;
+    const BSET: U10 = 0b0010100_001;
+    const CZERO_EQZ: U10 = 0b0000111_101;
+    const CZERO_NEZ: U10 = 0b0000111_111;
+    const PACK: U10 = 0b0000100_100;
+    const PACKH: U10 = 0b0000100_111;
+    const XPERM4: U10 = 0b0010100_010;
+    const XPERM8: U10 = 0b0010100_100;
+    /// Byte-select instructions, compared after [`without_bs`].
+    const AES32ESI: U10 = 0b0010001_000;
+    const AES32ESMI: U10 = 0b0010011_000;
+    const AES32DSI: U10 = 0b0010101_000;
+    const AES32DSMI: U10 = 0b0010111_000;
+    const SM4ED: U10 = 0b0011000_000;
+    const SM4KS: U10 = 0b0011010_000;
+    const SHA512SUM0R: U10 = 0b0101000_000;
+    const SHA512SUM1R: U10 = 0b0101001_000;
+    const SHA512SIG0L: U10 = 0b0101010_000;
+    const SHA512SIG1L: U10 = 0b0101011_000;
+    const SHA512SIG0H: U10 = 0b0101110_000;
+    const SHA512SIG1H: U10 = 0b0101111_000;
+    const AES64ES: U10 = 0b0011001_000;
+    const AES64ESM: U10 = 0b0011011_000;
+    const AES64DS: U10 = 0b0011101_000;
+    const AES64DSM: U10 = 0b0011111_000;
+    const AES64KS2: U10 = 0b0111111_000;
     const ADDI: U3 = 0b000;
     const SLTI: U3 = 0b010;
     const SLTIU: U3 = 0b011;
@@ -45,13 +100,32 @@ def_uconst! {
     const LW: U3 = 0b010;
     const LWU: U3 = 0b110;
     const LD: U3 = 0b011;
+    const LDU: U3 = 0b111;
+    const LQ: U3 = 0b010;
     const SLLI: U10 = 0b0000000_001;
     const SRLI: U10 = 0b0000000_101;
     const SRAI: U10 = 0b0100000_101;
+    const RORI: U10 = 0b0110000_101;
+    /// CLZ, CTZ, CPOP, SEXT.B and SEXT.H, selected by the shift amount field.
+    const UNARY: U10 = 0b0110000_001;
+    const REV8: U10 = 0b0110100_101;
+    const ORC_B: U10 = 0b0010100_101;
+    const BCLRI: U10 = 0b0100100_001;
+    const BEXTI: U10 = 0b0100100_101;
+    const BINVI: U10 = 0b0110100_001;
+    const BSETI: U10 = 0b0010100_001;
+    const BREV8: U10 = 0b0110100_101;
+    const ZIP: U10 = 0b0000100_001;
+    const UNZIP: U10 = 0b0000100_101;
+    /// SHA-256, SHA-512 and SM3 functions, selected by the shift amount field.
+    const SHA: U10 = 0b0001000_001;
+    /// AES64IM and AES64KS1I, selected by the shift amount field.
+    const AES64: U10 = 0b0011000_001;
     const SB: U3 = 0b000;
     const SH: U3 = 0b001;
     const SW: U3 = 0b010;
     const SD: U3 = 0b011;
+    const SQ: U3 = 0b100;
     const BEQ: U3 = 0b000;
     const BNE: U3 = 0b001;
     const BLT: U3 = 0b100;
@@ -64,12 +138,116 @@ def_uconst! {
     const SLLIW: U10 = 0b0000000_001;
     const SRLIW: U10 = 0b0000000_101;
     const SRAIW: U10 = 0b0100000_101;
+    const SLLI_UW: U10 = 0b0000100_001;
+    const RORIW: U10 = 0b0110000_101;
 
     const ADDW: U10 = 0b0000000_000;
     const SUBW: U10 = 0b0100000_000;
     const SLLW: U10 = 0b0000000_001;
     const SRLW: U10 = 0b0000000_101;
     const SRAW: U10 = 0b0100000_101;
+    const MULW: U10 = 0b0000001_000;
+    const DIVW: U10 = 0b0000001_100;
+    const DIVUW: U10 = 0b0000001_101;
+    const REMW: U10 = 0b0000001_110;
+    const REMUW: U10 = 0b0000001_111;
+    const ADD_UW: U10 = 0b0000100_000;
+    const SH1ADD_UW: U10 = 0b0010000_010;
+    const SH2ADD_UW: U10 = 0b0010000_100;
+    const SH3ADD_UW: U10 = 0b0010000_110;
+    const ROLW: U10 = 0b0110000_001;
+    const RORW: U10 = 0b0110000_101;
+    const PACKW: U10 = 0b0000100_100;
+
+    const ADDID: U3 = 0b000;
+
+    const SLLID: U10 = 0b0000000_001;
+    const SRLID: U10 = 0b0000000_101;
+    const SRAID: U10 = 0b0100000_101;
+
+    const ADDD: U10 = 0b0000000_000;
+    const SUBD: U10 = 0b0100000_000;
+    const SLLD: U10 = 0b0000000_001;
+    const SRLD: U10 = 0b0000000_101;
+    const SRAD: U10 = 0b0100000_101;
+
+    const LR_W: U10 = 0b00010_010;
+    const SC_W: U10 = 0b00011_010;
+    const AMOSWAP_W: U10 = 0b00001_010;
+    const AMOADD_W: U10 = 0b00000_010;
+    const AMOXOR_W: U10 = 0b00100_010;
+    const AMOAND_W: U10 = 0b01100_010;
+    const AMOOR_W: U10 = 0b01000_010;
+    const AMOMIN_W: U10 = 0b10000_010;
+    const AMOMAX_W: U10 = 0b10100_010;
+    const AMOMINU_W: U10 = 0b11000_010;
+    const AMOMAXU_W: U10 = 0b11100_010;
+    const LR_D: U10 = 0b00010_011;
+    const SC_D: U10 = 0b00011_011;
+    const AMOSWAP_D: U10 = 0b00001_011;
+    const AMOADD_D: U10 = 0b00000_011;
+    const AMOXOR_D: U10 = 0b00100_011;
+    const AMOAND_D: U10 = 0b01100_011;
+    const AMOOR_D: U10 = 0b01000_011;
+    const AMOMIN_D: U10 = 0b10000_011;
+    const AMOMAX_D: U10 = 0b10100_011;
+    const AMOMINU_D: U10 = 0b11000_011;
+    const AMOMAXU_D: U10 = 0b11100_011;
+
+    const FMT_S: U2 = 0b00;
+    const FMT_D: U2 = 0b01;
+    const FMT_H: U2 = 0b10;
+
+    const FLH: U3 = 0b001;
+    const FLW: U3 = 0b010;
+    const FLD: U3 = 0b011;
+    const FSH: U3 = 0b001;
+    const FSW: U3 = 0b010;
+    const FSD: U3 = 0b011;
+
+    const FADD: U5 = 0b00000;
+    const FSUB: U5 = 0b00001;
+    const FMUL: U5 = 0b00010;
+    const FDIV: U5 = 0b00011;
+    const FSQRT: U5 = 0b01011;
+    const FSGNJ: U5 = 0b00100;
+    const FCVT_FMT_FMT: U5 = 0b01000;
+    const FMINMAX: U5 = 0b00101;
+    const FCMP: U5 = 0b10100;
+    const FCVT_INT_FMT: U5 = 0b11000;
+    const FCVT_FMT_INT: U5 = 0b11010;
+    const FMV_INT_FMT: U5 = 0b11100;
+    const FMV_FMT_INT: U5 = 0b11110;
+    const FMVP: U5 = 0b10110;
+
+    const CSRRW: U3 = 0b001;
+    const CSRRS: U3 = 0b010;
+    const CSRRC: U3 = 0b011;
+    const CSRRWI: U3 = 0b101;
+    const CSRRSI: U3 = 0b110;
+    const CSRRCI: U3 = 0b111;
+
+    const PRIV: U3 = 0b000;
+    const ECALL: U12 = 0b0000000_00000;
+    const EBREAK: U12 = 0b0000000_00001;
+    const SRET: U12 = 0b0001000_00010;
+    const MRET: U12 = 0b0011000_00010;
+    const WFI: U12 = 0b0001000_00101;
+    const SFENCE_VMA: U7 = 0b0001001;
+
+    const FENCE: U3 = 0b000;
+    const FENCE_I: U3 = 0b001;
+    const CBO: U3 = 0b010;
+
+    const CBO_INVAL: U12 = 0b0000_0000_0000;
+    const CBO_CLEAN: U12 = 0b0000_0000_0001;
+    const CBO_FLUSH: U12 = 0b0000_0000_0010;
+    const CBO_ZERO: U12 = 0b0000_0000_0100;
+
+    const SSPUSH: U7 = 0b1100111;
+    const SSPOPCHK: U7 = 0b1100110;
+    const SSAMOSWAP_W: U10 = 0b01001_010;
+    const SSAMOSWAP_D: U10 = 0b01001_011;
 }
 
 pub trait MathW: Sized {
@@ -84,6 +262,18 @@ pub trait MathIW: Sized {
     fn mathiw(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error>;
 }
 
+pub trait MathD: Sized {
+    fn mathd(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error>;
+}
+
+pub trait ShiftID: Sized {
+    fn shiftid(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error>;
+}
+
+pub trait MathID: Sized {
+    fn mathid(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error>;
+}
+
 pub trait Math: Sized {
     fn math(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error>;
 }
@@ -104,32 +294,245 @@ pub trait Auipc: Sized {
     fn auipc(instruction: U, regs: &mut Registers<Self>, pc: Self) -> Result<(), Error>;
 }
 
+/// The memory accesses translate their effective address with `mmu`, which also applies the
+/// pointer masking of the privilege mode they are performed in.
 pub trait Load: Sized {
-    fn load(instruction: I, regs: &mut Registers<Self>, memory: &[u8]) -> Result<(), Error>;
+    fn load(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error>;
+}
+
+/// LQ is encoded in the MISC-MEM major opcode, next to the fences.
+pub trait LoadQ: Sized {
+    fn loadq(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error>;
 }
 
 pub trait Store: Sized {
-    fn store(instruction: S, regs: &mut Registers<Self>, memory: &mut [u8]) -> Result<(), Error>;
+    fn store(
+        instruction: S,
+        regs: &mut Registers<Self>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+pub trait Atomic: Sized {
+    fn atomic(
+        instruction: Amo,
+        regs: &mut Registers<Self>,
+        reservation: &mut Option<Reservation>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+/// The floating-point traits take no FP register file on Zfinx harts, whose floating-point
+/// operands live in the integer registers.
+pub trait FloatLoad: Sized {
+    fn float_load(
+        instruction: I,
+        regs: &Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error>;
+}
+
+pub trait FloatStore: Sized {
+    fn float_store(
+        instruction: S,
+        regs: &Registers<Self>,
+        fregs: Option<&FRegisters>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+pub trait FloatMath: Sized {
+    fn float_math(
+        instruction: R,
+        regs: &mut Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error>;
+}
+
+pub trait FloatMulAdd: Sized {
+    fn float_mul_add(
+        instruction: R4,
+        negate_product: bool,
+        negate_addend: bool,
+        regs: &mut Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error>;
+}
+
+pub trait VectorConfig: Sized {
+    fn vector_config(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        vector: &mut Vector,
+    ) -> Result<(), Error>;
+}
+
+pub trait VectorMath: Sized {
+    fn vector_math(
+        instruction: V,
+        regs: &mut Registers<Self>,
+        vector: &mut Vector,
+    ) -> Result<(), Error>;
+}
+
+pub trait VectorLoad: Sized {
+    fn vector_load(
+        instruction: VMem,
+        regs: &Registers<Self>,
+        vector: &mut Vector,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error>;
+}
+
+pub trait VectorStore: Sized {
+    fn vector_store(
+        instruction: VMem,
+        regs: &Registers<Self>,
+        vector: &mut Vector,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+pub trait Fence: Sized {
+    fn fence(instruction: crate::decode::Fence) -> Result<(), Error>;
+}
+
+/// `menvcfg` and `senvcfg` fields enabling cbo.inval, cbo.clean and cbo.flush, and cbo.zero below
+/// M-mode. Without caches the two non-zero CBIE encodings, flush and invalidate, behave the same.
+pub const CBIE: u64 = 0b11 << 4;
+pub const CBCFE: u64 = 1 << 6;
+pub const CBZE: u64 = 1 << 7;
+
+/// `block` is the size in bytes of a cache block, a power of two. The instructions operate on the
+/// whole block holding the address in `rs1`, so they are never misaligned.
+pub trait CacheBlock: Sized {
+    fn cache_block(
+        instruction: I,
+        regs: &Registers<Self>,
+        block: usize,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+/// May-be-operations only write zero to `rd`, the shadow stack instructions take over some of
+/// their encodings when `shadow_stack` is given.
+pub trait Mop: Sized {
+    fn mop(
+        instruction: R,
+        regs: &mut Registers<Self>,
+        shadow_stack: Option<ShadowStack<Self>>,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
+}
+
+/// `shadow_stack` is only given when the shadow stack is accessible, the instruction is illegal
+/// otherwise.
+pub trait ShadowStackSwap: Sized {
+    fn ssamoswap(
+        instruction: Amo,
+        regs: &mut Registers<Self>,
+        shadow_stack: Option<ShadowStack<Self>>,
+        memory: &mut [u8],
+    ) -> Result<(), Error>;
 }
 
+pub trait Csr: Sized {
+    fn csr(instruction: I, hart: &mut Hart<Self>) -> Result<(), Error>;
+}
+
+/// ECALL, EBREAK, WFI, SFENCE.VMA and the trap returns, they update the pc of the hart themselves
+/// and `size` is the length in bytes of the executing instruction.
+pub trait Privileged: Sized {
+    fn privileged(instruction: I, hart: &mut Hart<Self>, size: u8) -> Result<(), Error>;
+}
+
+/// `size` is the length in bytes of the executing instruction, 2 for compressed encodings.
+/// Without `compressed`, IALIGN is 32 and a target that is not four-byte aligned is misaligned.
 pub trait Jal: Sized {
-    fn jal(instruction: J, regs: &mut Registers<Self>, pc: &mut Self) -> Result<(), Error>;
+    fn jal(
+        instruction: J,
+        regs: &mut Registers<Self>,
+        pc: &mut Self,
+        size: u8,
+        compressed: bool,
+    ) -> Result<(), Error>;
 }
 
+/// `size` is the length in bytes of the executing instruction, 2 for compressed encodings.
+/// Without `compressed`, IALIGN is 32 and a target that is not four-byte aligned is misaligned.
+/// `elp` is given when landing pads are enforced: jumps that are neither returns nor
+/// software-guarded through `x7` expect a landing pad at their target.
 pub trait Jalr: Sized {
-    fn jalr(instruction: I, regs: &mut Registers<Self>, pc: &mut Self) -> Result<(), Error>;
+    fn jalr(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        pc: &mut Self,
+        size: u8,
+        compressed: bool,
+        elp: Option<&mut bool>,
+    ) -> Result<(), Error>;
 }
 
+/// `size` is the length in bytes of the executing instruction, 2 for compressed encodings.
+/// Without `compressed`, IALIGN is 32 and a taken branch to a target that is not four-byte
+/// aligned is misaligned.
 pub trait Branch: Sized {
-    fn branch(instruction: B, regs: &mut Registers<Self>, pc: &mut Self) -> Result<(), Error>;
+    fn branch(
+        instruction: B,
+        regs: &mut Registers<Self>,
+        pc: &mut Self,
+        size: u8,
+        compressed: bool,
+    ) -> Result<(), Error>;
+}
+
+/// Drops the byte select in `funct7[6:5]` of the AES32 and SM4 instructions.
+#[inline(always)]
+#[allow(clippy::unusual_byte_groupings)]
+const fn without_bs(id: U10) -> U10 {
+    U10::new_truncate(id.as_u16() & 0b0011111_111)
+}
+
+/// Instantiates the byte-select operation `$f` for the byte select `$bs`.
+macro_rules! byte_select {
+    ($bs:expr, $($f:ident)::+) => {
+        match $bs {
+            0 => $($f)::+::<0>,
+            1 => $($f)::+::<1>,
+            2 => $($f)::+::<2>,
+            _ => $($f)::+::<3>,
+        }
+    };
 }
 
+/// `|name|` binds the decoded instruction for the extension arms.
 macro_rules! impl_math {
-    ($t:ty $({ $($tt:tt)* })?) => {
+    ($t:ty $(|$instruction:ident|)? $({ $($tt:tt)* })?) => {
         impl Math for $t {
             #[inline(always)]
             fn math(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error> {
                 use crate::ops;
+                $(let $instruction = instruction;)?
 
                 #[deny(unreachable_patterns)]
                 let f: fn(Self, Self) -> Self = match instruction.id() {
@@ -146,16 +549,58 @@ macro_rules! impl_math {
                     SRA => ops::Sra::sra,
                     OR => ops::Or::or,
                     AND => ops::And::and,
+                    MUL => ops::Mul::mul,
+                    MULH => ops::Mulh::mulh,
+                    MULHSU => ops::Mulhsu::mulhsu,
+                    MULHU => ops::Mulhu::mulhu,
+                    DIV => ops::Div::div,
+                    DIVU => ops::Divu::divu,
+                    REM => ops::Rem::rem,
+                    REMU => ops::Remu::remu,
+                    SH1ADD => ops::Sh1add::sh1add,
+                    SH2ADD => ops::Sh2add::sh2add,
+                    SH3ADD => ops::Sh3add::sh3add,
+                    ANDN => ops::Andn::andn,
+                    ORN => ops::Orn::orn,
+                    XNOR => ops::Xnor::xnor,
+                    MAX => ops::Max::max,
+                    MAXU => ops::Maxu::maxu,
+                    MIN => ops::Min::min,
+                    MINU => ops::Minu::minu,
+                    ROL => ops::Rol::rol,
+                    ROR => ops::Ror::ror,
+                    // RV64 encodes ZEXT.H in OP-32
+                    ZEXT_H if <$t>::BITS == 32 && instruction.rs2.as_u8() == 0 => {
+                        |src1, _| ops::ZextH::zext_h(src1)
+                    }
+                    CLMUL => ops::Clmul::clmul,
+                    CLMULH => ops::Clmulh::clmulh,
+                    CLMULR => ops::Clmulr::clmulr,
+                    BCLR => ops::Bclr::bclr,
+                    BEXT => ops::Bext::bext,
+                    BINV => ops::Binv::binv,
+                    BSET => ops::Bset::bset,
+                    CZERO_EQZ => ops::CzeroEqz::czero_eqz,
+                    CZERO_NEZ => ops::CzeroNez::czero_nez,
+                    PACK => ops::Pack::pack,
+                    PACKH => ops::Packh::packh,
+                    XPERM4 => ops::Xperm4::xperm4,
+                    XPERM8 => ops::Xperm8::xperm8,
+                    id if without_bs(id) == SM4ED => {
+                        byte_select!(instruction.funct7.as_u8() >> 5, ops::Sm4ed::sm4ed)
+                    }
+                    id if without_bs(id) == SM4KS => {
+                        byte_select!(instruction.funct7.as_u8() >> 5, ops::Sm4ks::sm4ks)
+                    }
                     $($($tt)*)?
                     _ => return Err(Error::InvalidOpCode),
                 };
 
-                let ZeroOrRegister::Register(reg) = instruction.rd.into() else {
-                    return Err(Error::InvalidOpCode);
-                };
-                let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-                let src2 = ZeroOrRegister::from_u5(instruction.rs2).fetch(regs);
-                *regs.get_mut(reg) = f(src1, src2);
+                let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+                let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+                if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+                    *regs.get_mut(reg) = f(src1, src2);
+                }
                 Ok(())
             }
         }
@@ -184,41 +629,69 @@ macro_rules! impl_mathi {
                     _ => return Err(Error::InvalidOpCode),
                 };
 
-                let ZeroOrRegister::Register(reg) = instruction.rd.into() else {
-                    return Err(Error::InvalidOpCode);
-                };
-                let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-                *regs.get_mut(reg) = f(src1, instruction.imm);
+                let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+                if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+                    *regs.get_mut(reg) = f(src1, instruction.imm);
+                }
                 Ok(())
             }
         }
     };
 }
 
+/// `|name|` binds the shift amount for the extension arms.
 macro_rules! impl_shifti {
-    ($t:ty $({ $($tt:tt)* })?) => {
+    ($t:ty $(|$shamt:ident|)? $({ $($tt:tt)* })?) => {
         impl ShiftI for $t {
             #[inline(always)]
             fn shifti(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
                 use crate::ops;
 
+                let (id, shamt) = instruction.split(<$t>::BITS.trailing_zeros());
+                $(let $shamt = shamt;)?
+
                 #[deny(unreachable_patterns)]
-                let f: fn(Self, U5) -> Self = match instruction.id() {
+                let f: fn(Self, U7) -> Self = match id {
                     x if x > U10::MAX => unsafe {
                         core::hint::unreachable_unchecked()
                     },
                     SLLI => ops::Slli::slli,
                     SRLI => ops::Srli::srli,
                     SRAI => ops::Srai::srai,
+                    RORI => ops::Rori::rori,
+                    UNARY => match shamt.as_u8() {
+                        0b00000 => |src1, _| ops::Clz::clz(src1),
+                        0b00001 => |src1, _| ops::Ctz::ctz(src1),
+                        0b00010 => |src1, _| ops::Cpop::cpop(src1),
+                        0b00100 => |src1, _| ops::SextB::sext_b(src1),
+                        0b00101 => |src1, _| ops::SextH::sext_h(src1),
+                        _ => return Err(Error::InvalidOpCode),
+                    },
+                    REV8 if shamt.as_u32() == <$t>::BITS - 8 => |src1, _| ops::Rev8::rev8(src1),
+                    ORC_B if shamt.as_u8() == 0b00111 => |src1, _| ops::OrcB::orc_b(src1),
+                    BCLRI => ops::Bclri::bclri,
+                    BEXTI => ops::Bexti::bexti,
+                    BINVI => ops::Binvi::binvi,
+                    BSETI => ops::Bseti::bseti,
+                    BREV8 if shamt.as_u8() == 0b00111 => |src1, _| ops::Brev8::brev8(src1),
+                    // SHA-512 exists only on RV64 and is left to the extension arms
+                    SHA if !matches!(shamt.as_u8(), 0b00100..=0b00111) => match shamt.as_u8() {
+                        0b00000 => |src1, _| ops::Sha256sum0::sha256sum0(src1),
+                        0b00001 => |src1, _| ops::Sha256sum1::sha256sum1(src1),
+                        0b00010 => |src1, _| ops::Sha256sig0::sha256sig0(src1),
+                        0b00011 => |src1, _| ops::Sha256sig1::sha256sig1(src1),
+                        0b01000 => |src1, _| ops::Sm3p0::sm3p0(src1),
+                        0b01001 => |src1, _| ops::Sm3p1::sm3p1(src1),
+                        _ => return Err(Error::InvalidOpCode),
+                    },
                     $($($tt)*)?
                     _ => return Err(Error::InvalidOpCode),
                 };
 
-                let ZeroOrRegister::Register(dest_reg) = instruction.rd.into() else {
-                    return Err(Error::InvalidOpCode);
-                };
-                let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-                *regs.get_mut(dest_reg) = f(src1, instruction.shamt);
+                let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+                if let ZeroOrRegister::Register(dest_reg) = regs.decode(instruction.rd)? {
+                    *regs.get_mut(dest_reg) = f(src1, shamt);
+                }
 
                 Ok(())
             }
@@ -234,6 +707,8 @@ macro_rules! impl_branch {
                 instruction: B,
                 regs: &mut Registers<Self>,
                 pc: &mut Self,
+                size: u8,
+                compressed: bool,
             ) -> Result<(), Error> {
                 use crate::ops;
 
@@ -250,14 +725,19 @@ macro_rules! impl_branch {
                     _ => return Err(Error::InvalidOpCode),
                 };
 
-                let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-                let src2 = ZeroOrRegister::from_u5(instruction.rs2).fetch(regs);
+                let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+                let src2 = regs.decode(instruction.rs2)?.fetch(regs);
                 if f(src1, src2) {
-                    *pc = pc.wrapping_add_signed(
+                    let next = pc.wrapping_add_signed(
                         instruction.imm.sign_extend() as <$t as Unsigned>::Signed
                     );
+                    // with C, IALIGN is 16 and every target is aligned
+                    if !compressed && next & 0b10 != 0 {
+                        return Err(Error::InstructionAddressMisaligned(next as usize));
+                    }
+                    *pc = next;
                 } else {
-                    *pc = pc.wrapping_add(OPCODE_SIZE as _);
+                    *pc = pc.wrapping_add(size as _);
                 }
                 Ok(())
             }
@@ -269,7 +749,12 @@ macro_rules! impl_load {
     (__internal $t:ty { $($cond:pat => $body:expr),* $(,)? }) => {
         impl Load for $t {
             #[inline(always)]
-            fn load(instruction: I, regs: &mut Registers<Self>, memory: &[u8]) -> Result<(), Error> {
+            fn load(
+                instruction: I,
+                regs: &mut Registers<Self>,
+                mmu: &Mmu,
+                memory: &[u8],
+            ) -> Result<(), Error> {
                 use crate::mem::{self, Pod};
                 use crate::ops;
 
@@ -277,6 +762,7 @@ macro_rules! impl_load {
                 fn exec<T, F>(
                     instruction: I,
                     regs: &mut Registers<$t>,
+                    mmu: &Mmu,
                     memory: &[u8],
                     f: F,
                 ) -> Result<(), Error>
@@ -284,14 +770,14 @@ macro_rules! impl_load {
                     T: Pod,
                     F: Fn(T) -> $t,
                 {
-                    let ZeroOrRegister::Register(dest_reg) = ZeroOrRegister::from_u5(instruction.rd) else {
-                            return Err(Error::InvalidOpCode);
-                    };
-                    let offset = ZeroOrRegister::from_u5(instruction.rs1)
+                    let vaddr = regs.decode(instruction.rs1)?
                         .fetch(regs)
-                        .wrapping_add_signed(instruction.imm.sign_extend() as <$t as Unsigned>::Signed)
-                        as usize;
-                    *regs.get_mut(dest_reg) = f(mem::read::<T>(memory, offset)?);
+                        .wrapping_add_signed(instruction.imm.sign_extend() as <$t as Unsigned>::Signed);
+                    let offset = mmu.translate(vaddr as u64, core::mem::size_of::<T>(), Access::Load, memory)?;
+                    let value = f(mem::read::<T>(memory, offset)?);
+                    if let Some(dest) = regs.decode(instruction.rd)?.fetch_mut(regs) {
+                        *dest = value;
+                    }
                     Ok(())
                 }
 
@@ -300,7 +786,7 @@ macro_rules! impl_load {
                     x if x > U3::MAX => unsafe {
                         core::hint::unreachable_unchecked()
                     },
-                    $($cond => exec(instruction, regs, memory, $body),)*
+                    $($cond => exec(instruction, regs, mmu, memory, $body),)*
                     _ => Err(Error::InvalidOpCode),
                 }
             }
@@ -321,7 +807,12 @@ macro_rules! impl_load {
 macro_rules! impl_store {
     (__internal $t:ty { $($cond:pat => $body:expr),* $(,)? }) => {
         impl Store for $t {
-            fn store(instruction: S, regs: &mut Registers<Self>, memory: &mut [u8]) -> Result<(), Error> {
+            fn store(
+                instruction: S,
+                regs: &mut Registers<Self>,
+                mmu: &Mmu,
+                memory: &mut [u8],
+            ) -> Result<(), Error> {
                 use crate::{
                     mem::{self, Pod},
                     ops,
@@ -331,6 +822,7 @@ macro_rules! impl_store {
                 fn exec<T, F>(
                     instruction: S,
                     regs: &mut Registers<$t>,
+                    mmu: &Mmu,
                     memory: &mut [u8],
                     f: F,
                 ) -> Result<(), Error>
@@ -338,9 +830,10 @@ macro_rules! impl_store {
                     T: Pod,
                     F: Fn($t) -> T,
                 {
-                    let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-                    let src2 = ZeroOrRegister::from_u5(instruction.rs2).fetch(regs);
-                    let offset = src1.wrapping_add_signed(instruction.imm.sign_extend() as <$t as Unsigned>::Signed) as usize;
+                    let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+                    let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+                    let vaddr = src1.wrapping_add_signed(instruction.imm.sign_extend() as <$t as Unsigned>::Signed);
+                    let offset = mmu.translate(vaddr as u64, core::mem::size_of::<T>(), Access::Store, memory)?;
                     mem::write(&f(src2), memory, offset)
                 }
 
@@ -349,7 +842,7 @@ macro_rules! impl_store {
                     x if x > U3::MAX => unsafe {
                         core::hint::unreachable_unchecked()
                     },
-                    $($cond => exec(instruction, regs, memory, $body),)*
+                    $($cond => exec(instruction, regs, mmu, memory, $body),)*
                     _ => Err(Error::InvalidOpCode),
                 }
             }
@@ -365,16 +858,603 @@ macro_rules! impl_store {
     };
 }
 
-impl_math!(u32);
+/// Every access of a hart is performed in program order against memory it owns
+/// exclusively, so the ordering constraints requested by the aq and rl bits always hold.
+mod atomic {
+    use crate::{
+        decode::Amo,
+        error::Error,
+        hart::Reservation,
+        mem::{self, Pod},
+        mmu::{Access, Mmu},
+        num::{As, Zero},
+        registers::{Registers, ZeroOrRegister},
+    };
+
+    #[inline(always)]
+    fn aligned<W>(addr: usize) -> bool {
+        addr.is_multiple_of(core::mem::size_of::<W>())
+    }
+
+    #[inline(always)]
+    fn writeback<T>(rd: ZeroOrRegister, regs: &mut Registers<T>, value: T) {
+        if let ZeroOrRegister::Register(reg) = rd {
+            *regs.get_mut(reg) = value;
+        }
+    }
+
+    #[inline(always)]
+    pub fn lr<T, W, P>(
+        instruction: Amo,
+        regs: &mut Registers<T>,
+        reservation: &mut Option<Reservation>,
+        mmu: &Mmu,
+        memory: &[u8],
+        extend: fn(W) -> T,
+    ) -> Result<(), Error>
+    where
+        T: Copy + Zero + As<usize> + As<u64>,
+        P: Pod + Into<W>,
+    {
+        if instruction.rs2.as_u8() != 0 {
+            return Err(Error::InvalidOpCode);
+        }
+        let rd = regs.decode(instruction.rd)?;
+        let vaddr: u64 = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+        if !aligned::<W>(vaddr as usize) {
+            return Err(Error::LoadAddressMisaligned(vaddr as usize));
+        }
+        let addr = mmu.translate(vaddr, core::mem::size_of::<W>(), Access::Load, memory)?;
+        let value = mem::read::<P>(memory, addr)?.into();
+        *reservation = Some(Reservation {
+            addr,
+            size: core::mem::size_of::<W>(),
+        });
+        writeback(rd, regs, extend(value));
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn sc<T, W, P>(
+        instruction: Amo,
+        regs: &mut Registers<T>,
+        reservation: &mut Option<Reservation>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error>
+    where
+        T: Copy + Zero + As<usize> + As<u64> + As<W>,
+        bool: As<T>,
+        P: Pod + From<W>,
+    {
+        let rd = regs.decode(instruction.rd)?;
+        let src: W = regs.decode(instruction.rs2)?.fetch(regs).r#as();
+        let vaddr: u64 = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+        if !aligned::<W>(vaddr as usize) {
+            return Err(Error::StoreAddressMisaligned(vaddr as usize));
+        }
+        let addr = mmu.translate(vaddr, core::mem::size_of::<W>(), Access::Store, memory)?;
+        let reserved = reservation.take()
+            == Some(Reservation {
+                addr,
+                size: core::mem::size_of::<W>(),
+            });
+        if reserved {
+            mem::write(&P::from(src), memory, addr)?;
+        }
+        writeback(rd, regs, (!reserved).r#as());
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn amo<T, W, P>(
+        instruction: Amo,
+        regs: &mut Registers<T>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+        f: fn(W, W) -> W,
+        extend: fn(W) -> T,
+    ) -> Result<(), Error>
+    where
+        T: Copy + Zero + As<usize> + As<u64> + As<W>,
+        W: Copy,
+        P: Pod + From<W> + Into<W>,
+    {
+        let rd = regs.decode(instruction.rd)?;
+        let src: W = regs.decode(instruction.rs2)?.fetch(regs).r#as();
+        let vaddr: u64 = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+        if !aligned::<W>(vaddr as usize) {
+            return Err(Error::StoreAddressMisaligned(vaddr as usize));
+        }
+        let addr = mmu.translate(vaddr, core::mem::size_of::<W>(), Access::Store, memory)?;
+        // AMOs report their faults as stores
+        let value: W = mem::read::<P>(memory, addr)
+            .map_err(|_| Error::StoreAccessFault(addr))?
+            .into();
+        mem::write(&P::from(f(value, src)), memory, addr)?;
+        writeback(rd, regs, extend(value));
+        Ok(())
+    }
+}
+
+mod float {
+    use super::*;
+    use crate::float::{Float, IntFormat, F16, F32, F64};
+
+    #[inline(always)]
+    pub fn read<F: Float>(fregs: &FRegisters, reg: U5) -> u64 {
+        F::unbox(fregs.get(reg))
+    }
+
+    #[inline(always)]
+    pub fn write<F: Float>(fregs: &mut FRegisters, reg: U5, value: u64) {
+        *fregs.get_mut(reg) = F::nan_box(value);
+    }
+
+    #[inline(always)]
+    pub fn xlen<T>() -> u32 {
+        core::mem::size_of::<T>() as u32 * 8
+    }
+
+    /// Reads an operand from the FP register file or, without one, from the integer registers
+    /// where it is not NaN-boxed and doubles take an even/odd register pair on RV32.
+    #[inline(always)]
+    fn fetch<T, F: Float>(
+        regs: &Registers<T>,
+        fregs: &Option<&mut FRegisters>,
+        reg: U5,
+    ) -> Result<u64, Error>
+    where
+        T: Copy + Zero + As<u64>,
+    {
+        match fregs {
+            Some(fregs) => Ok(read::<F>(fregs, reg)),
+            None if F::BITS <= xlen::<T>() => {
+                let value: u64 = regs.decode(reg)?.fetch(regs).r#as();
+                Ok(value & F::MASK)
+            }
+            None => {
+                let (low, high) = pair(regs, reg)?;
+                let low: u64 = low.fetch(regs).r#as();
+                let high: u64 = high.fetch(regs).r#as();
+                Ok((high << 32) | (low & 0xffff_ffff))
+            }
+        }
+    }
+
+    /// Writes a result to the FP register file or, without one, sign-extends it in the integer
+    /// registers.
+    #[inline(always)]
+    fn store<T, F: Float>(
+        regs: &mut Registers<T>,
+        fregs: &mut Option<&mut FRegisters>,
+        reg: U5,
+        value: u64,
+    ) -> Result<(), Error>
+    where
+        u64: As<T>,
+    {
+        match fregs {
+            Some(fregs) => write::<F>(fregs, reg, value),
+            None if F::BITS <= xlen::<T>() => {
+                let rd = regs.decode(reg)?;
+                write_x(regs, rd, sign_extend(value, F::BITS));
+            }
+            None => {
+                let (low, high) = pair(regs, reg)?;
+                write_x(regs, low, value & 0xffff_ffff);
+                write_x(regs, high, value >> 32);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes an even/odd register pair, the pair of `x0` reads as zero and ignores writes.
+    #[inline(always)]
+    fn pair<T>(regs: &Registers<T>, reg: U5) -> Result<(ZeroOrRegister, ZeroOrRegister), Error> {
+        match reg.as_u8() {
+            0 => Ok((ZeroOrRegister::Zero, ZeroOrRegister::Zero)),
+            n if n % 2 == 0 => Ok((regs.decode(reg)?, regs.decode(U5::new_truncate(n + 1))?)),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+
+    /// Writes a sign-extended result to an integer register.
+    #[inline(always)]
+    fn write_x<T>(regs: &mut Registers<T>, rd: ZeroOrRegister, value: u64)
+    where
+        u64: As<T>,
+    {
+        if let ZeroOrRegister::Register(reg) = rd {
+            *regs.get_mut(reg) = value.r#as();
+        }
+    }
+
+    #[inline(always)]
+    fn int_format<T>(rs2: U5) -> Result<IntFormat, Error> {
+        match rs2.as_u8() {
+            0b00000 => Ok(IntFormat::I32),
+            0b00001 => Ok(IntFormat::U32),
+            0b00010 if xlen::<T>() >= 64 => Ok(IntFormat::I64),
+            0b00011 if xlen::<T>() >= 64 => Ok(IntFormat::U64),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+
+    /// Converts the register `reg` holding a value of the source format `fmt` to format `F`.
+    #[inline(always)]
+    fn convert<T, F: Float>(
+        fmt: U5,
+        regs: &Registers<T>,
+        fregs: &Option<&mut FRegisters>,
+        reg: U5,
+        rm: crate::float::RoundingMode,
+        flags: &mut crate::float::Flags,
+    ) -> Result<u64, Error>
+    where
+        T: Copy + Zero + As<u64>,
+    {
+        #[deny(unreachable_patterns)]
+        match U2::new(fmt.as_u8()) {
+            Some(FMT_S) if F::BITS != F32::BITS => Ok(F::convert::<F32>(
+                fetch::<T, F32>(regs, fregs, reg)?,
+                rm,
+                flags,
+            )),
+            Some(FMT_D) if F::BITS != F64::BITS => Ok(F::convert::<F64>(
+                fetch::<T, F64>(regs, fregs, reg)?,
+                rm,
+                flags,
+            )),
+            Some(FMT_H) if F::BITS != F16::BITS => Ok(F::convert::<F16>(
+                fetch::<T, F16>(regs, fregs, reg)?,
+                rm,
+                flags,
+            )),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+
+    #[inline(always)]
+    fn sign_extend(value: u64, bits: u32) -> u64 {
+        ((value << (64 - bits)) as i64 >> (64 - bits)) as u64
+    }
+
+    pub fn math<T, F>(
+        instruction: R,
+        regs: &mut Registers<T>,
+        mut fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error>
+    where
+        T: Copy + Zero + As<u64>,
+        u64: As<T>,
+        F: Float,
+    {
+        type Binary = fn(u64, u64, crate::float::RoundingMode, &mut crate::float::Flags) -> u64;
+
+        let R {
+            funct7,
+            rs2,
+            rs1,
+            funct3,
+            rd,
+        } = instruction;
+        // Zfa depends on F, the Zfinx and Zdinx harts have none of its instructions
+        let zfa = fregs.is_some();
+
+        #[deny(unreachable_patterns)]
+        let f: Binary = match U5::new_truncate(funct7.as_u8() >> 2) {
+            FADD => F::add,
+            FSUB => F::sub,
+            FMUL => F::mul,
+            FDIV => F::div,
+            FSQRT if rs2.as_u8() == 0 => {
+                let rm = fcsr.rounding_mode(funct3)?;
+                let value = F::sqrt(fetch::<T, F>(regs, &fregs, rs1)?, rm, &mut fcsr.fflags);
+                return store::<T, F>(regs, &mut fregs, rd, value);
+            }
+            FSGNJ => {
+                let f: fn(u64, u64) -> u64 = match funct3.as_u8() {
+                    0b000 => F::sgnj,
+                    0b001 => F::sgnjn,
+                    0b010 => F::sgnjx,
+                    _ => return Err(Error::InvalidOpCode),
+                };
+                let value = f(
+                    fetch::<T, F>(regs, &fregs, rs1)?,
+                    fetch::<T, F>(regs, &fregs, rs2)?,
+                );
+                return store::<T, F>(regs, &mut fregs, rd, value);
+            }
+            FMINMAX => {
+                let f: fn(u64, u64, &mut crate::float::Flags) -> u64 = match funct3.as_u8() {
+                    0b000 => F::min,
+                    0b001 => F::max,
+                    0b010 if zfa => F::minm,
+                    0b011 if zfa => F::maxm,
+                    _ => return Err(Error::InvalidOpCode),
+                };
+                let value = f(
+                    fetch::<T, F>(regs, &fregs, rs1)?,
+                    fetch::<T, F>(regs, &fregs, rs2)?,
+                    &mut fcsr.fflags,
+                );
+                return store::<T, F>(regs, &mut fregs, rd, value);
+            }
+            FCVT_FMT_FMT => {
+                let rm = fcsr.rounding_mode(funct3)?;
+                let flags = &mut fcsr.fflags;
+                let value = match rs2.as_u8() {
+                    0b00100 if zfa => {
+                        F::round_to_int(fetch::<T, F>(regs, &fregs, rs1)?, rm, false, flags)
+                    }
+                    0b00101 if zfa => {
+                        F::round_to_int(fetch::<T, F>(regs, &fregs, rs1)?, rm, true, flags)
+                    }
+                    _ => convert::<T, F>(rs2, regs, &fregs, rs1, rm, flags)?,
+                };
+                return store::<T, F>(regs, &mut fregs, rd, value);
+            }
+            FCMP => {
+                let f: fn(u64, u64, &mut crate::float::Flags) -> bool = match funct3.as_u8() {
+                    0b010 => F::eq,
+                    0b001 => F::lt,
+                    0b000 => F::le,
+                    0b101 if zfa => F::ltq,
+                    0b100 if zfa => F::leq,
+                    _ => return Err(Error::InvalidOpCode),
+                };
+                let value = f(
+                    fetch::<T, F>(regs, &fregs, rs1)?,
+                    fetch::<T, F>(regs, &fregs, rs2)?,
+                    &mut fcsr.fflags,
+                );
+                let rd = regs.decode(rd)?;
+                write_x(regs, rd, value as u64);
+                return Ok(());
+            }
+            // fcvtmod.w.d
+            FCVT_INT_FMT if rs2.as_u8() == 0b01000 && zfa => {
+                if F::BITS != F64::BITS || funct3.as_u8() != 0b001 {
+                    return Err(Error::InvalidOpCode);
+                }
+                let value = F::to_int_modular(fetch::<T, F>(regs, &fregs, rs1)?, &mut fcsr.fflags);
+                let rd = regs.decode(rd)?;
+                write_x(regs, rd, value);
+                return Ok(());
+            }
+            FCVT_INT_FMT => {
+                let format = int_format::<T>(rs2)?;
+                let rm = fcsr.rounding_mode(funct3)?;
+                let src = fetch::<T, F>(regs, &fregs, rs1)?;
+                let value = F::to_int(src, format, rm, &mut fcsr.fflags);
+                let value = match format {
+                    IntFormat::I32 | IntFormat::U32 => sign_extend(value, 32),
+                    IntFormat::I64 | IntFormat::U64 => value,
+                };
+                let rd = regs.decode(rd)?;
+                write_x(regs, rd, value);
+                return Ok(());
+            }
+            FCVT_FMT_INT => {
+                let format = int_format::<T>(rs2)?;
+                let rm = fcsr.rounding_mode(funct3)?;
+                let src = regs.decode(rs1)?.fetch(regs).r#as();
+                let value = F::from_int(src, format, rm, &mut fcsr.fflags);
+                return store::<T, F>(regs, &mut fregs, rd, value);
+            }
+            FMV_INT_FMT if rs2.as_u8() == 0 => {
+                let value = match (funct3.as_u8(), &fregs) {
+                    (0b000, Some(fregs)) if F::BITS <= xlen::<T>() => {
+                        sign_extend(fregs.get(rs1), F::BITS)
+                    }
+                    (0b001, _) => F::classify(fetch::<T, F>(regs, &fregs, rs1)?),
+                    _ => return Err(Error::InvalidOpCode),
+                };
+                let rd = regs.decode(rd)?;
+                write_x(regs, rd, value);
+                return Ok(());
+            }
+            // fmvh.x.d
+            FMV_INT_FMT
+                if rs2.as_u8() == 1
+                    && funct3.as_u8() == 0
+                    && F::BITS == F64::BITS
+                    && xlen::<T>() == 32
+                    && zfa =>
+            {
+                let value = fetch::<T, F>(regs, &fregs, rs1)?;
+                let rd = regs.decode(rd)?;
+                write_x(regs, rd, sign_extend(value >> 32, 32));
+                return Ok(());
+            }
+            FMV_FMT_INT
+                if rs2.as_u8() == 0
+                    && funct3.as_u8() == 0
+                    && F::BITS <= xlen::<T>()
+                    && fregs.is_some() =>
+            {
+                let src: u64 = regs.decode(rs1)?.fetch(regs).r#as();
+                return store::<T, F>(regs, &mut fregs, rd, src & F::MASK);
+            }
+            // fli
+            FMV_FMT_INT if rs2.as_u8() == 1 && funct3.as_u8() == 0 && zfa => {
+                return store::<T, F>(regs, &mut fregs, rd, F::constant(rs1.as_u8()));
+            }
+            // fmvp.d.x
+            FMVP if funct3.as_u8() == 0 && F::BITS == F64::BITS && xlen::<T>() == 32 && zfa => {
+                let low: u64 = regs.decode(rs1)?.fetch(regs).r#as();
+                let high: u64 = regs.decode(rs2)?.fetch(regs).r#as();
+                return store::<T, F>(regs, &mut fregs, rd, (high << 32) | (low & 0xffff_ffff));
+            }
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let rm = fcsr.rounding_mode(funct3)?;
+        let value = f(
+            fetch::<T, F>(regs, &fregs, rs1)?,
+            fetch::<T, F>(regs, &fregs, rs2)?,
+            rm,
+            &mut fcsr.fflags,
+        );
+        store::<T, F>(regs, &mut fregs, rd, value)
+    }
+
+    pub fn mul_add<T, F>(
+        instruction: R4,
+        (negate_product, negate_addend): (bool, bool),
+        regs: &mut Registers<T>,
+        mut fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error>
+    where
+        T: Copy + Zero + As<u64>,
+        u64: As<T>,
+        F: Float,
+    {
+        let rm = fcsr.rounding_mode(instruction.funct3)?;
+        let mut a = fetch::<T, F>(regs, &fregs, instruction.rs1)?;
+        let b = fetch::<T, F>(regs, &fregs, instruction.rs2)?;
+        let mut c = fetch::<T, F>(regs, &fregs, instruction.rs3)?;
+        if negate_product {
+            a ^= F::SIGN;
+        }
+        if negate_addend {
+            c ^= F::SIGN;
+        }
+        let value = F::mul_add(a, b, c, rm, &mut fcsr.fflags);
+        store::<T, F>(regs, &mut fregs, instruction.rd, value)
+    }
+}
+
+macro_rules! impl_atomic {
+    (__internal $t:ty {
+        $($w:ty as $pod:ident => $extend:expr, [
+            $lr:pat,
+            $sc:pat,
+            $swap:pat,
+            $add:pat,
+            $xor:pat,
+            $and:pat,
+            $or:pat,
+            $min:pat,
+            $max:pat,
+            $minu:pat,
+            $maxu:pat $(,)?
+        ])*
+    }) => {
+        impl Atomic for $t {
+            #[inline(always)]
+            fn atomic(
+                instruction: Amo,
+                regs: &mut Registers<Self>,
+                reservation: &mut Option<Reservation>,
+                mmu: &Mmu,
+                memory: &mut [u8],
+            ) -> Result<(), Error> {
+                #[allow(unused_imports)]
+                use crate::{
+                    mem::{U32, U64},
+                    num::As,
+                    ops,
+                };
+
+                #[deny(unreachable_patterns)]
+                match instruction.id() {
+                    x if x > U10::MAX => unsafe {
+                        core::hint::unreachable_unchecked()
+                    },
+                    $(
+                        $lr => atomic::lr::<_, $w, $pod>(instruction, regs, reservation, mmu, memory, $extend),
+                        $sc => atomic::sc::<_, $w, $pod>(instruction, regs, reservation, mmu, memory),
+                        $swap => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, |_, src| src, $extend),
+                        $add => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Add::add, $extend),
+                        $xor => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Xor::xor, $extend),
+                        $and => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::And::and, $extend),
+                        $or => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Or::or, $extend),
+                        $min => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Min::min, $extend),
+                        $max => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Max::max, $extend),
+                        $minu => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Minu::minu, $extend),
+                        $maxu => atomic::amo::<_, $w, $pod>(instruction, regs, mmu, memory, ops::Maxu::maxu, $extend),
+                    )*
+                    _ => Err(Error::InvalidOpCode),
+                }
+            }
+        }
+    };
+    ($t:ty $({ $($tt:tt)* })?) => {
+        impl_atomic!(__internal $t {
+            u32 as U32 => ops::Imm::imm, [
+                LR_W,
+                SC_W,
+                AMOSWAP_W,
+                AMOADD_W,
+                AMOXOR_W,
+                AMOAND_W,
+                AMOOR_W,
+                AMOMIN_W,
+                AMOMAX_W,
+                AMOMINU_W,
+                AMOMAXU_W,
+            ]
+            $($($tt)*)?
+        });
+    };
+}
+
+impl_math!(u32 |instruction| {
+    id if without_bs(id) == AES32ESI => {
+        byte_select!(instruction.funct7.as_u8() >> 5, ops::Aes32esi::aes32esi)
+    }
+    id if without_bs(id) == AES32ESMI => {
+        byte_select!(instruction.funct7.as_u8() >> 5, ops::Aes32esmi::aes32esmi)
+    }
+    id if without_bs(id) == AES32DSI => {
+        byte_select!(instruction.funct7.as_u8() >> 5, ops::Aes32dsi::aes32dsi)
+    }
+    id if without_bs(id) == AES32DSMI => {
+        byte_select!(instruction.funct7.as_u8() >> 5, ops::Aes32dsmi::aes32dsmi)
+    }
+    SHA512SUM0R => ops::Sha512sum0r::sha512sum0r,
+    SHA512SUM1R => ops::Sha512sum1r::sha512sum1r,
+    SHA512SIG0L => ops::Sha512sig0l::sha512sig0l,
+    SHA512SIG1L => ops::Sha512sig1l::sha512sig1l,
+    SHA512SIG0H => ops::Sha512sig0h::sha512sig0h,
+    SHA512SIG1H => ops::Sha512sig1h::sha512sig1h,
+});
 impl_mathi!(u32);
-impl_shifti!(u32);
+impl_shifti!(u32 |shamt| {
+    ZIP if shamt.as_u8() == 0b01111 => |src1, _| ops::Zip::zip(src1),
+    UNZIP if shamt.as_u8() == 0b01111 => |src1, _| ops::Unzip::unzip(src1),
+});
 impl_branch!(u32);
 impl_load!(u32);
 impl_store!(u32);
+impl_atomic!(u32);
 
-impl_math!(u64);
+impl_math!(u64 {
+    AES64ES => ops::Aes64es::aes64es,
+    AES64ESM => ops::Aes64esm::aes64esm,
+    AES64DS => ops::Aes64ds::aes64ds,
+    AES64DSM => ops::Aes64dsm::aes64dsm,
+    AES64KS2 => ops::Aes64ks2::aes64ks2,
+});
 impl_mathi!(u64);
-impl_shifti!(u64);
+impl_shifti!(u64 |shamt| {
+    SHA => match shamt.as_u8() {
+        0b00100 => |src1, _| ops::Sha512sum0::sha512sum0(src1),
+        0b00101 => |src1, _| ops::Sha512sum1::sha512sum1(src1),
+        0b00110 => |src1, _| ops::Sha512sig0::sha512sig0(src1),
+        0b00111 => |src1, _| ops::Sha512sig1::sha512sig1(src1),
+        _ => return Err(Error::InvalidOpCode),
+    },
+    AES64 => match shamt.as_u8() {
+        0b000000 => |src1, _| ops::Aes64im::aes64im(src1),
+        0b010000..=0b011010 => ops::Aes64ks1i::aes64ks1i,
+        _ => return Err(Error::InvalidOpCode),
+    },
+});
 impl_branch!(u64);
 impl_load!(u64 {
     LWU => ops::Lwu::lwu,
@@ -383,6 +1463,33 @@ impl_load!(u64 {
 impl_store!(u64 {
     SD => ops::Sd::sd,
 });
+impl_atomic!(u64 {
+    u64 as U64 => As::r#as, [
+        LR_D,
+        SC_D,
+        AMOSWAP_D,
+        AMOADD_D,
+        AMOXOR_D,
+        AMOAND_D,
+        AMOOR_D,
+        AMOMIN_D,
+        AMOMAX_D,
+        AMOMINU_D,
+        AMOMAXU_D,
+    ]
+});
+
+impl_mathi!(u128);
+impl_branch!(u128);
+impl_load!(u128 {
+    LWU => ops::Lwu::lwu,
+    LD => ops::Ld::ld,
+    LDU => ops::Ldu::ldu,
+});
+impl_store!(u128 {
+    SD => ops::Sd::sd,
+    SQ => ops::Sq::sq,
+});
 
 impl<T> Lui for T
 where
@@ -390,10 +1497,9 @@ where
 {
     #[inline(always)]
     fn lui(instruction: U, regs: &mut Registers<Self>) -> Result<(), Error> {
-        let dest = ZeroOrRegister::from_u5(instruction.rd)
-            .fetch_mut(regs)
-            .ok_or(Error::InvalidOpCode)?;
-        *dest = crate::ops::Imm::imm(instruction.imm);
+        if let Some(dest) = regs.decode(instruction.rd)?.fetch_mut(regs) {
+            *dest = crate::ops::Imm::imm(instruction.imm);
+        }
         Ok(())
     }
 }
@@ -406,10 +1512,9 @@ where
     fn auipc(instruction: U, regs: &mut Registers<Self>, pc: Self) -> Result<(), Error> {
         use crate::ops;
 
-        let dest = ZeroOrRegister::from_u5(instruction.rd)
-            .fetch_mut(regs)
-            .ok_or(Error::InvalidOpCode)?;
-        *dest = pc.add(ops::Imm::imm(instruction.imm));
+        if let Some(dest) = regs.decode(instruction.rd)?.fetch_mut(regs) {
+            *dest = pc.add(ops::Imm::imm(instruction.imm));
+        }
 
         Ok(())
     }
@@ -417,19 +1522,30 @@ where
 
 impl<T> Jal for T
 where
-    T: Unsigned + crate::ops::Add + Copy,
+    T: Unsigned + crate::ops::Add + Copy + As<u64>,
     <T as Unsigned>::Signed: From<i32>,
     u8: As<T>,
 {
     #[inline(always)]
-    fn jal(instruction: J, regs: &mut Registers<Self>, pc: &mut Self) -> Result<(), Error> {
-        // TODO: The JAL and JALR instructions will generate an instruction-address-misaligned exception if the target
-        //       address is not aligned to a four-byte boundary. (???)
-        if let ZeroOrRegister::Register(reg) = instruction.rd.into() {
-            *regs.get_mut(reg) = pc.add(OPCODE_SIZE.r#as());
+    fn jal(
+        instruction: J,
+        regs: &mut Registers<Self>,
+        pc: &mut Self,
+        size: u8,
+        compressed: bool,
+    ) -> Result<(), Error> {
+        let next = pc.add(<T as Unsigned>::Signed::from(instruction.imm.sign_extend()).bitcast());
+        // with C, IALIGN is 16 and every target is aligned
+        let target: u64 = next.r#as();
+        if !compressed && target & 0b10 != 0 {
+            return Err(Error::InstructionAddressMisaligned(target as usize));
+        }
+
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = pc.add(size.r#as());
         }
 
-        *pc = pc.add(<T as Unsigned>::Signed::from(instruction.imm.sign_extend()).bitcast());
+        *pc = next;
 
         Ok(())
     }
@@ -443,21 +1559,37 @@ where
         + Copy
         + Zero
         + One
-        + core::ops::Not<Output = T>,
+        + core::ops::Not<Output = T>
+        + As<u64>,
     <T as Unsigned>::Signed: From<i16>,
     u8: As<T>,
 {
     #[inline(always)]
-    fn jalr(instruction: I, regs: &mut Registers<Self>, pc: &mut Self) -> Result<(), Error> {
-        // TODO: The JAL and JALR instructions will generate an instruction-address-misaligned exception if the target
-        //       address is not aligned to a four-byte boundary. (???)
-        let next = ZeroOrRegister::from_u5(instruction.rs1)
+    fn jalr(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        pc: &mut Self,
+        size: u8,
+        compressed: bool,
+        elp: Option<&mut bool>,
+    ) -> Result<(), Error> {
+        let next = regs
+            .decode(instruction.rs1)?
             .fetch(regs)
             .add(<T as Unsigned>::Signed::from(instruction.imm.sign_extend()).bitcast())
             .and(!T::one());
+        // with C, IALIGN is 16 and every target is aligned
+        let target: u64 = next.r#as();
+        if !compressed && target & 0b10 != 0 {
+            return Err(Error::InstructionAddressMisaligned(target as usize));
+        }
+
+        if let Some(elp) = elp {
+            *elp = !matches!(instruction.rs1.as_u8(), 1 | 5 | 7);
+        }
 
-        if let ZeroOrRegister::Register(reg) = ZeroOrRegister::from_u5(instruction.rd) {
-            *regs.get_mut(reg) = pc.add(OPCODE_SIZE.r#as());
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = pc.add(size.r#as());
         }
 
         *pc = next;
@@ -466,44 +1598,557 @@ where
     }
 }
 
-impl MathIW for u64 {
-    fn mathiw(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error> {
-        use crate::ops;
+impl<T> Fence for T {
+    #[inline]
+    fn fence(instruction: crate::decode::Fence) -> Result<(), Error> {
+        use core::sync::atomic::{fence, Ordering};
+
+        // predecessor and successor sets
+        const I: u8 = 0b1000;
+        const O: u8 = 0b0100;
+        const R: u8 = 0b0010;
+        const W: u8 = 0b0001;
+        const TSO: u8 = 0b1000;
 
         #[deny(unreachable_patterns)]
-        let f: fn(u64, U12) -> u64 = match instruction.id() {
+        match instruction.id() {
             x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
-            ADDIW => ops::Addiw::addiw,
+            FENCE => {
+                let fm = instruction.fm.as_u8();
+                let pred = instruction.pred.as_u8();
+                let succ = instruction.succ.as_u8();
+                if fm == 0 && pred == W && succ == 0 {
+                    // PAUSE
+                    core::hint::spin_loop();
+                } else if pred == 0 || succ == 0 {
+                    // orders nothing, reserved hint space
+                } else if fm == TSO && pred == R | W && succ == R | W {
+                    // FENCE.TSO does not order earlier stores with later loads
+                    fence(Ordering::AcqRel);
+                } else if pred & (O | W) != 0 && succ & (I | R) != 0 {
+                    fence(Ordering::SeqCst);
+                } else {
+                    // reserved fm values behave like a normal fence
+                    fence(Ordering::AcqRel);
+                }
+            }
+            FENCE_I => {
+                // instructions are fetched and decoded from memory at every step, making stores
+                // visible to the instruction stream only requires them to be ordered
+                fence(Ordering::SeqCst);
+            }
             _ => return Err(Error::InvalidOpCode),
-        };
+        }
 
-        let ZeroOrRegister::Register(reg) = instruction.rd.into() else {
-            return Err(Error::InvalidOpCode);
-        };
-        let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-        *regs.get_mut(reg) = f(src1, instruction.imm);
         Ok(())
     }
 }
 
-impl ShiftIW for u64 {
-    fn shiftiw(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
-        use crate::ops;
+impl<T> CacheBlock for T
+where
+    T: Copy + Zero + As<u64>,
+{
+    #[inline]
+    fn cache_block(
+        instruction: I,
+        regs: &Registers<Self>,
+        block: usize,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error> {
+        use core::sync::atomic::{fence, Ordering};
+
+        debug_assert!(
+            block.is_power_of_two(),
+            "cache block size is not a power of two"
+        );
+        if instruction.id() != CBO || regs.decode(instruction.rd)? != ZeroOrRegister::Zero {
+            return Err(Error::InvalidOpCode);
+        }
 
         #[deny(unreachable_patterns)]
-        let f: fn(u64, U5) -> u64 = match instruction.id() {
-            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
-            SLLIW => ops::Slliw::slliw,
-            SRLIW => ops::Srliw::srliw,
-            SRAIW => ops::Sraiw::sraiw,
+        let zero = match instruction.imm {
+            x if x > U12::MAX => unsafe { core::hint::unreachable_unchecked() },
+            CBO_ZERO => true,
+            CBO_INVAL | CBO_CLEAN | CBO_FLUSH => false,
             _ => return Err(Error::InvalidOpCode),
         };
 
-        let ZeroOrRegister::Register(dest_reg) = instruction.rd.into() else {
+        let addr: u64 = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+        // a block never crosses a page, the one holding `addr` is mapped with it
+        let start = mmu.translate(addr, 1, Access::Store, memory)? & !(block - 1);
+        let line = memory
+            .get_mut(start..start + block)
+            .filter(|_| mmu.allows(start, block, Access::Store))
+            .ok_or(Error::StoreAccessFault(addr as usize))?;
+        if zero {
+            line.fill(0);
+        } else {
+            // there are no caches, memory is always coherent and only needs the accesses ordered
+            fence(Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+impl<T> Mop for T
+where
+    T: Copy + PartialEq + Zero + As<u128> + As<usize>,
+    u128: As<T>,
+    usize: As<T>,
+{
+    fn mop(
+        instruction: R,
+        regs: &mut Registers<Self>,
+        shadow_stack: Option<ShadowStack<Self>>,
+        memory: &mut [u8],
+    ) -> Result<(), Error> {
+        let R {
+            funct7,
+            rs2,
+            rs1,
+            funct3,
+            rd,
+        } = instruction;
+        let rd = regs.decode(rd)?;
+        let link = |reg: U5| matches!(reg.as_u8(), 1 | 5);
+
+        // MOP.R.n sets bits 31 and 25:22 to 1 and 0111, MOP.RR.n sets bits 31 and 25
+        let funct7 = funct7.as_u8();
+        let mop_r = funct7 & 0b1011001 == 0b1000000 && rs2.as_u8() >> 2 == 0b111;
+        let mop_rr = funct7 & 0b1011001 == 0b1000001;
+        if funct3.as_u8() != 0b100 || !(mop_r || mop_rr) {
             return Err(Error::InvalidOpCode);
+        }
+
+        match (shadow_stack, rd) {
+            // sspush x1/x5
+            (Some(mut ss), ZeroOrRegister::Zero)
+                if funct7 == SSPUSH.as_u8() && rs1.as_u8() == 0 && link(rs2) =>
+            {
+                let value = regs.decode(rs2)?.fetch(regs);
+                ss.push(value, memory)
+            }
+            // sspopchk x1/x5
+            (Some(mut ss), ZeroOrRegister::Zero)
+                if funct7 == SSPOPCHK.as_u8() && rs2.as_u8() == 0b11100 && link(rs1) =>
+            {
+                let expected = regs.decode(rs1)?.fetch(regs);
+                ss.pop(expected, memory)
+            }
+            // ssrdp
+            (Some(ss), ZeroOrRegister::Register(reg))
+                if funct7 == SSPOPCHK.as_u8() && rs2.as_u8() == 0b11100 && rs1.as_u8() == 0 =>
+            {
+                *regs.get_mut(reg) = *ss.ssp;
+                Ok(())
+            }
+            (_, rd) => {
+                if let Some(dest) = rd.fetch_mut(regs) {
+                    *dest = T::zero();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T> ShadowStackSwap for T
+where
+    T: Copy + PartialEq + Zero + As<u128> + As<usize>,
+    u128: As<T>,
+    usize: As<T>,
+{
+    fn ssamoswap(
+        instruction: Amo,
+        regs: &mut Registers<Self>,
+        shadow_stack: Option<ShadowStack<Self>>,
+        memory: &mut [u8],
+    ) -> Result<(), Error> {
+        let xlen = core::mem::size_of::<T>() * 8;
+        #[deny(unreachable_patterns)]
+        let size = match instruction.id() {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            SSAMOSWAP_W => 4,
+            SSAMOSWAP_D if xlen >= 64 => 8,
+            _ => return Err(Error::InvalidOpCode),
+        };
+        let ss = shadow_stack.ok_or(Error::InvalidOpCode)?;
+
+        let addr = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+        let src: u128 = regs.decode(instruction.rs2)?.fetch(regs).r#as();
+        let old = ss.swap(addr, src, size, memory)?;
+        // the loaded value is sign-extended
+        let old = ((old << (128 - size * 8)) as i128 >> (128 - size * 8)) as u128;
+        if let Some(dest) = regs.decode(instruction.rd)?.fetch_mut(regs) {
+            *dest = old.r#as();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Csr for T
+where
+    T: Copy
+        + As<u64>
+        + Zero
+        + core::ops::BitAnd<Output = T>
+        + core::ops::BitOr<Output = T>
+        + core::ops::Not<Output = T>,
+    u8: As<T>,
+{
+    fn csr(instruction: I, hart: &mut Hart<Self>) -> Result<(), Error> {
+        use crate::csr;
+
+        let addr = instruction.imm;
+        let rd = hart.regs.decode(instruction.rd)?;
+        // the immediate forms use the rs1 field as a zero-extended 5-bit value
+        let src = match instruction.id() {
+            CSRRW | CSRRS | CSRRC => hart.regs.decode(instruction.rs1)?.fetch(&hart.regs),
+            _ => instruction.rs1.as_u8().r#as(),
+        };
+        let zero_src = instruction.rs1.as_u8() == 0;
+
+        // CSRRW(I) with rd=x0 does not read, CSRRS(I)/CSRRC(I) with rs1=x0 or uimm=0 do not write
+        #[deny(unreachable_patterns)]
+        let (read, write) = match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            CSRRW | CSRRWI => (rd != ZeroOrRegister::Zero, true),
+            CSRRS | CSRRC | CSRRSI | CSRRCI => (true, !zero_src),
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        hart.csrs.check(addr, hart.privilege, write)?;
+        csr::check_state(hart, addr)?;
+        let old = if read {
+            csr::read(hart, addr)?
+        } else {
+            T::zero()
         };
-        let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-        *regs.get_mut(dest_reg) = f(src1, instruction.shamt);
+
+        if write {
+            let value = match instruction.id() {
+                CSRRS | CSRRSI => old | src,
+                CSRRC | CSRRCI => old & !src,
+                _ => src,
+            };
+            csr::write(hart, addr, value)?;
+        }
+
+        if let ZeroOrRegister::Register(reg) = rd {
+            *hart.regs.get_mut(reg) = old;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Privileged for T
+where
+    T: crate::ops::Add + Copy + As<u64>,
+    u8: As<T>,
+    u64: As<T>,
+{
+    fn privileged(instruction: I, hart: &mut Hart<Self>, size: u8) -> Result<(), Error> {
+        use crate::trap::{TVM, TW};
+
+        if instruction.id() != PRIV || instruction.rd.as_u8() != 0 {
+            return Err(Error::InvalidOpCode);
+        }
+        let mstatus = hart
+            .csrs
+            .get(crate::csr::MSTATUS)
+            .map_or(0, |entry| As::<u64>::r#as(entry.value));
+        if instruction.imm.as_u16() >> 5 == SFENCE_VMA.as_u16() {
+            hart.regs.decode(instruction.rs1)?;
+            hart.regs
+                .decode(U5::new_truncate(instruction.imm.as_u16() as u8))?;
+            return match hart.privilege {
+                Privilege::User => Err(Error::InvalidOpCode),
+                Privilege::Supervisor if mstatus & TVM != 0 => Err(Error::InvalidOpCode),
+                // translations are not cached whatever the address and ASID in rs1 and rs2, only
+                // the page-table updates need ordering before the following implicit accesses
+                _ => {
+                    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+                    hart.pc = hart.pc.add(size.r#as());
+                    Ok(())
+                }
+            };
+        }
+        if instruction.rs1.as_u8() != 0 {
+            return Err(Error::InvalidOpCode);
+        }
+        match instruction.imm {
+            ECALL => Err(Error::EnvironmentCall),
+            EBREAK => Err(Error::Breakpoint),
+            SRET => crate::trap::sret(hart),
+            MRET => crate::trap::mret(hart),
+            // the time limit of mstatus.TW is zero, below M-mode it traps at once
+            WFI if hart.privilege != Privilege::Machine && mstatus & TW != 0 => {
+                Err(Error::InvalidOpCode)
+            }
+            // WFI completes at once, the interrupts are polled before every instruction
+            WFI => {
+                hart.pc = hart.pc.add(size.r#as());
+                Ok(())
+            }
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+}
+
+impl<T> FloatLoad for T
+where
+    T: crate::ops::Addi + Copy + Zero + As<u64>,
+{
+    #[inline(always)]
+    fn float_load(
+        instruction: I,
+        regs: &Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error> {
+        use crate::{
+            float::{F16, F32, F64},
+            mem,
+        };
+
+        let fregs = fregs.ok_or(Error::InvalidOpCode)?;
+        if !matches!(instruction.id(), FLH | FLW | FLD) {
+            return Err(Error::InvalidOpCode);
+        }
+        let vaddr = regs
+            .decode(instruction.rs1)?
+            .fetch(regs)
+            .addi(instruction.imm)
+            .r#as();
+        let size = 1 << instruction.funct3.as_u8();
+        let offset = mmu.translate(vaddr, size, Access::Load, memory)?;
+
+        #[deny(unreachable_patterns)]
+        match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            FLH => float::write::<F16>(
+                fregs,
+                instruction.rd,
+                mem::read::<mem::U16>(memory, offset)?.as_u16() as u64,
+            ),
+            FLW => float::write::<F32>(
+                fregs,
+                instruction.rd,
+                mem::read::<mem::U32>(memory, offset)?.as_u32() as u64,
+            ),
+            FLD => float::write::<F64>(
+                fregs,
+                instruction.rd,
+                mem::read::<mem::U64>(memory, offset)?.as_u64(),
+            ),
+            _ => return Err(Error::InvalidOpCode),
+        }
+        Ok(())
+    }
+}
+
+impl<T> FloatStore for T
+where
+    T: crate::ops::Addi + Copy + Zero + As<u64>,
+{
+    #[inline(always)]
+    fn float_store(
+        instruction: S,
+        regs: &Registers<Self>,
+        fregs: Option<&FRegisters>,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error> {
+        use crate::mem;
+
+        let fregs = fregs.ok_or(Error::InvalidOpCode)?;
+        if !matches!(instruction.id(), FSH | FSW | FSD) {
+            return Err(Error::InvalidOpCode);
+        }
+        let vaddr = regs
+            .decode(instruction.rs1)?
+            .fetch(regs)
+            .addi(instruction.imm)
+            .r#as();
+        let size = 1 << instruction.funct3.as_u8();
+        let offset = mmu.translate(vaddr, size, Access::Store, memory)?;
+        let value = fregs.get(instruction.rs2);
+
+        #[deny(unreachable_patterns)]
+        match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            FSH => mem::write(&mem::U16::new(value as u16), memory, offset),
+            FSW => mem::write(&mem::U32::new(value as u32), memory, offset),
+            FSD => mem::write(&mem::U64::new(value), memory, offset),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+}
+
+impl<T> FloatMath for T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    #[inline(always)]
+    fn float_math(
+        instruction: R,
+        regs: &mut Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error> {
+        use crate::float::{F16, F32, F64};
+
+        #[deny(unreachable_patterns)]
+        match U2::new_truncate(instruction.funct7.as_u8()) {
+            FMT_S => float::math::<T, F32>(instruction, regs, fregs, fcsr),
+            FMT_D => float::math::<T, F64>(instruction, regs, fregs, fcsr),
+            FMT_H => float::math::<T, F16>(instruction, regs, fregs, fcsr),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+}
+
+impl<T> FloatMulAdd for T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    #[inline(always)]
+    fn float_mul_add(
+        instruction: R4,
+        negate_product: bool,
+        negate_addend: bool,
+        regs: &mut Registers<Self>,
+        fregs: Option<&mut FRegisters>,
+        fcsr: &mut Fcsr,
+    ) -> Result<(), Error> {
+        use crate::float::{F16, F32, F64};
+
+        let negate = (negate_product, negate_addend);
+        #[deny(unreachable_patterns)]
+        match instruction.funct2 {
+            FMT_S => float::mul_add::<T, F32>(instruction, negate, regs, fregs, fcsr),
+            FMT_D => float::mul_add::<T, F64>(instruction, negate, regs, fregs, fcsr),
+            FMT_H => float::mul_add::<T, F16>(instruction, negate, regs, fregs, fcsr),
+            _ => Err(Error::InvalidOpCode),
+        }
+    }
+}
+
+impl<T> VectorConfig for T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    #[inline(always)]
+    fn vector_config(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        vector: &mut Vector,
+    ) -> Result<(), Error> {
+        crate::vector::config(instruction, regs, vector)
+    }
+}
+
+impl<T> VectorMath for T
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    #[inline(always)]
+    fn vector_math(
+        instruction: V,
+        regs: &mut Registers<Self>,
+        vector: &mut Vector,
+    ) -> Result<(), Error> {
+        crate::vector::math(instruction, regs, vector)
+    }
+}
+
+impl<T> VectorLoad for T
+where
+    T: Copy + Zero + As<u64>,
+{
+    #[inline(always)]
+    fn vector_load(
+        instruction: VMem,
+        regs: &Registers<Self>,
+        vector: &mut Vector,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error> {
+        crate::vector::load(instruction, regs, vector, mmu, memory)
+    }
+}
+
+impl<T> VectorStore for T
+where
+    T: Copy + Zero + As<u64>,
+{
+    #[inline(always)]
+    fn vector_store(
+        instruction: VMem,
+        regs: &Registers<Self>,
+        vector: &mut Vector,
+        mmu: &Mmu,
+        memory: &mut [u8],
+    ) -> Result<(), Error> {
+        crate::vector::store(instruction, regs, vector, mmu, memory)
+    }
+}
+
+impl MathIW for u64 {
+    fn mathiw(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u64, U12) -> u64 = match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADDIW => ops::Addiw::addiw,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, instruction.imm);
+        }
+        Ok(())
+    }
+}
+
+impl ShiftIW for u64 {
+    fn shiftiw(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        // SLLI.UW takes a 6-bit shift amount, the 32-bit shifts reserve shamt[5]
+        let (id, shamt) = instruction.split(6);
+        let word = shamt.as_u8() < 32;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u64, U7) -> u64 = match id {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            SLLIW if word => ops::Slliw::slliw,
+            SRLIW if word => ops::Srliw::srliw,
+            SRAIW if word => ops::Sraiw::sraiw,
+            RORIW if word => ops::Roriw::roriw,
+            SLLI_UW => ops::SlliUw::slli_uw,
+            UNARY => match shamt.as_u8() {
+                0b00000 => |src1, _| ops::Clzw::clzw(src1),
+                0b00001 => |src1, _| ops::Ctzw::ctzw(src1),
+                0b00010 => |src1, _| ops::Cpopw::cpopw(src1),
+                _ => return Err(Error::InvalidOpCode),
+            },
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(dest_reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(dest_reg) = f(src1, shamt);
+        }
 
         Ok(())
     }
@@ -521,23 +2166,1691 @@ impl MathW for u64 {
             SLLW => ops::Sllw::sllw,
             SRLW => ops::Srlw::srlw,
             SRAW => ops::Sraw::sraw,
+            MULW => ops::Mulw::mulw,
+            DIVW => ops::Divw::divw,
+            DIVUW => ops::Divuw::divuw,
+            REMW => ops::Remw::remw,
+            REMUW => ops::Remuw::remuw,
+            ADD_UW => ops::AddUw::add_uw,
+            SH1ADD_UW => ops::Sh1addUw::sh1add_uw,
+            SH2ADD_UW => ops::Sh2addUw::sh2add_uw,
+            SH3ADD_UW => ops::Sh3addUw::sh3add_uw,
+            ZEXT_H if instruction.rs2.as_u8() == 0 => |src1, _| ops::ZextH::zext_h(src1),
+            PACKW => ops::Packw::packw,
+            ROLW => ops::Rolw::rolw,
+            RORW => ops::Rorw::rorw,
             _ => return Err(Error::InvalidOpCode),
         };
 
-        let ZeroOrRegister::Register(reg) = instruction.rd.into() else {
-            return Err(Error::InvalidOpCode);
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, src2);
+        }
+        Ok(())
+    }
+}
+
+impl Math for u128 {
+    fn math(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, u128) -> u128 = match instruction.id() {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADD => ops::Add::add,
+            SUB => ops::Sub::sub,
+            SLL => ops::Sll::sll,
+            SLT => ops::Slt::slt,
+            SLTU => ops::Sltu::sltu,
+            XOR => ops::Xor::xor,
+            SRL => ops::Srl::srl,
+            SRA => ops::Sra::sra,
+            OR => ops::Or::or,
+            AND => ops::And::and,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, src2);
+        }
+        Ok(())
+    }
+}
+
+impl ShiftI for u128 {
+    fn shifti(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        let (id, shamt) = instruction.split(7);
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, U7) -> u128 = match id {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            SLLI => ops::Slli::slli,
+            SRLI => ops::Srli::srli,
+            SRAI => ops::Srai::srai,
+            _ => return Err(Error::InvalidOpCode),
         };
-        let src1 = ZeroOrRegister::from_u5(instruction.rs1).fetch(regs);
-        let src2 = ZeroOrRegister::from_u5(instruction.rs2).fetch(regs);
-        *regs.get_mut(reg) = f(src1, src2);
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(dest_reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(dest_reg) = f(src1, shamt);
+        }
+
+        Ok(())
+    }
+}
+
+impl LoadQ for u128 {
+    fn loadq(
+        instruction: I,
+        regs: &mut Registers<Self>,
+        mmu: &Mmu,
+        memory: &[u8],
+    ) -> Result<(), Error> {
+        use crate::{mem, ops};
+
+        #[deny(unreachable_patterns)]
+        match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            LQ => (),
+            _ => return Err(Error::InvalidOpCode),
+        }
+
+        let vaddr = ops::Addi::addi(regs.decode(instruction.rs1)?.fetch(regs), instruction.imm);
+        let offset = mmu.translate(vaddr as u64, 16, Access::Load, memory)?;
+        let value = ops::Lq::lq(mem::read::<mem::U128>(memory, offset)?);
+        if let Some(dest) = regs.decode(instruction.rd)?.fetch_mut(regs) {
+            *dest = value;
+        }
+        Ok(())
+    }
+}
+
+impl MathIW for u128 {
+    fn mathiw(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, U12) -> u128 = match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADDIW => ops::Addiw::addiw,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, instruction.imm);
+        }
+        Ok(())
+    }
+}
+
+impl ShiftIW for u128 {
+    fn shiftiw(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        // the 32-bit shifts reserve shamt[6:5]
+        let (id, shamt) = instruction.split(7);
+        let word = shamt.as_u8() < 32;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, U7) -> u128 = match id {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            SLLIW if word => ops::Slliw::slliw,
+            SRLIW if word => ops::Srliw::srliw,
+            SRAIW if word => ops::Sraiw::sraiw,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(dest_reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(dest_reg) = f(src1, shamt);
+        }
+
+        Ok(())
+    }
+}
+
+impl MathW for u128 {
+    fn mathw(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, u128) -> u128 = match instruction.id() {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADDW => ops::Addw::addw,
+            SUBW => ops::Subw::subw,
+            SLLW => ops::Sllw::sllw,
+            SRLW => ops::Srlw::srlw,
+            SRAW => ops::Sraw::sraw,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, src2);
+        }
+        Ok(())
+    }
+}
+
+impl MathID for u128 {
+    fn mathid(instruction: I, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, U12) -> u128 = match instruction.id() {
+            x if x > U3::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADDID => ops::Addid::addid,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, instruction.imm);
+        }
+        Ok(())
+    }
+}
+
+impl ShiftID for u128 {
+    fn shiftid(instruction: Shift, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        // the 64-bit shifts reserve shamt[6]
+        let (id, shamt) = instruction.split(7);
+        let double = shamt.as_u8() < 64;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, U7) -> u128 = match id {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            SLLID if double => ops::Sllid::sllid,
+            SRLID if double => ops::Srlid::srlid,
+            SRAID if double => ops::Sraid::sraid,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        if let ZeroOrRegister::Register(dest_reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(dest_reg) = f(src1, shamt);
+        }
+
+        Ok(())
+    }
+}
+
+impl MathD for u128 {
+    fn mathd(instruction: R, regs: &mut Registers<Self>) -> Result<(), Error> {
+        use crate::ops;
+
+        #[deny(unreachable_patterns)]
+        let f: fn(u128, u128) -> u128 = match instruction.id() {
+            x if x > U10::MAX => unsafe { core::hint::unreachable_unchecked() },
+            ADDD => ops::Addd::addd,
+            SUBD => ops::Subd::subd,
+            SLLD => ops::Slld::slld,
+            SRLD => ops::Srld::srld,
+            SRAD => ops::Srad::srad,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let src1 = regs.decode(instruction.rs1)?.fetch(regs);
+        let src2 = regs.decode(instruction.rs2)?.fetch(regs);
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.rd)? {
+            *regs.get_mut(reg) = f(src1, src2);
+        }
         Ok(())
     }
 }
 
 #[allow(dead_code)]
 const fn implements_instructions<
-    T: Math + MathI + ShiftI + Lui + Auipc + Load + Store + Jal + Jalr + Branch,
+    T: Math + MathI + ShiftI + Lui + Auipc + Load + Store + Atomic + Fence + Csr + Jal + Jalr + Branch,
 >() {
 }
 const _: () = implements_instructions::<u32>();
 const _: () = implements_instructions::<u64>();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::Register;
+
+    #[test]
+    fn zero_destination() {
+        let memory = [0xffu8; 8];
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = 42;
+
+        // addi x0, x0, 0 is the canonical nop
+        u64::mathi(I::from_u32(0x00000013), &mut regs).unwrap();
+        // add x0, x1, x1
+        u64::math(R::from_u32(0x00108033), &mut regs).unwrap();
+        // lui x0, 0x12345
+        u64::lui(U::from_u32(0x12345037), &mut regs).unwrap();
+        // auipc x0, 1
+        u64::auipc(U::from_u32(0x00001017), &mut regs, 0x100).unwrap();
+        // ld x0, 0(x0)
+        u64::load(I::from_u32(0x00003003), &mut regs, &Mmu::BARE, &memory).unwrap();
+
+        assert_eq!(ZeroOrRegister::Zero.fetch(&regs), 0);
+        assert_eq!(regs.get(Register::X1), 42);
+    }
+
+    fn amo(funct5: u8, funct3: u8, rd: u8, rs1: u8, rs2: u8) -> Amo {
+        Amo::from_u32(
+            (funct5 as u32) << 27
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | 0b0101111,
+        )
+    }
+
+    #[test]
+    fn lr_sc() {
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u64>::default();
+        let mut reservation = None;
+        *regs.get_mut(Register::X1) = 8;
+        *regs.get_mut(Register::X2) = 0x1234_5678_9abc_def0;
+        memory[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        u64::atomic(
+            amo(0b00010, 0b011, 3, 1, 0),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X3), u64::MAX);
+        u64::atomic(
+            amo(0b00011, 0b011, 4, 1, 2),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X4), 0);
+        assert_eq!(memory[8..16], 0x1234_5678_9abc_def0u64.to_le_bytes());
+
+        // the reservation is consumed by the first store-conditional
+        u64::atomic(
+            amo(0b00011, 0b011, 4, 1, 0),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X4), 1);
+        assert_eq!(memory[8..16], 0x1234_5678_9abc_def0u64.to_le_bytes());
+
+        // a reservation of a different width does not match
+        u64::atomic(
+            amo(0b00010, 0b010, 3, 1, 0),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X3), 0xffff_ffff_9abc_def0);
+        u64::atomic(
+            amo(0b00011, 0b011, 4, 1, 0),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X4), 1);
+    }
+
+    #[test]
+    fn amo_word() {
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u64>::default();
+        let mut reservation = None;
+        *regs.get_mut(Register::X1) = 4;
+        *regs.get_mut(Register::X2) = 0x0000_0001_0000_0001;
+        memory[4..8].copy_from_slice(&(-5i32).to_le_bytes());
+
+        // AMOMIN.W compares the low words as signed values
+        u64::atomic(
+            amo(0b10000, 0b010, 3, 1, 2),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X3), -5i64 as u64);
+        assert_eq!(memory[4..8], (-5i32).to_le_bytes());
+
+        // AMOMAXU.W treats them as unsigned
+        u64::atomic(
+            amo(0b11100, 0b010, 3, 1, 2),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[4..8], (-5i32).to_le_bytes());
+
+        u64::atomic(
+            amo(0b00000, 0b010, 0, 1, 2),
+            &mut regs,
+            &mut reservation,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[4..8], (-4i32).to_le_bytes());
+        assert_eq!(memory[8], 0);
+    }
+
+    #[test]
+    fn amo_misaligned() {
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u32>::default();
+        let mut reservation = None;
+        *regs.get_mut(Register::X1) = 2;
+
+        assert_eq!(
+            u32::atomic(
+                amo(0b00010, 0b010, 3, 1, 0),
+                &mut regs,
+                &mut reservation,
+                &Mmu::BARE,
+                &mut memory
+            ),
+            Err(Error::LoadAddressMisaligned(2))
+        );
+        assert_eq!(
+            u32::atomic(
+                amo(0b00001, 0b010, 3, 1, 0),
+                &mut regs,
+                &mut reservation,
+                &Mmu::BARE,
+                &mut memory
+            ),
+            Err(Error::StoreAddressMisaligned(2))
+        );
+        assert_eq!(
+            u32::atomic(
+                amo(0b00001, 0b011, 3, 1, 0),
+                &mut regs,
+                &mut reservation,
+                &Mmu::BARE,
+                &mut memory
+            ),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    fn op_fp(funct7: u8, rs2: u8, rs1: u8, funct3: u8, rd: u8) -> R {
+        R::from_u32(
+            (funct7 as u32) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | 0b1010011,
+        )
+    }
+
+    #[test]
+    fn float_load_store() {
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u64>::default();
+        let mut fregs = FRegisters::default();
+        *regs.get_mut(Register::X1) = 16;
+        memory[20..24].copy_from_slice(&1.5f32.to_bits().to_le_bytes());
+
+        // flw f2, 4(x1)
+        u64::float_load(
+            I::from_u32(0x0040a107),
+            &regs,
+            Some(&mut fregs),
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(2)),
+            0xffff_ffff_0000_0000 | 1.5f32.to_bits() as u64
+        );
+
+        // fsw f2, -4(x1)
+        u64::float_store(
+            S::from_u32(0xfe20ae23),
+            &regs,
+            Some(&fregs),
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[12..16], 1.5f32.to_bits().to_le_bytes());
+    }
+
+    #[test]
+    fn float_math() {
+        let mut regs = Registers::<u64>::default();
+        let mut fregs = FRegisters::default();
+        let mut fcsr = Fcsr::default();
+        *fregs.get_mut(U5::new_truncate(1)) = 0xffff_ffff_0000_0000 | 1.5f32.to_bits() as u64;
+        *fregs.get_mut(U5::new_truncate(2)) = 0xffff_ffff_0000_0000 | (-4.0f32).to_bits() as u64;
+
+        // fadd.s f3, f1, f2
+        u64::float_math(
+            op_fp(0b0000000, 2, 1, 0b111, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(3)),
+            0xffff_ffff_0000_0000 | (-2.5f32).to_bits() as u64
+        );
+
+        // fcvt.w.s x1, f3, rne
+        u64::float_math(
+            op_fp(0b1100000, 0, 3, 0b000, 1),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X1), -2i64 as u64);
+        assert_eq!(fcsr.fflags, crate::float::Flags::NX);
+
+        // fmv.x.w x2, f3
+        u64::float_math(
+            op_fp(0b1110000, 0, 3, 0b000, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            regs.get(Register::X2),
+            (-2.5f32).to_bits() as i32 as i64 as u64
+        );
+
+        // fmv.w.x f4, x1 then fclass.s x3, f4 (negative subnormal)
+        u64::float_math(
+            op_fp(0b1111000, 0, 1, 0b000, 4),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        u64::float_math(
+            op_fp(0b1110000, 0, 4, 0b001, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X3), 1 << 9);
+
+        // fcvt.l.s only exists on RV64
+        let mut regs = Registers::<u32>::default();
+        assert_eq!(
+            u32::float_math(
+                op_fp(0b1100000, 2, 3, 0b000, 1),
+                &mut regs,
+                Some(&mut fregs),
+                &mut fcsr
+            ),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn float_mul_add() {
+        let mut regs = Registers::<u64>::default();
+        let mut fregs = FRegisters::default();
+        let mut fcsr = Fcsr::default();
+        *fregs.get_mut(U5::new_truncate(1)) = 0xffff_ffff_0000_0000 | 2.0f32.to_bits() as u64;
+        *fregs.get_mut(U5::new_truncate(2)) = 0xffff_ffff_0000_0000 | 3.0f32.to_bits() as u64;
+        *fregs.get_mut(U5::new_truncate(3)) = 0xffff_ffff_0000_0000 | 1.0f32.to_bits() as u64;
+
+        // fnmsub.s f4, f1, f2, f3
+        let instruction = R4::from_u32(0x1820f24b);
+        u64::float_mul_add(
+            instruction,
+            true,
+            false,
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(4)),
+            0xffff_ffff_0000_0000 | (-5.0f32).to_bits() as u64
+        );
+    }
+
+    #[test]
+    fn half() {
+        const BOX: u64 = 0xffff_ffff_ffff_0000;
+        let mut memory = [0u8; 16];
+        let mut regs = Registers::<u64>::default();
+        let mut fregs = FRegisters::default();
+        let mut fcsr = Fcsr::default();
+        *regs.get_mut(Register::X1) = 8;
+        memory[8..10].copy_from_slice(&0x3e00u16.to_le_bytes());
+
+        // flh f1, 0(x1)
+        u64::float_load(
+            I::from_u32(0x00009087),
+            &regs,
+            Some(&mut fregs),
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(1)), BOX | 0x3e00);
+
+        // fadd.h f2, f1, f1
+        u64::float_math(
+            op_fp(0b0000010, 1, 1, 0b111, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(2)), BOX | 0x4200);
+
+        // fsh f2, 2(x1)
+        u64::float_store(
+            S::from_u32(0x00209127),
+            &regs,
+            Some(&fregs),
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[10..12], 0x4200u16.to_le_bytes());
+
+        // fmadd.h f7, f1, f2, f1
+        let instruction = R4::from_u32(0x0c20f3c3);
+        u64::float_mul_add(
+            instruction,
+            false,
+            false,
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(7)), BOX | 0x4600);
+
+        // fcvt.s.h f3, f2
+        u64::float_math(
+            op_fp(0b0100000, 2, 2, 0b111, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(3)),
+            0xffff_ffff_0000_0000 | 3.0f32.to_bits() as u64
+        );
+        assert_eq!(fcsr.fflags, crate::float::Flags::default());
+
+        // fcvt.h.s f4, f3
+        *fregs.get_mut(U5::new_truncate(3)) = 0xffff_ffff_0000_0000 | 0.1f32.to_bits() as u64;
+        u64::float_math(
+            op_fp(0b0100010, 0, 3, 0b111, 4),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(4)), BOX | 0x2e66);
+        assert_eq!(fcsr.fflags, crate::float::Flags::NX);
+
+        // a single is not a properly boxed half, fadd.h f5, f3, f3
+        fcsr.fflags = crate::float::Flags::default();
+        u64::float_math(
+            op_fp(0b0000010, 3, 3, 0b111, 5),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(5)), BOX | 0x7e00);
+        assert_eq!(fcsr.fflags, crate::float::Flags::default());
+
+        // fmv.x.h x2, f6 sign-extends
+        *fregs.get_mut(U5::new_truncate(6)) = BOX | 0xc000;
+        u64::float_math(
+            op_fp(0b1110010, 0, 6, 0b000, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X2), 0xffff_ffff_ffff_c000);
+    }
+
+    #[test]
+    fn double() {
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u32>::default();
+        let mut fregs = FRegisters::default();
+        let mut fcsr = Fcsr::default();
+        *regs.get_mut(Register::X1) = 8;
+        memory[8..16].copy_from_slice(&0.1f64.to_bits().to_le_bytes());
+
+        // fld f1, 0(x1)
+        u32::float_load(
+            I::from_u32(0x0000b087),
+            &regs,
+            Some(&mut fregs),
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(1)), 0.1f64.to_bits());
+
+        // fcvt.s.d f2, f1
+        u32::float_math(
+            op_fp(0b0100000, 1, 1, 0b111, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(2)),
+            0xffff_ffff_0000_0000 | 0.1f32.to_bits() as u64
+        );
+        assert_eq!(fcsr.fflags, crate::float::Flags::NX);
+
+        // fcvt.d.s f3, f2
+        u32::float_math(
+            op_fp(0b0100001, 0, 2, 0b111, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(3)), (0.1f32 as f64).to_bits());
+
+        // fcvt.d.s of a single that is not NaN-boxed gives the canonical NaN
+        u32::float_math(
+            op_fp(0b0100001, 0, 1, 0b111, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(3)), 0x7ff8_0000_0000_0000);
+
+        // fadd.d f4, f1, f1
+        u32::float_math(
+            op_fp(0b0000001, 1, 1, 0b000, 4),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(4)), 0.2f64.to_bits());
+
+        // fsd f4, 8(x1)
+        u32::float_store(
+            S::from_u32(0x0040b427),
+            &regs,
+            Some(&fregs),
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[16..24], 0.2f64.to_bits().to_le_bytes());
+
+        // fmv.x.d is RV64 only
+        assert_eq!(
+            u32::float_math(
+                op_fp(0b1110001, 0, 4, 0b000, 5),
+                &mut regs,
+                Some(&mut fregs),
+                &mut fcsr
+            ),
+            Err(Error::InvalidOpCode)
+        );
+        let mut regs = Registers::<u64>::default();
+        u64::float_math(
+            op_fp(0b1110001, 0, 4, 0b000, 5),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X5), 0.2f64.to_bits());
+    }
+
+    #[test]
+    fn zfa() {
+        let mut regs = Registers::<u32>::default();
+        let mut fregs = FRegisters::default();
+        let mut fcsr = Fcsr::default();
+
+        // fli.s f1, 2.5
+        u32::float_math(
+            op_fp(0b1111000, 1, 21, 0b000, 1),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(1)),
+            0xffff_ffff_0000_0000 | 2.5f32.to_bits() as u64
+        );
+
+        // fround.s f2, f1, rne
+        u32::float_math(
+            op_fp(0b0100000, 4, 1, 0b000, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(2)),
+            0xffff_ffff_0000_0000 | 2.0f32.to_bits() as u64
+        );
+        assert_eq!(fcsr.fflags, crate::float::Flags::default());
+
+        // froundnx.s f2, f1, rup
+        u32::float_math(
+            op_fp(0b0100000, 5, 1, 0b011, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(
+            fregs.get(U5::new_truncate(2)),
+            0xffff_ffff_0000_0000 | 3.0f32.to_bits() as u64
+        );
+        assert_eq!(fcsr.fflags, crate::float::Flags::NX);
+        fcsr.fflags = crate::float::Flags::default();
+
+        // fli.s f3, nan; fminm.s f4, f1, f3
+        u32::float_math(
+            op_fp(0b1111000, 1, 31, 0b000, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        u32::float_math(
+            op_fp(0b0010100, 3, 1, 0b010, 4),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(4)), 0xffff_ffff_7fc0_0000);
+
+        // fltq.s x1, f3, f1 is quiet
+        u32::float_math(
+            op_fp(0b1010000, 1, 3, 0b101, 1),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X1), 0);
+        assert_eq!(fcsr.fflags, crate::float::Flags::default());
+
+        // fleq.s x1, f2, f1
+        u32::float_math(
+            op_fp(0b1010000, 1, 2, 0b100, 1),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X1), 0);
+
+        // fli.d f5, -1.0; fcvtmod.w.d x2, f5, rtz
+        u32::float_math(
+            op_fp(0b1111001, 1, 0, 0b000, 5),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(5)), (-1.0f64).to_bits());
+        u32::float_math(
+            op_fp(0b1100001, 8, 5, 0b001, 2),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X2), u32::MAX);
+
+        // fcvtmod.w.d requires the rtz rounding mode
+        assert_eq!(
+            u32::float_math(
+                op_fp(0b1100001, 8, 5, 0b000, 2),
+                &mut regs,
+                Some(&mut fregs),
+                &mut fcsr
+            ),
+            Err(Error::InvalidOpCode)
+        );
+
+        // fmvh.x.d x3, f5; fmvp.d.x f6, x2, x3
+        u32::float_math(
+            op_fp(0b1110001, 1, 5, 0b000, 3),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X3), 0xbff0_0000);
+        *regs.get_mut(Register::X2) = 0x1234_5678;
+        u32::float_math(
+            op_fp(0b1011001, 3, 2, 0b000, 6),
+            &mut regs,
+            Some(&mut fregs),
+            &mut fcsr,
+        )
+        .unwrap();
+        assert_eq!(fregs.get(U5::new_truncate(6)), 0xbff0_0000_1234_5678);
+    }
+
+    #[test]
+    fn zfinx() {
+        let mut memory = [0u8; 16];
+        let mut regs = Registers::<u32>::default();
+        let mut fcsr = Fcsr::default();
+        *regs.get_mut(Register::X3) = 0x3ff0_0000;
+
+        // fadd.d x4, x2, x2 on the x2/x3 and x4/x5 pairs
+        u32::float_math(op_fp(0b0000001, 2, 2, 0b000, 4), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X4), 0);
+        assert_eq!(regs.get(Register::X5), 0x4000_0000);
+
+        // odd registers cannot name a pair
+        assert_eq!(
+            u32::float_math(op_fp(0b0000001, 2, 3, 0b000, 4), &mut regs, None, &mut fcsr),
+            Err(Error::InvalidOpCode)
+        );
+
+        // fadd.d x6, x0, x4 reads the pair of x0 as zero
+        u32::float_math(op_fp(0b0000001, 4, 0, 0b000, 6), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X7), 0x4000_0000);
+
+        // fcvt.s.d x8, x4 and fcvt.w.d x9, x4, rtz
+        u32::float_math(op_fp(0b0100000, 1, 4, 0b000, 8), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X8), 2.0f32.to_bits());
+        u32::float_math(op_fp(0b1100001, 0, 4, 0b001, 9), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X9), 2);
+
+        // fmadd.d x10, x4, x4, x2
+        let instruction = R4::from_u32(0x1242_0543);
+        u32::float_mul_add(instruction, false, false, &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X11), 0x4014_0000);
+
+        // fmv.x.w, fli.s, flw and fsw are illegal
+        assert_eq!(
+            u32::float_math(op_fp(0b1110000, 0, 8, 0b000, 9), &mut regs, None, &mut fcsr),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            u32::float_math(
+                op_fp(0b1111000, 1, 16, 0b000, 9),
+                &mut regs,
+                None,
+                &mut fcsr
+            ),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            u32::float_load(I::from_u32(0x0000a087), &regs, None, &Mmu::BARE, &memory),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            u32::float_store(
+                S::from_u32(0x0020a027),
+                &regs,
+                None,
+                &Mmu::BARE,
+                &mut memory
+            ),
+            Err(Error::InvalidOpCode)
+        );
+
+        // none of Zfa is available: fminm.s, fround.s, froundnx.s, fleq.s, fltq.s and
+        // fcvtmod.w.d
+        for instruction in [
+            op_fp(0b0010100, 1, 1, 0b010, 9),
+            op_fp(0b0010100, 1, 1, 0b011, 9),
+            op_fp(0b0100000, 0b00100, 1, 0b000, 9),
+            op_fp(0b0100000, 0b00101, 1, 0b000, 9),
+            op_fp(0b1010000, 1, 1, 0b100, 9),
+            op_fp(0b1010000, 1, 1, 0b101, 9),
+            op_fp(0b1100001, 0b01000, 4, 0b001, 9),
+        ] {
+            assert_eq!(
+                u32::float_math(instruction, &mut regs, None, &mut fcsr),
+                Err(Error::InvalidOpCode)
+            );
+        }
+
+        // fclass.s x9, x8 is still available
+        u32::float_math(op_fp(0b1110000, 0, 8, 0b001, 9), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(regs.get(Register::X9), 1 << 6);
+
+        // on RV64 narrower results are sign-extended, fsgnjn.s x2, x1, x1
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = 1.5f32.to_bits() as u64;
+        u64::float_math(op_fp(0b0010000, 1, 1, 0b001, 2), &mut regs, None, &mut fcsr).unwrap();
+        assert_eq!(
+            regs.get(Register::X2),
+            0xffff_ffff_0000_0000 | (-1.5f32).to_bits() as u64
+        );
+    }
+
+    #[test]
+    fn pointer_masking() {
+        use crate::{csr, hart::Privilege, isa::Isa};
+
+        let mut memory = [0u8; 32];
+        let mut hart = Hart::<u64>::new(0);
+        *hart.regs.get_mut(Register::X1) = 0xabcd_0000_0000_0010;
+        *hart.regs.get_mut(Register::X2) = 0x0123_4567_89ab_cdef;
+
+        // sd x2, 0(x1) and ld x3, 0(x1) with PMLEN = 16
+        csr::write(&mut hart, csr::MSECCFG, 0b11 << 32).unwrap();
+        let mmu = Mmu::data(&hart);
+        let regs = &mut hart.regs;
+        u64::store(S::from_u32(0x0020b023), regs, &mmu, &mut memory).unwrap();
+        assert_eq!(memory[16..24], 0x0123_4567_89ab_cdef_u64.to_le_bytes());
+        u64::load(I::from_u32(0x0000b183), regs, &mmu, &memory).unwrap();
+        assert_eq!(regs.get(Register::X3), 0x0123_4567_89ab_cdef);
+        // PMLEN = 7 keeps bit 56 of the tag
+        csr::write(&mut hart, csr::MSECCFG, 0b10 << 32).unwrap();
+        let mmu = Mmu::data(&hart);
+        assert_eq!(
+            u64::load(I::from_u32(0x0000b183), &mut hart.regs, &mmu, &memory),
+            Err(Error::LoadAccessFault(0x01cd_0000_0000_0010))
+        );
+
+        // masking follows the privilege mode of the hart
+        csr::write(&mut hart, csr::SENVCFG, 0b11 << 32).unwrap();
+        hart.privilege = Privilege::User;
+        u64::execute(0x0000b203, &mut hart, &mut memory);
+        assert_eq!(hart.regs.get(Register::X4), 0x0123_4567_89ab_cdef);
+    }
+
+    #[test]
+    fn compressed_link() {
+        let mut regs = Registers::<u32>::default();
+        let mut pc = 0x100u32;
+
+        // c.jal -2
+        u32::jal(J::from_u32(0xfffff0ef), &mut regs, &mut pc, 2, true).unwrap();
+        assert_eq!(regs.get(Register::X1), 0x102);
+        assert_eq!(pc, 0xfe);
+
+        // c.jalr a0
+        *regs.get_mut(Register::X10) = 0x200;
+        u32::jalr(I::from_u32(0x000500e7), &mut regs, &mut pc, 2, true, None).unwrap();
+        assert_eq!(regs.get(Register::X1), 0x100);
+        assert_eq!(pc, 0x200);
+
+        // c.beqz a0, 8 not taken
+        u32::branch(B::from_u32(0x00050463), &mut regs, &mut pc, 2, true).unwrap();
+        assert_eq!(pc, 0x202);
+    }
+
+    #[test]
+    fn misaligned_jump() {
+        let mut regs = Registers::<u64>::default();
+        let mut pc = 0x100u64;
+
+        // jal ra, 2 without C
+        assert_eq!(
+            u64::jal(J::from_u32(0x002000ef), &mut regs, &mut pc, 4, false),
+            Err(Error::InstructionAddressMisaligned(0x102))
+        );
+        // jalr ra, 1(a0) clears bit 0 only
+        *regs.get_mut(Register::X10) = 0x201;
+        assert_eq!(
+            u64::jalr(I::from_u32(0x001500e7), &mut regs, &mut pc, 4, false, None),
+            Err(Error::InstructionAddressMisaligned(0x202))
+        );
+        assert_eq!(regs.get(Register::X1), 0);
+        assert_eq!(pc, 0x100);
+
+        // beq x0, x0, 6 is misaligned when taken only
+        assert_eq!(
+            u64::branch(B::from_u32(0x00000363), &mut regs, &mut pc, 4, false),
+            Err(Error::InstructionAddressMisaligned(0x106))
+        );
+        assert_eq!(pc, 0x100);
+        // bne x0, x0, 6
+        u64::branch(B::from_u32(0x00001363), &mut regs, &mut pc, 4, false).unwrap();
+        assert_eq!(pc, 0x104);
+
+        // the same jumps are aligned with C
+        u64::branch(B::from_u32(0x00000363), &mut regs, &mut pc, 4, true).unwrap();
+        assert_eq!(pc, 0x10a);
+        u64::jalr(I::from_u32(0x001500e7), &mut regs, &mut pc, 4, true, None).unwrap();
+        assert_eq!(pc, 0x202);
+
+        // RV128 has no C, the trap leaves ra untouched
+        let mut hart = Hart::<u128>::new(0x100);
+        <u128 as crate::isa::Isa>::execute(0x002000ef, &mut hart, &mut []);
+        assert_eq!(hart.pc, 0);
+        assert_eq!(hart.regs.get(Register::X1), 0);
+    }
+
+    #[test]
+    fn landing_pad_expectation() {
+        let mut regs = Registers::<u64>::default();
+        let mut pc = 0x100u64;
+        let mut elp = false;
+        *regs.get_mut(Register::X10) = 0x200;
+
+        // jalr a0 expects a landing pad, returns through ra and jumps through t2 do not
+        u64::jalr(
+            I::from_u32(0x000500e7),
+            &mut regs,
+            &mut pc,
+            4,
+            true,
+            Some(&mut elp),
+        )
+        .unwrap();
+        assert!(elp);
+        u64::jalr(
+            I::from_u32(0x00008067),
+            &mut regs,
+            &mut pc,
+            4,
+            true,
+            Some(&mut elp),
+        )
+        .unwrap();
+        assert!(!elp);
+        u64::jalr(
+            I::from_u32(0x00038067),
+            &mut regs,
+            &mut pc,
+            4,
+            true,
+            Some(&mut elp),
+        )
+        .unwrap();
+        assert!(!elp);
+    }
+
+    #[test]
+    fn shadow_stack() {
+        use crate::cfi::{Cfi, SHADOW_STACK_FAULT};
+
+        let mut memory = [0u8; 64];
+        let mut regs = Registers::<u64>::default();
+        let mut cfi = Cfi {
+            ssp: 32u64,
+            ..Default::default()
+        };
+        *regs.get_mut(Register::X1) = 0x1234;
+        *regs.get_mut(Register::X2) = 0xdead;
+
+        // without shadow stacks ssrdp x2 is a MOP writing zero
+        u64::mop(R::from_u32(0xcdc04173), &mut regs, None, &mut memory).unwrap();
+        assert_eq!(regs.get(Register::X2), 0);
+
+        // sspush x1; ssrdp x2
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        u64::mop(R::from_u32(0xce104073), &mut regs, ss, &mut memory).unwrap();
+        assert_eq!(cfi.ssp, 24);
+        u64::mop(
+            R::from_u32(0xcdc04173),
+            &mut regs,
+            Some(cfi.shadow_stack(&Mmu::BARE)),
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X2), 24);
+
+        // sspopchk x5 with a mismatching return address, then sspopchk x1
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        assert_eq!(
+            u64::mop(R::from_u32(0xcdc2c073), &mut regs, ss, &mut memory),
+            Err(Error::SoftwareCheck(SHADOW_STACK_FAULT))
+        );
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        u64::mop(R::from_u32(0xcdc0c073), &mut regs, ss, &mut memory).unwrap();
+        assert_eq!(cfi.ssp, 32);
+
+        // mop.r.0 x3, x1 and a reserved encoding
+        *regs.get_mut(Register::X3) = 1;
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        u64::mop(R::from_u32(0x81c0c1f3), &mut regs, ss, &mut memory).unwrap();
+        assert_eq!(regs.get(Register::X3), 0);
+        assert_eq!(
+            u64::mop(R::from_u32(0x000041f3), &mut regs, None, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+
+        // ssamoswap.w x3, x2, (x4) sign-extends the previous value
+        *regs.get_mut(Register::X4) = 8;
+        memory[8..12].copy_from_slice(&0x8000_0000u32.to_le_bytes());
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        u64::ssamoswap(Amo::from_u32(0x482221af), &mut regs, ss, &mut memory).unwrap();
+        assert_eq!(regs.get(Register::X3), 0xffff_ffff_8000_0000);
+        assert_eq!(memory[8..12], 24u32.to_le_bytes());
+        assert_eq!(
+            u64::ssamoswap(Amo::from_u32(0x482231af), &mut regs, None, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+
+        // ssamoswap.d is RV64 only
+        let mut regs = Registers::<u32>::default();
+        let mut cfi = Cfi::<u32>::default();
+        let ss = Some(cfi.shadow_stack(&Mmu::BARE));
+        assert_eq!(
+            u32::ssamoswap(Amo::from_u32(0x482231af), &mut regs, ss, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    fn csr(funct3: u8, rd: u8, rs1: u8, addr: u16) -> I {
+        I::from_u32(
+            (addr as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | 0b1110011,
+        )
+    }
+
+    #[test]
+    fn csr_access() {
+        let mut hart = Hart::<u32>::default();
+        *hart.regs.get_mut(Register::X1) = 0xf0;
+
+        // csrrw x2, mscratch, x1
+        u32::csr(csr(0b001, 2, 1, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0);
+        // csrrsi x2, mscratch, 0b1111
+        u32::csr(csr(0b110, 2, 0b01111, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0xf0);
+        // csrrc x2, mscratch, x1
+        u32::csr(csr(0b011, 2, 1, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0xff);
+        // csrrs x2, mscratch, x0
+        u32::csr(csr(0b010, 2, 0, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0x0f);
+
+        // fcsr is a view over the floating-point state
+        u32::csr(csr(0b101, 0, 0b00101, 0x001), &mut hart).unwrap();
+        assert_eq!(hart.fcsr.bits(), 0b00101);
+
+        // csrrw x0, mhartid, x1 writes a read-only CSR
+        assert_eq!(
+            u32::csr(csr(0b001, 0, 1, 0xf14), &mut hart),
+            Err(Error::InvalidOpCode)
+        );
+        // csrrs x2, mhartid, x0 only reads it
+        u32::csr(csr(0b010, 2, 0, 0xf14), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0);
+
+        // machine CSRs are not accessible from user mode
+        hart.privilege = crate::hart::Privilege::User;
+        assert_eq!(
+            u32::csr(csr(0b010, 2, 0, 0x340), &mut hart),
+            Err(Error::InvalidOpCode)
+        );
+        u32::csr(csr(0b010, 2, 0, 0x003), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X2), 0b00101);
+    }
+
+    #[test]
+    fn csr_suppression() {
+        fn count_read(hart: &mut Hart<u64>, _: U12) -> u64 {
+            hart.pc += 1;
+            0
+        }
+
+        fn count_write(hart: &mut Hart<u64>, _: U12, _: u64) {
+            hart.pc += 0x100;
+        }
+
+        let mut hart = Hart::<u64>::default();
+        hart.csrs.insert(
+            crate::csr::MSCRATCH,
+            crate::csr::Entry::new(0)
+                .on_read(count_read)
+                .on_write(count_write),
+        );
+
+        // csrrw x0 does not read
+        u64::csr(csr(0b001, 0, 1, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.pc, 0x100);
+        // csrrwi x1 reads and writes
+        u64::csr(csr(0b101, 1, 0, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.pc, 0x201);
+        // csrrs/csrrc with x0 and csrrsi/csrrci with 0 do not write
+        for funct3 in [0b010, 0b011, 0b110, 0b111] {
+            u64::csr(csr(funct3, 1, 0, 0x340), &mut hart).unwrap();
+        }
+        assert_eq!(hart.pc, 0x205);
+        // csrrs with a non-zero register writes even if the value is zero
+        u64::csr(csr(0b010, 0, 2, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.pc, 0x306);
+    }
+
+    #[test]
+    fn fence() {
+        use crate::decode::Fence as F;
+
+        // fence rw, rw
+        assert_eq!(u32::fence(F::from_u32(0x0330000f)), Ok(()));
+        // fence.tso
+        assert_eq!(u32::fence(F::from_u32(0x8330000f)), Ok(()));
+        // pause
+        assert_eq!(u32::fence(F::from_u32(0x0100000f)), Ok(()));
+        // fence.i
+        assert_eq!(u64::fence(F::from_u32(0x0000100f)), Ok(()));
+        // funct3 010 is not a fence
+        assert_eq!(
+            u64::fence(F::from_u32(0x0000200f)),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn cache_block() {
+        let mut memory = [0xffu8; 256];
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = 70;
+
+        // cbo.zero (x1) clears the whole block holding the address
+        u64::cache_block(I::from_u32(0x0040a00f), &regs, 64, &Mmu::BARE, &mut memory).unwrap();
+        assert!(memory[..64].iter().all(|&b| b == 0xff));
+        assert!(memory[64..128].iter().all(|&b| b == 0));
+        assert!(memory[128..].iter().all(|&b| b == 0xff));
+
+        // cbo.clean (x1) leaves memory untouched
+        memory[70] = 1;
+        u64::cache_block(I::from_u32(0x0010a00f), &regs, 64, &Mmu::BARE, &mut memory).unwrap();
+        assert_eq!(memory[70], 1);
+
+        // blocks past the end of memory fault with the effective address
+        *regs.get_mut(Register::X1) = 0x1f8;
+        assert_eq!(
+            u64::cache_block(I::from_u32(0x0040a00f), &regs, 64, &Mmu::BARE, &mut memory),
+            Err(Error::StoreAccessFault(0x1f8))
+        );
+        *regs.get_mut(Register::X1) = 0xf8;
+        assert_eq!(
+            u64::cache_block(I::from_u32(0x0010a00f), &regs, 512, &Mmu::BARE, &mut memory),
+            Err(Error::StoreAccessFault(0xf8))
+        );
+
+        // rd must be x0 and funct12 must name an operation
+        assert_eq!(
+            u64::cache_block(I::from_u32(0x0040a10f), &regs, 64, &Mmu::BARE, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            u64::cache_block(I::from_u32(0x0030a00f), &regs, 64, &Mmu::BARE, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+
+        // prefetch.r 0(x1) is an ori writing x0
+        u64::mathi(I::from_u32(0x0010e013), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X1), 0xf8);
+    }
+
+    #[test]
+    fn cache_block_enables() {
+        use crate::{csr, hart::Privilege, isa::Isa};
+
+        let mut memory = [0xffu8; 128];
+        let mut hart = Hart::<u64>::new(0);
+        hart.privilege = Privilege::User;
+        *hart.regs.get_mut(Register::X1) = 64;
+
+        // cbo.zero (x1) and cbo.inval (x1) are illegal in U-mode until both levels enable them
+        csr::write(&mut hart, csr::MENVCFG, CBZE | CBIE).unwrap();
+        u64::execute(0x0040a00f, &mut hart, &mut memory);
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(2));
+        assert!(memory.iter().all(|&b| b == 0xff));
+
+        hart.privilege = Privilege::User;
+        hart.pc = 0;
+        csr::write(&mut hart, csr::SENVCFG, CBZE).unwrap();
+        u64::execute(0x0040a00f, &mut hart, &mut memory);
+        assert_eq!(hart.pc, 4);
+        assert!(memory[64..].iter().all(|&b| b == 0));
+        u64::execute(0x0000a00f, &mut hart, &mut memory);
+        assert_eq!(hart.pc, 0);
+        assert_eq!(hart.privilege, Privilege::Machine);
+
+        // M-mode is never restricted, and the reserved CBIE encoding reads as disabled
+        csr::write(&mut hart, csr::MENVCFG, 0b10 << 4).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MENVCFG), Ok(0));
+        u64::execute(0x0000a00f, &mut hart, &mut memory);
+        assert_eq!(hart.pc, 4);
+    }
+
+    #[test]
+    fn bitmanip() {
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = 0x8000_0000_0000_00f0;
+
+        // srai x2, x1, 35
+        u64::shifti(Shift::from_u32(0x4230d113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0xffff_ffff_f000_0000);
+        // rev8 x2, x1
+        u64::shifti(Shift::from_u32(0x6b80d113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0xf000_0000_0000_0080);
+        // cpop x2, x1
+        u64::shifti(Shift::from_u32(0x60209113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 5);
+        // the RV32 rev8 encoding is reserved on RV64
+        assert_eq!(
+            u64::shifti(Shift::from_u32(0x6980d113), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+
+        // slli.uw x2, x1, 36
+        u64::shiftiw(Shift::from_u32(0x0a40911b), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0xf00_0000_0000);
+        // slliw with shamt[5] set is reserved
+        assert_eq!(
+            u64::shiftiw(Shift::from_u32(0x0200911b), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+
+        // zext.h x2, x1 lives in OP-32 on RV64
+        u64::mathw(R::from_u32(0x0800c13b), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0xf0);
+        // while its RV32 encoding is pack x2, x1, x0 and keeps the low word
+        *regs.get_mut(Register::X1) = 0x8000_0000_8000_00f0;
+        u64::math(R::from_u32(0x0800c133), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0x8000_00f0);
+
+        let mut regs = Registers::<u32>::default();
+        *regs.get_mut(Register::X1) = 0x1234_5678;
+        u32::math(R::from_u32(0x0800c133), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0x5678);
+        // rori x2, x1, 4
+        u32::shifti(Shift::from_u32(0x6040d113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0x8123_4567);
+    }
+
+    #[test]
+    fn zicond() {
+        fn czero(funct3: u8, rd: u8, rs1: u8, rs2: u8) -> R {
+            R::from_u32(
+                0b0000111 << 25
+                    | (rs2 as u32) << 20
+                    | (rs1 as u32) << 15
+                    | (funct3 as u32) << 12
+                    | (rd as u32) << 7
+                    | 0b0110011,
+            )
+        }
+
+        let mut regs = Registers::<u32>::default();
+        *regs.get_mut(Register::X1) = 42;
+        *regs.get_mut(Register::X2) = 1;
+
+        // czero.eqz x3, x1, x2
+        u32::math(czero(0b101, 3, 1, 2), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), 42);
+        // czero.nez x3, x1, x2
+        u32::math(czero(0b111, 3, 1, 2), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), 0);
+
+        // rs2 = x0 always reads as zero
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = u64::MAX;
+        u64::math(czero(0b101, 3, 1, 0), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), 0);
+        u64::math(czero(0b111, 3, 1, 0), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), u64::MAX);
+
+        // rd = x0 discards the result
+        u64::math(czero(0b111, 0, 1, 0), &mut regs).unwrap();
+        u32::math(czero(0b101, 0, 1, 2), &mut Registers::<u32>::default()).unwrap();
+        assert_eq!(regs.get(Register::X1), u64::MAX);
+        assert_eq!(regs.get(Register::X3), u64::MAX);
+    }
+
+    #[test]
+    fn crypto() {
+        use crate::ops::{
+            Aes32esmi, Aes64ks1i, Aes64ks2, Sha256sig0, Sha512sig1h, Sha512sum1, Sm4ks, Zip,
+        };
+
+        fn r(funct7: u8, funct3: u8, rd: u8, rs1: u8, rs2: u8, opcode: u8) -> u32 {
+            (funct7 as u32) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | opcode as u32
+        }
+        fn unary(imm: u16, funct3: u8) -> Shift {
+            // x2 <- x1
+            Shift::from_u32(
+                (imm as u32) << 20 | 1 << 15 | (funct3 as u32) << 12 | 2 << 7 | 0b0010011,
+            )
+        }
+
+        let mut regs = Registers::<u32>::default();
+        *regs.get_mut(Register::X1) = 0x0123_4567;
+        *regs.get_mut(Register::X2) = 0x89ab_cdef;
+
+        // aes32esmi x3, x1, x2, 2
+        u32::math(R::from_u32(r(0b10_10011, 0, 3, 1, 2, 0b0110011)), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X3),
+            Aes32esmi::aes32esmi::<2>(0x0123_4567u32, 0x89ab_cdef)
+        );
+        // sha512sig1h x3, x1, x2
+        u32::math(R::from_u32(r(0b0101111, 0, 3, 1, 2, 0b0110011)), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X3),
+            Sha512sig1h::sha512sig1h(0x0123_4567u32, 0x89ab_cdef)
+        );
+        // sha256sig0 x2, x1
+        u32::shifti(unary(0x102, 0b001), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X2),
+            Sha256sig0::sha256sig0(0x0123_4567u32)
+        );
+        // zip x2, x1
+        u32::shifti(unary(0x08f, 0b001), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), Zip::zip(0x0123_4567u32));
+        // sha512sum1 x2, x1 is RV64 only
+        assert_eq!(
+            u32::shifti(unary(0x105, 0b001), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+
+        let mut regs = Registers::<u64>::default();
+        *regs.get_mut(Register::X1) = 0x0123_4567_89ab_cdef;
+        *regs.get_mut(Register::X3) = 0xfedc_ba98_7654_3210;
+
+        // sm4ks x4, x1, x3, 1
+        u64::math(R::from_u32(r(0b01_11010, 0, 4, 1, 3, 0b0110011)), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X4),
+            Sm4ks::sm4ks::<1>(0x0123_4567_89ab_cdefu64, 0xfedc_ba98_7654_3210)
+        );
+        // aes64ks2 x4, x1, x3
+        u64::math(R::from_u32(r(0b0111111, 0, 4, 1, 3, 0b0110011)), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X4),
+            Aes64ks2::aes64ks2(0x0123_4567_89ab_cdefu64, 0xfedc_ba98_7654_3210)
+        );
+        // the RV32 AES instructions are not available on RV64
+        assert_eq!(
+            u64::math(R::from_u32(r(0b10_10011, 0, 3, 1, 2, 0b0110011)), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+        // packw x4, x1, x3
+        u64::mathw(
+            R::from_u32(r(0b0000100, 0b100, 4, 1, 3, 0b0111011)),
+            &mut regs,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X4), 0x3210_cdef);
+        // sha512sum1 x2, x1
+        u64::shifti(unary(0x105, 0b001), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X2),
+            Sha512sum1::sha512sum1(0x0123_4567_89ab_cdefu64)
+        );
+        // brev8 x2, x1
+        u64::shifti(unary(0x687, 0b101), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 0x80c4_a2e6_91d5_b3f7);
+        // aes64ks1i x2, x1, 0xa and the reserved round number 0xb
+        u64::shifti(unary(0x31a, 0b001), &mut regs).unwrap();
+        assert_eq!(
+            regs.get(Register::X2),
+            Aes64ks1i::aes64ks1i(0x0123_4567_89ab_cdefu64, U7::new_truncate(0xa))
+        );
+        assert_eq!(
+            u64::shifti(unary(0x31b, 0b001), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+        // zip x2, x1 is RV32 only
+        assert_eq!(
+            u64::shifti(unary(0x08f, 0b001), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn embedded() {
+        let mut hart = Hart::<u32>::embedded(0);
+        *hart.regs.get_mut(Register::X1) = 7;
+        *hart.regs.get_mut(Register::X15) = 3;
+
+        // add x15, x1, x15
+        u32::math(R::from_u32(0x00f087b3), &mut hart.regs).unwrap();
+        assert_eq!(hart.regs.get(Register::X15), 10);
+        // add x16, x1, x15 and add x15, x1, x16
+        for encoded in [0x00f08833, 0x010087b3] {
+            assert_eq!(
+                u32::math(R::from_u32(encoded), &mut hart.regs),
+                Err(Error::InvalidOpCode)
+            );
+        }
+        assert_eq!(hart.regs.get(Register::X15), 10);
+        // lui x31, 1
+        assert_eq!(
+            u32::lui(U::from_u32(0x00001fb7), &mut hart.regs),
+            Err(Error::InvalidOpCode)
+        );
+
+        // amoswap.w x16, x15, (x1) leaves memory untouched
+        let mut memory = [0u8; 16];
+        *hart.regs.get_mut(Register::X1) = 8;
+        assert_eq!(
+            u32::atomic(
+                amo(0b00001, 0b010, 16, 1, 15),
+                &mut hart.regs,
+                &mut hart.reservation,
+                &Mmu::BARE,
+                &mut memory
+            ),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(memory, [0; 16]);
+
+        // csrrw x1, mscratch, x20 names a register, csrrwi x1, mscratch, 20 an immediate
+        assert_eq!(
+            u32::csr(csr(0b001, 1, 20, 0x340), &mut hart),
+            Err(Error::InvalidOpCode)
+        );
+        u32::csr(csr(0b101, 1, 20, 0x340), &mut hart).unwrap();
+        u32::csr(csr(0b010, 1, 0, 0x340), &mut hart).unwrap();
+        assert_eq!(hart.regs.get(Register::X1), 20);
+    }
+
+    #[test]
+    fn rv128() {
+        let mut regs = Registers::<u128>::default();
+        *regs.get_mut(Register::X1) = 1;
+
+        // slli x2, x1, 100
+        u128::shifti(Shift::from_u32(0x06409113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), 1 << 100);
+        // srai x2, x1, 100
+        *regs.get_mut(Register::X1) = 1 << 127;
+        u128::shifti(Shift::from_u32(0x4640d113), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X2), (i128::MIN >> 100) as u128);
+
+        // addd x3, x1, x2
+        *regs.get_mut(Register::X1) = i64::MAX as u128;
+        *regs.get_mut(Register::X2) = 1;
+        u128::mathd(R::from_u32(0x002081fb), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), i64::MIN as i128 as u128);
+        // addid x3, x1, -1
+        *regs.get_mut(Register::X1) = 0;
+        u128::mathid(I::from_u32(0xfff081db), &mut regs).unwrap();
+        assert_eq!(regs.get(Register::X3), u128::MAX);
+        // sllid x3, x1, 64 and sraiw x3, x1, 32 are reserved
+        assert_eq!(
+            u128::shiftid(Shift::from_u32(0x040091db), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            u128::shiftiw(Shift::from_u32(0x4200d19b), &mut regs),
+            Err(Error::InvalidOpCode)
+        );
+
+        // sq x1, 16(x0) and lq x4, 16(x0)
+        let mut memory = [0u8; 32];
+        *regs.get_mut(Register::X1) = 0x0123_4567_89ab_cdef_fedc_ba98_8765_4321;
+        u128::store(S::from_u32(0x00104823), &mut regs, &Mmu::BARE, &mut memory).unwrap();
+        u128::loadq(I::from_u32(0x0100220f), &mut regs, &Mmu::BARE, &memory).unwrap();
+        assert_eq!(regs.get(Register::X4), regs.get(Register::X1));
+        // ld x5, 16(x0) sign-extends and ldu x6, 16(x0) does not
+        u128::load(I::from_u32(0x01003283), &mut regs, &Mmu::BARE, &memory).unwrap();
+        assert_eq!(
+            regs.get(Register::X5),
+            0xfedc_ba98_8765_4321_u64 as i64 as u128
+        );
+        u128::load(I::from_u32(0x01007303), &mut regs, &Mmu::BARE, &memory).unwrap();
+        assert_eq!(regs.get(Register::X6), 0xfedc_ba98_8765_4321);
+
+        // lq x4, 16(x0) through the hart, MISC-MEM is shared with the fences
+        let mut hart = Hart::<u128>::new(0);
+        <u128 as crate::isa::Isa>::execute(0x0100220f, &mut hart, &mut memory);
+        assert_eq!(hart.regs.get(Register::X4), regs.get(Register::X1));
+        assert_eq!(hart.pc, 4);
+    }
+}
@@ -1,11 +1,21 @@
 use crate::{
-    decode::{B, I, J, R, S, U},
+    cfi,
+    counters::Event,
+    csr,
+    decode::{self, Amo, Fence as FenceInstruction, VMem, B, I, J, R, R4, S, U, U12, U5, V},
+    error::Error,
+    hart::{Hart, Privilege},
     instructions::{
-        Auipc, Branch, Jal, Jalr, Load, Lui, Math, MathI, MathIW, MathW, ShiftI, ShiftIW, Store,
+        self, Atomic, Auipc, Branch, CacheBlock, Csr, Fence, FloatLoad, FloatMath, FloatMulAdd,
+        FloatStore, Jal, Jalr, Load, LoadQ, Lui, Math, MathD, MathI, MathID, MathIW, MathW, Mop,
+        Privileged, ShadowStackSwap, ShiftI, ShiftID, ShiftIW, Store, VectorConfig, VectorLoad,
+        VectorMath, VectorStore,
     },
-    num::As,
-    ops::Add,
-    registers::Registers,
+    mem,
+    mmu::{Access, Mmu},
+    num::{As, Zero},
+    ops::{Add, Addi},
+    trap,
 };
 
 const LUI: u8 = 0b0110111;
@@ -21,14 +31,147 @@ const FENCE: u8 = 0b0001111;
 const SYSCALL: u8 = 0b1110011;
 const MATHIW: u8 = 0b0011011;
 const MATHW: u8 = 0b0111011;
+const AMO: u8 = 0b0101111;
+const LOAD_FP: u8 = 0b0000111;
+const STORE_FP: u8 = 0b0100111;
+const MADD: u8 = 0b1000011;
+const MSUB: u8 = 0b1000111;
+const NMSUB: u8 = 0b1001011;
+const NMADD: u8 = 0b1001111;
+const OP_FP: u8 = 0b1010011;
+const MATHID: u8 = 0b1011011;
+const MATHD: u8 = 0b1111011;
+const OP_V: u8 = 0b1010111;
 
 pub trait Isa: Sized {
-    fn execute(encoded: u32, regs: &mut Registers<Self>, pc: &mut Self, memory: &mut [u8]);
+    /// Whether the C extension is supported, IALIGN is 16 with it and 32 without.
+    const COMPRESSED: bool;
+
+    /// Executes `encoded`, the pc only advances when it does not raise an exception.
+    fn try_execute(encoded: u32, hart: &mut Hart<Self>, memory: &mut [u8]) -> Result<(), Error>;
+
+    /// Executes `encoded`, delivering the exception it raises to the hart.
+    fn execute(encoded: u32, hart: &mut Hart<Self>, memory: &mut [u8])
+    where
+        Self: Copy + As<u64>,
+        u64: As<Self>,
+    {
+        if let Err(error) = Self::try_execute(encoded, hart, memory) {
+            trap::take(hart, error, encoded);
+        }
+    }
+}
+
+/// Fetches and executes the instruction at the pc of `hart`, a fetch outside of `memory` raises
+/// an instruction access fault. A pending and enabled interrupt is taken instead, the handler
+/// starts at the next step.
+pub fn step<T>(hart: &mut Hart<T>, memory: &mut [u8])
+where
+    T: Isa + Copy + As<u64>,
+    u64: As<T>,
+    u128: As<T>,
+{
+    if trap::interrupt(hart) {
+        return;
+    }
+    match fetch(memory, hart.pc.r#as(), &Mmu::fetch(hart)) {
+        Ok(encoded) => T::execute(encoded, hart, memory),
+        Err(error) => trap::take(hart, error, 0),
+    }
+}
+
+/// Fetches the instruction at `pc`, the second parcel is translated and read only for 32-bit
+/// encodings.
+pub fn fetch(memory: &[u8], pc: u64, mmu: &Mmu) -> Result<u32, Error> {
+    let parcel = |vaddr: u64| {
+        let addr = mmu.translate(vaddr, 2, Access::Fetch, memory)?;
+        mem::memr16(memory, addr)
+            .map(u16::from_le_bytes)
+            .map_err(|_| Error::InstructionAccessFault(vaddr as usize))
+    };
+    let low = parcel(pc)? as u32;
+    if decode::is_compressed(low) {
+        Ok(low)
+    } else {
+        Ok(low | (parcel(pc.wrapping_add(2))? as u32) << 16)
+    }
+}
+
+/// Returns the 32-bit form of `encoded` along with its size in bytes.
+#[inline(always)]
+fn expand(encoded: u32, xlen: u32) -> Result<(u32, u8), Error> {
+    if decode::is_compressed(encoded) {
+        let expanded =
+            decode::expand_compressed(encoded as u16, xlen).ok_or(Error::InvalidOpCode)?;
+        Ok((expanded, 2))
+    } else {
+        Ok((encoded, 4))
+    }
+}
+
+/// An indirect jump that expected a landing pad must be followed by one.
+#[inline(always)]
+fn expect_landing_pad<T>(encoded: u32, size: u8, hart: &mut Hart<T>) -> Result<(), Error>
+where
+    T: Copy + As<u64>,
+{
+    if hart.cfi.elp {
+        hart.cfi.elp = false;
+        cfi::landing_pad(encoded, size, hart.pc, &hart.regs)?;
+    }
+    Ok(())
+}
+
+/// Ordinary stores of `width` bytes at `rs1 + imm`, once translated by `mmu`, cannot write the
+/// shadow-stack memory.
+#[inline(always)]
+fn guard_store<T>(
+    hart: &Hart<T>,
+    rs1: U5,
+    imm: U12,
+    width: usize,
+    mmu: &Mmu,
+    memory: &[u8],
+) -> Result<(), Error>
+where
+    T: Addi + Copy + Zero + As<u64>,
+{
+    if hart.cfi.regions.is_empty() {
+        return Ok(());
+    }
+    let vaddr = hart.regs.decode(rs1)?.fetch(&hart.regs).addi(imm).r#as();
+    let addr = mmu.translate(vaddr, width, Access::Store, memory)?;
+    cfi::check_store(&hart.cfi.regions, addr, width)
+}
+
+/// Updates the hart counters after the instruction at `pc` completed, a branch is taken when it
+/// did not fall through to the next instruction.
+#[inline(always)]
+fn retire<T>(hart: &mut Hart<T>, opcode: u8, pc: T, size: u8)
+where
+    T: Add + PartialEq + Copy,
+    u8: As<T>,
+{
+    let events: &[Event] = match opcode {
+        LOAD | LOAD_FP => &[Event::Load],
+        STORE | STORE_FP => &[Event::Store],
+        BRANCH if hart.pc != pc.add(size.r#as()) => &[Event::Branch, Event::TakenBranch],
+        BRANCH => &[Event::Branch],
+        JAL | JALR => &[Event::Jump],
+        AMO => &[Event::Atomic],
+        MADD | MSUB | NMSUB | NMADD | OP_FP => &[Event::Float],
+        _ => &[],
+    };
+    hart.counters.retire(events);
 }
 
 impl Isa for u32 {
-    fn execute(encoded: u32, regs: &mut Registers<Self>, pc: &mut Self, memory: &mut [u8]) {
-        println!("{:#034b} - PC: {:#0x}", encoded, pc);
+    const COMPRESSED: bool = true;
+
+    fn try_execute(encoded: u32, hart: &mut Hart<Self>, memory: &mut [u8]) -> Result<(), Error> {
+        println!("{:#034b} - PC: {:#0x}", encoded, hart.pc);
+        let (encoded, size) = expand(encoded, 32)?;
+        expect_landing_pad(encoded, size, hart)?;
         let opcode = (encoded & 0b1111111) as u8;
         let f = match opcode {
             x if x > 0b1111111 => unsafe { core::hint::unreachable_unchecked() },
@@ -41,18 +184,34 @@ impl Isa for u32 {
             STORE => store::<Self>,
             MATHI => mathi::<Self>,
             MATH => math::<Self>,
-            FENCE => todo!("FENCE"),
-            SYSCALL => todo!("SYSCALL"),
-            _ => panic!("Invalid OPCode"),
+            AMO => amo::<Self>,
+            LOAD_FP => float_load::<Self>,
+            STORE_FP => float_store::<Self>,
+            MADD => fmadd::<Self>,
+            MSUB => fmsub::<Self>,
+            NMSUB => fnmsub::<Self>,
+            NMADD => fnmadd::<Self>,
+            OP_FP => float_math::<Self>,
+            OP_V => vector_math::<Self>,
+            FENCE => cache_block::<Self>,
+            SYSCALL => system::<Self>,
+            _ => return Err(Error::InvalidOpCode),
         };
 
-        f(encoded, regs, pc, memory)
+        let pc = hart.pc;
+        f(encoded, size, hart, memory)?;
+        retire(hart, opcode, pc, size);
+        Ok(())
     }
 }
 
 impl Isa for u64 {
-    fn execute(encoded: u32, regs: &mut Registers<Self>, pc: &mut Self, memory: &mut [u8]) {
-        println!("{:#034b} - PC: {:#0x}", encoded, pc);
+    const COMPRESSED: bool = true;
+
+    fn try_execute(encoded: u32, hart: &mut Hart<Self>, memory: &mut [u8]) -> Result<(), Error> {
+        println!("{:#034b} - PC: {:#0x}", encoded, hart.pc);
+        let (encoded, size) = expand(encoded, 64)?;
+        expect_landing_pad(encoded, size, hart)?;
         let opcode = (encoded & 0b1111111) as u8;
         let f = match opcode {
             x if x > 0b1111111 => unsafe { core::hint::unreachable_unchecked() },
@@ -65,97 +224,177 @@ impl Isa for u64 {
             STORE => store::<Self>,
             MATHI => mathi::<Self>,
             MATH => math::<Self>,
-            FENCE => todo!("FENCE"),
-            SYSCALL => todo!("SYSCALL"),
+            AMO => amo::<Self>,
+            LOAD_FP => float_load::<Self>,
+            STORE_FP => float_store::<Self>,
+            MADD => fmadd::<Self>,
+            MSUB => fmsub::<Self>,
+            NMSUB => fnmsub::<Self>,
+            NMADD => fnmadd::<Self>,
+            OP_FP => float_math::<Self>,
+            OP_V => vector_math::<Self>,
+            FENCE => cache_block::<Self>,
+            SYSCALL => system::<Self>,
             MATHIW => mathiw::<Self>,
             MATHW => mathw::<Self>,
-            _ => panic!("Invalid OPCode"),
+            _ => return Err(Error::InvalidOpCode),
         };
 
-        f(encoded, regs, pc, memory)
+        let pc = hart.pc;
+        f(encoded, size, hart, memory)?;
+        retire(hart, opcode, pc, size);
+        Ok(())
+    }
+}
+
+/// RV128C is not implemented, compressed encodings never match a 32-bit major opcode.
+impl Isa for u128 {
+    const COMPRESSED: bool = false;
+
+    fn try_execute(encoded: u32, hart: &mut Hart<Self>, memory: &mut [u8]) -> Result<(), Error> {
+        println!("{:#034b} - PC: {:#0x}", encoded, hart.pc);
+        let size = 4;
+        expect_landing_pad(encoded, size, hart)?;
+        let opcode = (encoded & 0b1111111) as u8;
+        let f = match opcode {
+            x if x > 0b1111111 => unsafe { core::hint::unreachable_unchecked() },
+            LUI => lui::<Self>,
+            AUIPC => auipc::<Self>,
+            JAL => jal::<Self>,
+            JALR => jalr::<Self>,
+            BRANCH => branch::<Self>,
+            LOAD => load::<Self>,
+            STORE => store::<Self>,
+            MATHI => mathi::<Self>,
+            MATH => math::<Self>,
+            FENCE => misc_mem::<Self>,
+            SYSCALL => system::<Self>,
+            MATHIW => mathiw::<Self>,
+            MATHW => mathw::<Self>,
+            MATHID => mathid::<Self>,
+            MATHD => mathd::<Self>,
+            _ => return Err(Error::InvalidOpCode),
+        };
+
+        let pc = hart.pc;
+        f(encoded, size, hart, memory)?;
+        retire(hart, opcode, pc, size);
+        Ok(())
     }
 }
 
 #[inline(always)]
-fn lui<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn lui<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: Lui + Add + Copy,
     u8: As<T>,
 {
     let instruction = U::from_u32(encoded);
     println!("{:?}", instruction);
-    T::lui(instruction, regs).unwrap();
-    *pc = pc.add(4.r#as());
+    T::lui(instruction, &mut hart.regs)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn auipc<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn auipc<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: Auipc + Add + Copy,
     u8: As<T>,
 {
     let instruction = U::from_u32(encoded);
     println!("{:?}", instruction);
-    T::auipc(instruction, regs, *pc).unwrap();
-    *pc = pc.add(4.r#as());
+    T::auipc(instruction, &mut hart.regs, hart.pc)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn jal<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn jal<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
-    T: Jal + Add + Copy,
+    T: Isa + Jal + Add + Copy,
 {
     let instruction = J::from_u32(encoded);
     println!("{:?}", instruction);
-    T::jal(instruction, regs, pc).unwrap();
+    T::jal(
+        instruction,
+        &mut hart.regs,
+        &mut hart.pc,
+        size,
+        T::COMPRESSED,
+    )
 }
 
 #[inline(always)]
-fn jalr<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn jalr<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
-    T: Jalr + Add + Copy,
+    T: Isa + Jalr + Add + Copy + As<u64>,
 {
     let instruction = I::from_u32(encoded);
     println!("{:?}", instruction);
-    T::jalr(instruction, regs, pc).unwrap();
+    let elp = if cfi::landing_pads(hart) {
+        Some(&mut hart.cfi.elp)
+    } else {
+        None
+    };
+    T::jalr(
+        instruction,
+        &mut hart.regs,
+        &mut hart.pc,
+        size,
+        T::COMPRESSED,
+        elp,
+    )
 }
 
 #[inline(always)]
-fn branch<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn branch<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
-    T: Branch + Add + Copy,
+    T: Isa + Branch + Add + Copy,
 {
     let instruction = B::from_u32(encoded);
     println!("{:?}", instruction);
-    T::branch(instruction, regs, pc).unwrap();
+    T::branch(
+        instruction,
+        &mut hart.regs,
+        &mut hart.pc,
+        size,
+        T::COMPRESSED,
+    )
 }
 
 #[inline(always)]
-fn load<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, memory: &mut [u8])
+fn load<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
 where
-    T: Load + Add + Copy,
+    T: Load + Add + Copy + As<u64>,
     u8: As<T>,
 {
     let instruction = I::from_u32(encoded);
     println!("{:?}", instruction);
-    T::load(instruction, regs, memory).unwrap();
-    *pc = pc.add(4.r#as());
+    let mmu = Mmu::data(hart);
+    T::load(instruction, &mut hart.regs, &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn store<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, memory: &mut [u8])
+fn store<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
 where
-    T: Store + Add + Copy,
+    T: Store + Add + Addi + Copy + Zero + As<u64>,
     u8: As<T>,
 {
     let instruction = S::from_u32(encoded);
     println!("{:?}", instruction);
-    T::store(instruction, regs, memory).unwrap();
-    *pc = pc.add(4.r#as());
+    let width = 1 << instruction.funct3.as_u8();
+    let mmu = Mmu::data(hart);
+    guard_store(hart, instruction.rs1, instruction.imm, width, &mmu, memory)?;
+    T::store(instruction, &mut hart.regs, &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn mathi<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn mathi<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: ShiftI + MathI + Add + Copy,
     u8: As<T>,
@@ -163,28 +402,29 @@ where
     let instruction = I::from_u32(encoded);
     println!("{:?}", instruction);
     if matches!(instruction.funct3.as_u8(), 0b001 | 0b101) {
-        T::shifti(instruction.into(), regs)
+        T::shifti(instruction.into(), &mut hart.regs)
     } else {
-        T::mathi(instruction, regs)
-    }
-    .unwrap();
-    *pc = pc.add(4.r#as());
+        T::mathi(instruction, &mut hart.regs)
+    }?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn math<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn math<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: Math + Add + Copy,
     u8: As<T>,
 {
     let instruction = R::from_u32(encoded);
     println!("{:?}", instruction);
-    T::math(instruction, regs).unwrap();
-    *pc = pc.add(4.r#as());
+    T::math(instruction, &mut hart.regs)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn mathiw<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn mathiw<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: ShiftIW + MathIW + Add + Copy,
     u8: As<T>,
@@ -192,22 +432,380 @@ where
     let instruction = I::from_u32(encoded);
     println!("{:?}", instruction);
     if matches!(instruction.funct3.as_u8(), 0b000 /* ADDIW */) {
-        T::mathiw(instruction, regs)
+        T::mathiw(instruction, &mut hart.regs)
     } else {
-        T::shiftiw(instruction.into(), regs)
-    }
-    .unwrap();
-    *pc = pc.add(4.r#as());
+        T::shiftiw(instruction.into(), &mut hart.regs)
+    }?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
 
 #[inline(always)]
-fn mathw<T>(encoded: u32, regs: &mut Registers<T>, pc: &mut T, _: &mut [u8])
+fn mathw<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
 where
     T: MathW + Add + Copy,
     u8: As<T>,
 {
     let instruction = R::from_u32(encoded);
     println!("{:?}", instruction);
-    T::mathw(instruction, regs).unwrap();
-    *pc = pc.add(4.r#as());
+    T::mathw(instruction, &mut hart.regs)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn mathid<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: ShiftID + MathID + Add + Copy,
+    u8: As<T>,
+{
+    let instruction = I::from_u32(encoded);
+    println!("{:?}", instruction);
+    if matches!(instruction.funct3.as_u8(), 0b000 /* ADDID */) {
+        T::mathid(instruction, &mut hart.regs)
+    } else {
+        T::shiftid(instruction.into(), &mut hart.regs)
+    }?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn mathd<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: MathD + Add + Copy,
+    u8: As<T>,
+{
+    let instruction = R::from_u32(encoded);
+    println!("{:?}", instruction);
+    T::mathd(instruction, &mut hart.regs)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn misc_mem<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+where
+    T: LoadQ + Fence + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    if (encoded >> 12) & 0b111 == 0b010 {
+        // LQ
+        let instruction = I::from_u32(encoded);
+        println!("{:?}", instruction);
+        let mmu = Mmu::data(hart);
+        T::loadq(instruction, &mut hart.regs, &mmu, memory)?;
+        hart.pc = hart.pc.add(size.r#as());
+        Ok(())
+    } else {
+        fence(encoded, size, hart, memory)
+    }
+}
+
+#[inline(always)]
+fn cache_block<T>(
+    encoded: u32,
+    size: u8,
+    hart: &mut Hart<T>,
+    memory: &mut [u8],
+) -> Result<(), Error>
+where
+    T: CacheBlock + Fence + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    if (encoded >> 12) & 0b111 == 0b010 {
+        let instruction = I::from_u32(encoded);
+        println!("{:?}", instruction);
+        if !cache_block_enabled(hart, instruction.imm) {
+            return Err(Error::InvalidOpCode);
+        }
+        let mmu = Mmu::data(hart);
+        T::cache_block(instruction, &hart.regs, hart.cache_block, &mmu, memory)?;
+        hart.pc = hart.pc.add(size.r#as());
+        Ok(())
+    } else {
+        fence(encoded, size, hart, memory)
+    }
+}
+
+/// Whether the cache-block operation `op` is enabled in the current privilege mode, S-mode needs
+/// its `menvcfg` field set and U-mode its `senvcfg` field as well.
+fn cache_block_enabled<T: Copy + As<u64>>(hart: &Hart<T>, op: U12) -> bool {
+    let field = match op.as_u16() {
+        0b000 => instructions::CBIE,
+        0b001 | 0b010 => instructions::CBCFE,
+        0b100 => instructions::CBZE,
+        // not an operation, rejected with the other encoding errors
+        _ => return true,
+    };
+    let bits = |addr| -> u64 { hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as()) };
+    match hart.privilege {
+        Privilege::Machine => true,
+        Privilege::Supervisor => bits(csr::MENVCFG) & field != 0,
+        Privilege::User => bits(csr::MENVCFG) & bits(csr::SENVCFG) & field != 0,
+    }
+}
+
+#[inline(always)]
+fn fence<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: Fence + Add + Copy,
+    u8: As<T>,
+{
+    let instruction = FenceInstruction::from_u32(encoded);
+    println!("{:?}", instruction);
+    T::fence(instruction)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn system<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+where
+    T: Csr + Privileged + Mop + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    if (encoded >> 12) & 0b111 == 0b100 {
+        return mop(encoded, size, hart, memory);
+    }
+    let instruction = I::from_u32(encoded);
+    println!("{:?}", instruction);
+    if instruction.funct3.as_u8() == 0 {
+        return T::privileged(instruction, hart, size);
+    }
+    T::csr(instruction, hart)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn mop<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+where
+    T: Mop + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    let instruction = R::from_u32(encoded);
+    println!("{:?}", instruction);
+    let mmu = Mmu::data(hart);
+    let shadow_stack = cfi::shadow_stacks(hart).then(|| hart.cfi.shadow_stack(&mmu));
+    T::mop(instruction, &mut hart.regs, shadow_stack, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn amo<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+where
+    T: Atomic + ShadowStackSwap + Add + Addi + Copy + Zero + As<u64>,
+    u8: As<T>,
+{
+    const LR: u8 = 0b00010;
+    const SSAMOSWAP: u8 = 0b01001;
+
+    let instruction = Amo::from_u32(encoded);
+    println!("{:?}", instruction);
+    let mmu = Mmu::data(hart);
+    match instruction.funct5.as_u8() {
+        SSAMOSWAP => {
+            let shadow_stack = cfi::shadow_stack_swaps(hart).then(|| hart.cfi.shadow_stack(&mmu));
+            T::ssamoswap(instruction, &mut hart.regs, shadow_stack, memory)?;
+        }
+        funct5 => {
+            if funct5 != LR {
+                let width = 1 << instruction.funct3.as_u8();
+                let (rs1, imm) = (instruction.rs1, U12::new_truncate(0));
+                guard_store(hart, rs1, imm, width, &mmu, memory)?;
+            }
+            let reservation = &mut hart.reservation;
+            T::atomic(instruction, &mut hart.regs, reservation, &mmu, memory)?;
+        }
+    }
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+/// The vector loads and stores share the LOAD-FP and STORE-FP opcodes, with widths unused by the
+/// scalar floating-point accesses.
+#[inline(always)]
+const fn is_vector_width(encoded: u32) -> bool {
+    matches!((encoded >> 12) & 0b111, 0b000 | 0b101 | 0b110 | 0b111)
+}
+
+#[inline(always)]
+fn float_load<T>(encoded: u32, size: u8, hart: &mut Hart<T>, memory: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatLoad + VectorLoad + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    if is_vector_width(encoded) {
+        return vector_load(encoded, size, hart, memory);
+    }
+    let instruction = I::from_u32(encoded);
+    println!("{:?}", instruction);
+    let mmu = Mmu::data(hart);
+    T::float_load(instruction, &hart.regs, hart.fregs.as_mut(), &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn float_store<T>(
+    encoded: u32,
+    size: u8,
+    hart: &mut Hart<T>,
+    memory: &mut [u8],
+) -> Result<(), Error>
+where
+    T: FloatStore + VectorStore + Add + Addi + Copy + Zero + As<u64>,
+    u8: As<T>,
+{
+    if is_vector_width(encoded) {
+        return vector_store(encoded, size, hart, memory);
+    }
+    let instruction = S::from_u32(encoded);
+    println!("{:?}", instruction);
+    let width = 1 << instruction.funct3.as_u8();
+    let mmu = Mmu::data(hart);
+    guard_store(hart, instruction.rs1, instruction.imm, width, &mmu, memory)?;
+    T::float_store(instruction, &hart.regs, hart.fregs.as_ref(), &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn float_mul_add<T>(
+    encoded: u32,
+    size: u8,
+    hart: &mut Hart<T>,
+    negate_product: bool,
+    negate_addend: bool,
+) -> Result<(), Error>
+where
+    T: FloatMulAdd + Add + Copy,
+    u8: As<T>,
+{
+    let instruction = R4::from_u32(encoded);
+    println!("{:?}", instruction);
+    T::float_mul_add(
+        instruction,
+        negate_product,
+        negate_addend,
+        &mut hart.regs,
+        hart.fregs.as_mut(),
+        &mut hart.fcsr,
+    )?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn fmadd<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatMulAdd + Add + Copy,
+    u8: As<T>,
+{
+    float_mul_add(encoded, size, hart, false, false)
+}
+
+#[inline(always)]
+fn fmsub<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatMulAdd + Add + Copy,
+    u8: As<T>,
+{
+    float_mul_add(encoded, size, hart, false, true)
+}
+
+#[inline(always)]
+fn fnmsub<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatMulAdd + Add + Copy,
+    u8: As<T>,
+{
+    float_mul_add(encoded, size, hart, true, false)
+}
+
+#[inline(always)]
+fn fnmadd<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatMulAdd + Add + Copy,
+    u8: As<T>,
+{
+    float_mul_add(encoded, size, hart, true, true)
+}
+
+#[inline(always)]
+fn float_math<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: FloatMath + Add + Copy,
+    u8: As<T>,
+{
+    let instruction = R::from_u32(encoded);
+    println!("{:?}", instruction);
+    T::float_math(
+        instruction,
+        &mut hart.regs,
+        hart.fregs.as_mut(),
+        &mut hart.fcsr,
+    )?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn vector_load<T>(
+    encoded: u32,
+    size: u8,
+    hart: &mut Hart<T>,
+    memory: &mut [u8],
+) -> Result<(), Error>
+where
+    T: VectorLoad + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    let instruction = VMem::from_u32(encoded);
+    println!("{:?}", instruction);
+    let mmu = Mmu::data(hart);
+    T::vector_load(instruction, &hart.regs, &mut hart.vector, &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn vector_store<T>(
+    encoded: u32,
+    size: u8,
+    hart: &mut Hart<T>,
+    memory: &mut [u8],
+) -> Result<(), Error>
+where
+    T: VectorStore + Add + Copy + As<u64>,
+    u8: As<T>,
+{
+    let instruction = VMem::from_u32(encoded);
+    println!("{:?}", instruction);
+    let mmu = Mmu::data(hart);
+    T::vector_store(instruction, &hart.regs, &mut hart.vector, &mmu, memory)?;
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
+}
+
+#[inline(always)]
+fn vector_math<T>(encoded: u32, size: u8, hart: &mut Hart<T>, _: &mut [u8]) -> Result<(), Error>
+where
+    T: VectorConfig + VectorMath + Add + Copy,
+    u8: As<T>,
+{
+    if (encoded >> 12) & 0b111 == 0b111 {
+        // OPCFG
+        let instruction = I::from_u32(encoded);
+        println!("{:?}", instruction);
+        T::vector_config(instruction, &mut hart.regs, &mut hart.vector)?;
+    } else {
+        let instruction = V::from_u32(encoded);
+        println!("{:?}", instruction);
+        T::vector_math(instruction, &mut hart.regs, &mut hart.vector)?;
+    }
+    hart.pc = hart.pc.add(size.r#as());
+    Ok(())
 }
@@ -1,33 +1,120 @@
+pub(crate) mod cfi;
+pub(crate) mod clint;
+pub(crate) mod counters;
+pub(crate) mod csr;
 pub(crate) mod decode;
 pub(crate) mod elf;
 pub(crate) mod error;
+pub(crate) mod float;
+pub(crate) mod hart;
 pub(crate) mod instructions;
 pub(crate) mod isa;
 pub(crate) mod mem;
+pub(crate) mod mmu;
 pub(crate) mod num;
 pub(crate) mod ops;
+pub(crate) mod pmp;
+pub(crate) mod pointer_mask;
 pub(crate) mod registers;
+pub(crate) mod trap;
+pub(crate) mod vector;
+
+/// Frequency of `time` when it follows the host clock.
+const HOST_TIMEBASE: u64 = 10_000_000;
+
+/// Widest vector element, the register width is taken from `RISCVEMU_VLEN`.
+const VECTOR_ELEN: u32 = 64;
+
+/// ELF header flag of binaries built for the E base.
+const EF_RISCV_RVE: u32 = 0x0008;
+
+/// Reads the environment variable `name` through `parse`, a value it rejects ends the emulator
+/// with an error stating the `expected` values.
+fn env_var<V>(name: &str, expected: &str, parse: impl FnOnce(&str) -> Option<V>) -> Option<V> {
+    let value = std::env::var(name).ok()?;
+    Some(parse(&value).unwrap_or_else(|| {
+        eprintln!("{name} must be {expected}, not {value:?}");
+        std::process::exit(1)
+    }))
+}
 
 fn main() {
     let mut memory = [0u8; 262140];
-    let mut regs = registers::Registers::default();
     let file = std::fs::read(
         "/home/andreatedeschi/Public/tests/riscv/litmus-tests-riscv/elf-tests/basic/build/loop2-O0",
     )
     .unwrap();
     let elfdata = elf::load_elf_le(&file).unwrap();
-    let mut program_counter: u32 = elfdata.ehdr.e_entry as u32;
+    let mut hart = if elfdata.ehdr.e_flags & EF_RISCV_RVE != 0 {
+        hart::Hart::<u32>::embedded(elfdata.ehdr.e_entry as u32)
+    } else {
+        hart::Hart::<u32>::new(elfdata.ehdr.e_entry as u32)
+    };
+    if std::env::var_os("RISCVEMU_HOST_TIME").is_some() {
+        hart.counters.time_source = counters::TimeSource::host(HOST_TIMEBASE);
+    }
+    if std::env::var_os("RISCVEMU_ZFINX").is_some() {
+        hart = hart.with_zfinx();
+    }
+    // a cache block is never larger than the guest RAM
+    let largest_block = 1 << memory.len().ilog2();
+    if let Some(size) = env_var(
+        "RISCVEMU_CACHE_BLOCK",
+        &format!("a power of two up to {largest_block}"),
+        |size| {
+            size.parse()
+                .ok()
+                .filter(|size: &usize| size.is_power_of_two() && *size <= largest_block)
+        },
+    ) {
+        hart = hart.with_cache_block(size);
+    }
+    if let Some(entries) = env_var("RISCVEMU_PMP", "0, 16 or 64", |entries| {
+        entries
+            .parse()
+            .ok()
+            .filter(|entries: &usize| matches!(entries, 0 | 16 | 64))
+    }) {
+        hart = hart.with_pmp(entries);
+    }
+    if let Some(vlen) = env_var(
+        "RISCVEMU_VLEN",
+        &format!("a power of two from {VECTOR_ELEN} to {}", vector::MAX_VLEN),
+        |vlen| {
+            vlen.parse().ok().filter(|vlen: &usize| {
+                vlen.is_power_of_two() && (VECTOR_ELEN as usize..=vector::MAX_VLEN).contains(vlen)
+            })
+        },
+    ) {
+        hart = hart.with_vector(vlen, VECTOR_ELEN);
+    }
     for sg in elfdata.segments().unwrap().iter() {
         let sg_data = elfdata.segment_data(&sg).unwrap();
         println!("{}, {}", sg.p_paddr, sg.p_memsz);
         mem::memw(sg_data, &mut memory, sg.p_paddr as usize).unwrap();
     }
+    // RISCVEMU_CLINT is the hex base of the CLINT, which is shadowed inside the guest RAM: its
+    // registers are plain memory synchronized with the hart between instructions.
+    let last = memory.len() - clint::SIZE;
+    let mut clint = std::env::var("RISCVEMU_CLINT").ok().map(|base| {
+        usize::from_str_radix(base.trim_start_matches("0x"), 16)
+            .ok()
+            .filter(|&base| base <= last)
+            .and_then(|base| clint::Clint::new(base, &mut memory).ok())
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "RISCVEMU_CLINT must be a hex base in the guest RAM, from 0x0 to {last:#x}"
+                );
+                std::process::exit(1)
+            })
+    });
     //...
     loop {
-        // fetch instruction (libmem::memr(4)), increase pc of 4
-        let ins = u32::from_le_bytes(mem::memr32(&memory, program_counter as usize).unwrap());
-        // decode and execute instruction
-        <u32 as isa::Isa>::execute(ins, &mut regs, &mut program_counter, &mut memory);
-        // increment the program counter
+        if let Some(clint) = clint.as_mut() {
+            clint.update(&mut hart, &mut memory).unwrap();
+        }
+        // take a pending interrupt, or fetch, decode and execute the instruction at pc, faults
+        // trap to mtvec
+        isa::step(&mut hart, &mut memory);
     }
 }
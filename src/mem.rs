@@ -50,6 +50,7 @@ impl_pod! {
     U32(u32) -> as_u32;
     I64(i64) -> as_i64;
     U64(u64) -> as_u64;
+    U128(u128) -> as_u128;
 }
 unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
 
@@ -58,7 +59,7 @@ pub fn read<T: Pod>(src: &[u8], addr: usize) -> Result<T, Error> {
         core::ptr::read_unaligned(
             src.get(addr..)
                 .and_then(|src| src.get(..core::mem::size_of::<T>()))
-                .ok_or(Error::InvalidOpCode)?
+                .ok_or(Error::LoadAccessFault(addr))?
                 .as_ptr()
                 .cast(),
         )
@@ -70,7 +71,7 @@ pub fn write<T: Pod>(src: &T, dest: &mut [u8], addr: usize) -> Result<(), Error>
         core::ptr::write_unaligned(
             dest.get_mut(addr..)
                 .and_then(|dst| dst.get_mut(..core::mem::size_of::<T>()))
-                .ok_or(Error::InvalidOpCode)?
+                .ok_or(Error::StoreAccessFault(addr))?
                 .as_mut_ptr()
                 .cast(),
             *src,
@@ -100,12 +101,12 @@ pub fn memr<const N: usize>(dest: &mut [u8], src: &[u8], addr: usize) -> Result<
     Ok(())
 }
 
+#[allow(dead_code)]
 #[inline(always)]
 pub fn memr32(src: &[u8], addr: usize) -> Result<[u8; 4], Error> {
     read::<[u8; 4]>(src, addr)
 }
 
-#[allow(dead_code)]
 #[inline(always)]
 pub fn memr16(src: &[u8], addr: usize) -> Result<[u8; 2], Error> {
     read::<[u8; 2]>(src, addr)
@@ -114,7 +115,7 @@ pub fn memr16(src: &[u8], addr: usize) -> Result<[u8; 2], Error> {
 #[allow(dead_code)]
 #[inline(always)]
 pub fn memr8(src: &[u8], addr: usize) -> Result<u8, Error> {
-    read::<[u8; 1]>(src, addr).map(|n| unsafe { core::mem::transmute(n) })
+    read::<[u8; 1]>(src, addr).map(|[n]| n)
 }
 
 #[cfg(test)]
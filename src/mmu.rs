@@ -0,0 +1,599 @@
+use crate::{
+    cfi, csr,
+    decode::U12,
+    error::Error,
+    hart::{Hart, Privilege},
+    mem,
+    num::As,
+    pmp::Pmp,
+    pointer_mask::{self, PointerMask},
+    trap::{MPP, MPRV, MXR, SUM},
+};
+
+const PAGE_SHIFT: u32 = 12;
+const PAGE_SIZE: usize = 1 << PAGE_SHIFT;
+
+const V: u64 = 1 << 0;
+const R: u64 = 1 << 1;
+const W: u64 = 1 << 2;
+const X: u64 = 1 << 3;
+const U: u64 = 1 << 4;
+const A: u64 = 1 << 6;
+const D: u64 = 1 << 7;
+
+/// Kind of a translated access, it selects the permission checked and the exception raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Fetch,
+    Load,
+    /// Stores, AMOs and cache-block operations.
+    Store,
+    /// Zicfiss accesses, only allowed to shadow-stack pages.
+    ShadowStack,
+}
+
+impl Access {
+    #[inline]
+    fn access_fault(self, addr: usize) -> Error {
+        match self {
+            Access::Fetch => Error::InstructionAccessFault(addr),
+            Access::Load => Error::LoadAccessFault(addr),
+            Access::Store | Access::ShadowStack => Error::StoreAccessFault(addr),
+        }
+    }
+
+    #[inline]
+    fn page_fault(self, addr: usize) -> Error {
+        match self {
+            Access::Fetch => Error::InstructionPageFault(addr),
+            Access::Load => Error::LoadPageFault(addr),
+            Access::Store | Access::ShadowStack => Error::StorePageFault(addr),
+        }
+    }
+
+    #[inline]
+    fn misaligned(self, addr: usize) -> Error {
+        match self {
+            Access::Fetch | Access::Load => Error::LoadAddressMisaligned(addr),
+            Access::Store | Access::ShadowStack => Error::StoreAddressMisaligned(addr),
+        }
+    }
+}
+
+/// Translation scheme selected by the MODE field of `satp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Bare,
+    Sv32,
+    Sv39,
+    Sv48,
+    Sv57,
+}
+
+impl Mode {
+    /// Decodes the MODE field of a `satp` value of an `xlen`-bit hart, unsupported modes give
+    /// `None`.
+    pub fn from_satp(satp: u64, xlen: u32) -> Option<Self> {
+        match xlen {
+            32 if satp & (1 << 31) == 0 => Some(Mode::Bare),
+            32 => Some(Mode::Sv32),
+            64 => match satp >> 60 {
+                0 => Some(Mode::Bare),
+                8 => Some(Mode::Sv39),
+                9 => Some(Mode::Sv48),
+                10 => Some(Mode::Sv57),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Number of levels, size in bytes of a PTE and width of the VPN fields.
+    #[inline]
+    const fn geometry(self) -> (u32, usize, u32) {
+        match self {
+            Mode::Bare => (0, 0, 0),
+            Mode::Sv32 => (2, 4, 10),
+            Mode::Sv39 => (3, 8, 9),
+            Mode::Sv48 => (4, 8, 9),
+            Mode::Sv57 => (5, 8, 9),
+        }
+    }
+
+    /// Width of the virtual addresses, the 64-bit ones must be sign-extended from it.
+    #[inline]
+    const fn va_bits(self) -> u32 {
+        let (levels, _, vpn_bits) = self.geometry();
+        PAGE_SHIFT + levels * vpn_bits
+    }
+
+    /// Width of the PPN field of `satp` and of the PTEs.
+    #[inline]
+    const fn ppn_bits(self) -> u32 {
+        match self {
+            Mode::Bare => 0,
+            Mode::Sv32 => 22,
+            Mode::Sv39 | Mode::Sv48 | Mode::Sv57 => 44,
+        }
+    }
+}
+
+/// Address translation and protection of the accesses of a hart, captured from its privilege
+/// mode, `satp`, `mstatus` and PMP entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mmu {
+    mode: Mode,
+    /// Physical address of the root page table.
+    root: usize,
+    /// Privilege mode the accesses are checked against.
+    privilege: Privilege,
+    sum: bool,
+    mxr: bool,
+    /// Whether the reserved `xwr = 010` encoding marks shadow-stack pages.
+    shadow_stack_pages: bool,
+    mask: PointerMask,
+    pmp: Pmp,
+}
+
+impl Mmu {
+    /// Physical addressing, as seen by M-mode.
+    pub const BARE: Self = Self {
+        mode: Mode::Bare,
+        root: 0,
+        privilege: Privilege::Machine,
+        sum: false,
+        mxr: false,
+        shadow_stack_pages: false,
+        mask: PointerMask::NONE,
+        pmp: Pmp::NONE,
+    };
+
+    fn new<T>(hart: &Hart<T>, privilege: Privilege) -> Self
+    where
+        T: Copy + As<u64>,
+    {
+        let mask = pointer_mask::pointer_mask(hart, privilege);
+        if privilege == Privilege::Machine {
+            return Self {
+                mask,
+                pmp: hart.pmp,
+                ..Self::BARE
+            };
+        }
+        let xlen = (core::mem::size_of::<T>() * 8) as u32;
+        let satp: u64 = hart.satp.r#as();
+        let mode = Mode::from_satp(satp, xlen).unwrap_or(Mode::Bare);
+        let mstatus = csr_bits(hart, csr::MSTATUS);
+        Self {
+            mode,
+            root: ((satp & ((1 << mode.ppn_bits()) - 1)) as usize) << PAGE_SHIFT,
+            privilege,
+            sum: mstatus & SUM != 0,
+            mxr: mstatus & MXR != 0,
+            shadow_stack_pages: cfi::shadow_stack_pages(hart),
+            mask,
+            pmp: hart.pmp,
+        }
+    }
+
+    /// Translation of the loads and stores, performed with the privilege in MPP when MPRV is set.
+    pub fn data<T>(hart: &Hart<T>) -> Self
+    where
+        T: Copy + As<u64>,
+    {
+        let mstatus = csr_bits(hart, csr::MSTATUS);
+        let privilege = if hart.privilege == Privilege::Machine && mstatus & MPRV != 0 {
+            match (mstatus & MPP) >> 11 {
+                0b11 => Privilege::Machine,
+                0b01 => Privilege::Supervisor,
+                _ => Privilege::User,
+            }
+        } else {
+            hart.privilege
+        };
+        Self::new(hart, privilege)
+    }
+
+    /// Translation of the instruction fetches, they are never masked.
+    pub fn fetch<T>(hart: &Hart<T>) -> Self
+    where
+        T: Copy + As<u64>,
+    {
+        Self {
+            mask: PointerMask::NONE,
+            ..Self::new(hart, hart.privilege)
+        }
+    }
+
+    /// Translates the `size` bytes wide access at `vaddr`, accesses outside of `memory` or denied
+    /// by PMP raise access faults. Accesses crossing a page boundary raise a misaligned exception
+    /// when the two pages are not physically contiguous.
+    pub fn translate(
+        &self,
+        vaddr: u64,
+        size: usize,
+        access: Access,
+        memory: &[u8],
+    ) -> Result<usize, Error> {
+        let (vaddr, paddr) = if self.mode == Mode::Bare {
+            let addr = self.mask.apply(vaddr);
+            (addr, addr as usize)
+        } else {
+            let vaddr = self.mask.apply_signed(vaddr);
+            let paddr = self.walk(vaddr, access, memory)?;
+            let offset = vaddr as usize & (PAGE_SIZE - 1);
+            if offset + size > PAGE_SIZE {
+                let next = vaddr.wrapping_add((PAGE_SIZE - offset) as u64);
+                if self.walk(next, access, memory)? != paddr + (PAGE_SIZE - offset) {
+                    return Err(access.misaligned(vaddr as usize));
+                }
+            }
+            (vaddr, paddr)
+        };
+        if paddr.saturating_add(size) > memory.len() || !self.allows(paddr, size, access) {
+            return Err(access.access_fault(vaddr as usize));
+        }
+        Ok(paddr)
+    }
+
+    /// Whether PMP allows the `size` bytes wide access at the physical address `paddr`.
+    #[inline]
+    pub fn allows(&self, paddr: usize, size: usize, access: Access) -> bool {
+        self.pmp.allows(paddr, size, access, self.privilege)
+    }
+
+    fn walk(&self, vaddr: u64, access: Access, memory: &[u8]) -> Result<usize, Error> {
+        let (levels, pte_size, vpn_bits) = self.mode.geometry();
+        let fault = access.page_fault(vaddr as usize);
+        let vpn_mask = (1 << vpn_bits) - 1;
+        let ppn_mask = (1 << self.mode.ppn_bits()) - 1;
+        // the bits above the virtual address of the 64-bit modes must all equal its top bit
+        let unused = 64 - self.mode.va_bits();
+        if pte_size == 8 && (((vaddr << unused) as i64) >> unused) as u64 != vaddr {
+            return Err(fault);
+        }
+
+        let mut table = self.root;
+        for level in (0..levels).rev() {
+            let vpn = (vaddr >> (PAGE_SHIFT + vpn_bits * level)) & vpn_mask;
+            let addr = table + vpn as usize * pte_size;
+            // the page-table accesses are checked by PMP as S-mode loads
+            if !self
+                .pmp
+                .allows(addr, pte_size, Access::Load, Privilege::Supervisor)
+            {
+                return Err(access.access_fault(vaddr as usize));
+            }
+            let pte = match pte_size {
+                4 => mem::read::<mem::U32>(memory, addr).map(|pte| pte.as_u32() as u64),
+                _ => mem::read::<mem::U64>(memory, addr).map(|pte| pte.as_u64()),
+            }
+            .map_err(|_| access.access_fault(vaddr as usize))?;
+            let ppn = (pte >> 10) & ppn_mask;
+
+            // the reserved bits are set, as are those of Svnapot and Svpbmt which are not supported
+            if pte & V == 0 || pte >> 54 != 0 {
+                return Err(fault);
+            }
+            if pte & (R | W | X) == 0 {
+                table = (ppn as usize) << PAGE_SHIFT;
+                continue;
+            }
+
+            self.check(pte, access, vaddr as usize)?;
+            // superpages must be aligned to their size
+            let superpage = (1 << (vpn_bits * level)) - 1;
+            if ppn & superpage != 0 {
+                return Err(fault);
+            }
+            let ppn = ppn | ((vaddr >> PAGE_SHIFT) & superpage);
+            return Ok(((ppn as usize) << PAGE_SHIFT) | (vaddr as usize & (PAGE_SIZE - 1)));
+        }
+        Err(fault)
+    }
+
+    /// Checks the permissions of a leaf PTE, the accessed and dirty bits are never updated by the
+    /// hart: accesses to pages without A, or stores to pages without D, fault.
+    fn check(&self, pte: u64, access: Access, vaddr: usize) -> Result<(), Error> {
+        let fault = Err(access.page_fault(vaddr));
+        let shadow_stack = pte & (R | W | X) == W;
+        if (shadow_stack && !self.shadow_stack_pages) || pte & (R | W | X) == W | X {
+            return fault;
+        }
+
+        let user = pte & U != 0;
+        match self.privilege {
+            Privilege::User if !user => return fault,
+            Privilege::Supervisor if user && (!self.sum || access == Access::Fetch) => {
+                return fault
+            }
+            _ => {}
+        }
+
+        let allowed = match access {
+            Access::Fetch => pte & X != 0,
+            Access::Load => pte & R != 0 || (self.mxr && pte & X != 0) || shadow_stack,
+            // ordinary stores cannot write shadow-stack pages, nor shadow-stack accesses others
+            Access::Store if shadow_stack => return Err(Error::StoreAccessFault(vaddr)),
+            Access::Store => pte & W != 0,
+            Access::ShadowStack if !shadow_stack => return Err(Error::StoreAccessFault(vaddr)),
+            Access::ShadowStack => true,
+        };
+        let dirty = !matches!(access, Access::Store | Access::ShadowStack) || pte & D != 0;
+        if !allowed || pte & A == 0 || !dirty {
+            return fault;
+        }
+        Ok(())
+    }
+}
+
+#[inline(always)]
+fn csr_bits<T: Copy + As<u64>>(hart: &Hart<T>, addr: U12) -> u64 {
+    hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::Register;
+
+    const ROOT: usize = 0x1000;
+    const TABLE: usize = 0x2000;
+
+    fn pte(memory: &mut [u8], addr: usize, ppn: u32, flags: u64) {
+        memory[addr..addr + 4].copy_from_slice(&((ppn << 10) | flags as u32).to_le_bytes());
+    }
+
+    /// An Sv32 hart whose first megapage points at a table mapping the virtual pages 0 to 5 to
+    /// the physical page 3 with various permissions, the second is an identity megapage and the
+    /// third a misaligned one.
+    fn sv32() -> (Hart<u32>, Vec<u8>) {
+        let mut memory = vec![0u8; 0x4000];
+        pte(&mut memory, ROOT, (TABLE >> 12) as u32, V);
+        pte(&mut memory, ROOT + 4, 0, V | R | W | X | A | D);
+        pte(&mut memory, ROOT + 8, 1, V | R | W | X | A | D);
+        pte(&mut memory, TABLE, 3, V | R | W | U | A | D);
+        pte(&mut memory, TABLE + 4, 3, V | R | A);
+        pte(&mut memory, TABLE + 8, 3, V | X | U | A);
+        pte(&mut memory, TABLE + 12, 3, V | R | W | U);
+        pte(&mut memory, TABLE + 20, 3, V | W | A | D);
+
+        let mut hart = Hart::<u32>::new(0);
+        csr::write(&mut hart, csr::SATP, (1 << 31) | (ROOT >> 12) as u32).unwrap();
+        (hart, memory)
+    }
+
+    #[test]
+    fn permissions() {
+        use Access::*;
+
+        let (mut hart, memory) = sv32();
+        let translate = |hart: &Hart<u32>, vaddr: u64, access| {
+            Mmu::data(hart).translate(vaddr, 4, access, &memory)
+        };
+
+        // M-mode is never translated
+        assert_eq!(translate(&hart, 0x10, Load), Ok(0x10));
+
+        hart.privilege = Privilege::User;
+        assert_eq!(translate(&hart, 0x10, Load), Ok(0x3010));
+        assert_eq!(translate(&hart, 0x10, Store), Ok(0x3010));
+        assert_eq!(
+            translate(&hart, 0x1000, Load),
+            Err(Error::LoadPageFault(0x1000))
+        );
+        assert_eq!(translate(&hart, 0x2000, Fetch), Ok(0x3000));
+        assert_eq!(
+            translate(&hart, 0x2000, Load),
+            Err(Error::LoadPageFault(0x2000))
+        );
+        // accessed clear, invalid and reserved W-only PTEs
+        assert_eq!(
+            translate(&hart, 0x3000, Load),
+            Err(Error::LoadPageFault(0x3000))
+        );
+        assert_eq!(
+            translate(&hart, 0x4000, Load),
+            Err(Error::LoadPageFault(0x4000))
+        );
+        assert_eq!(
+            translate(&hart, 0x5000, Store),
+            Err(Error::StorePageFault(0x5000))
+        );
+
+        // MXR makes the executable pages readable
+        csr::write(&mut hart, csr::MSTATUS, MXR as u32).unwrap();
+        assert_eq!(translate(&hart, 0x2000, Load), Ok(0x3000));
+
+        // S-mode reaches the user pages only with SUM, and never executes them
+        hart.privilege = Privilege::Supervisor;
+        assert_eq!(
+            translate(&hart, 0x10, Load),
+            Err(Error::LoadPageFault(0x10))
+        );
+        assert_eq!(translate(&hart, 0x1000, Load), Ok(0x3000));
+        assert_eq!(
+            translate(&hart, 0x1000, Store),
+            Err(Error::StorePageFault(0x1000))
+        );
+        csr::write(&mut hart, csr::MSTATUS, SUM as u32).unwrap();
+        assert_eq!(translate(&hart, 0x10, Load), Ok(0x3010));
+        assert_eq!(
+            translate(&hart, 0x2000, Fetch),
+            Err(Error::InstructionPageFault(0x2000))
+        );
+
+        // MPRV translates the M-mode loads and stores with the privilege in MPP
+        hart.privilege = Privilege::Machine;
+        csr::write(&mut hart, csr::MSTATUS, MPRV as u32).unwrap();
+        assert_eq!(translate(&hart, 0x10, Load), Ok(0x3010));
+        assert_eq!(
+            Mmu::fetch(&hart).translate(0x10, 4, Fetch, &memory),
+            Ok(0x10)
+        );
+    }
+
+    #[test]
+    fn megapages() {
+        let (mut hart, memory) = sv32();
+        hart.privilege = Privilege::Supervisor;
+        let mmu = Mmu::data(&hart);
+
+        assert_eq!(
+            mmu.translate(0x40_0123, 4, Access::Fetch, &memory),
+            Ok(0x123)
+        );
+        // misaligned megapage
+        assert_eq!(
+            mmu.translate(0x80_0000, 4, Access::Load, &memory),
+            Err(Error::LoadPageFault(0x80_0000))
+        );
+        // mapped outside of memory
+        assert_eq!(
+            mmu.translate(0x40_5000, 4, Access::Load, &memory),
+            Err(Error::LoadAccessFault(0x40_5000))
+        );
+        // contiguous pages can be crossed, the pages 0 and 1 both map page 3
+        assert_eq!(
+            mmu.translate(0x40_0ffe, 4, Access::Load, &memory),
+            Ok(0xffe)
+        );
+        csr::write(&mut hart, csr::MSTATUS, SUM as u32).unwrap();
+        assert_eq!(
+            Mmu::data(&hart).translate(0xffe, 4, Access::Load, &memory),
+            Err(Error::LoadAddressMisaligned(0xffe))
+        );
+    }
+
+    fn pte64(memory: &mut [u8], addr: usize, ppn: u64, flags: u64) {
+        memory[addr..addr + 8].copy_from_slice(&((ppn << 10) | flags).to_le_bytes());
+    }
+
+    #[test]
+    fn sv39() {
+        let mut memory = vec![0u8; 0x8000];
+        pte64(&mut memory, ROOT, 2, V);
+        pte64(&mut memory, ROOT + 8, 0, V | R | W | X | A | D);
+        pte64(&mut memory, ROOT + 16, 1, V | R | W | X | A | D);
+        pte64(&mut memory, ROOT + 24, 0, V | R | A | (1 << 62));
+        pte64(&mut memory, ROOT + 511 * 8, 0, V | R | A);
+        pte64(&mut memory, TABLE, 3, V);
+        pte64(&mut memory, TABLE + 8, 0, V | R | A);
+        pte64(&mut memory, 0x3000, 5, V | R | A);
+
+        let mut hart = Hart::<u64>::new(0);
+        csr::write(&mut hart, csr::SATP, (8 << 60) | (ROOT >> 12) as u64).unwrap();
+        hart.privilege = Privilege::Supervisor;
+        let mmu = Mmu::data(&hart);
+        let load = |vaddr| mmu.translate(vaddr, 8, Access::Load, &memory);
+
+        assert_eq!(load(0x10), Ok(0x5010));
+        assert_eq!(load(0x20_0010), Ok(0x10));
+        assert_eq!(load(0x4000_0010), Ok(0x10));
+        assert_eq!(load(0xffff_ffff_c000_0010), Ok(0x10));
+        // misaligned gigapage and reserved PBMT bits
+        assert_eq!(load(0x8000_0000), Err(Error::LoadPageFault(0x8000_0000)));
+        assert_eq!(load(0xc000_0000), Err(Error::LoadPageFault(0xc000_0000)));
+        // bits 63 to 39 must equal bit 38
+        assert_eq!(
+            load(0x40_0000_0010),
+            Err(Error::LoadPageFault(0x40_0000_0010))
+        );
+        assert_eq!(
+            load(0xffff_ff80_0000_0010),
+            Err(Error::LoadPageFault(0xffff_ff80_0000_0010))
+        );
+    }
+
+    #[test]
+    fn sv48_sv57() {
+        let mut memory = vec![0u8; 0x2000];
+        pte64(&mut memory, ROOT + 8, 0, V | R | W | X | A | D);
+        for (mode, levels) in [(9u64, 4), (10, 5)] {
+            let mut hart = Hart::<u64>::new(0);
+            csr::write(&mut hart, csr::SATP, (mode << 60) | (ROOT >> 12) as u64).unwrap();
+            hart.privilege = Privilege::Supervisor;
+            let mmu = Mmu::data(&hart);
+
+            // the largest superpage, a terapage or a petapage
+            let page = 1u64 << (12 + 9 * (levels - 1));
+            assert_eq!(mmu.translate(page + 8, 8, Access::Load, &memory), Ok(8));
+            let noncanonical = page << 8;
+            assert_eq!(
+                mmu.translate(noncanonical, 8, Access::Load, &memory),
+                Err(Error::LoadPageFault(noncanonical as usize))
+            );
+        }
+    }
+
+    #[test]
+    fn sfence_vma() {
+        use crate::isa::Isa;
+
+        // sfence.vma x1, x2
+        const SFENCE_VMA: u32 = 0x1220_8073;
+        let mut memory = [];
+        let mut hart = Hart::<u64>::new(0x100);
+        hart.privilege = Privilege::Supervisor;
+        assert_eq!(u64::try_execute(SFENCE_VMA, &mut hart, &mut memory), Ok(()));
+        assert_eq!(hart.pc, 0x104);
+
+        hart.privilege = Privilege::User;
+        assert_eq!(
+            u64::try_execute(SFENCE_VMA, &mut hart, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+        hart.privilege = Privilege::Supervisor;
+        csr::write(&mut hart, csr::MSTATUS, crate::trap::TVM).unwrap();
+        assert_eq!(
+            u64::try_execute(SFENCE_VMA, &mut hart, &mut memory),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn satp() {
+        let (mut hart, _) = sv32();
+        assert_eq!(csr::read(&mut hart, csr::SATP), Ok((1 << 31) | 1));
+        hart.privilege = Privilege::Supervisor;
+        csr::write(&mut hart, csr::MSTATUS, crate::trap::TVM as u32).unwrap();
+        assert_eq!(
+            csr::check_state(&hart, csr::SATP),
+            Err(Error::InvalidOpCode)
+        );
+
+        // Sv64 is not defined, the write is ignored
+        let mut hart = Hart::<u64>::new(0);
+        csr::write(&mut hart, csr::SATP, (11 << 60) | 1).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::SATP), Ok(0));
+        csr::write(&mut hart, csr::SATP, (8 << 60) | 1).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::SATP), Ok((8 << 60) | 1));
+    }
+
+    #[test]
+    fn user_program() {
+        use crate::isa;
+
+        let (mut hart, mut memory) = sv32();
+        memory[0x3000..0x3004].copy_from_slice(&0x0100_2083u32.to_le_bytes()); // lw x1, 16(x0)
+        memory[0x3004..0x3008].copy_from_slice(&0x0001_a103u32.to_le_bytes()); // lw x2, 0(x3)
+        memory[0x3010..0x3014].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        csr::write(&mut hart, csr::STVEC, 0x100).unwrap();
+        csr::write(&mut hart, csr::MEDELEG, 1 << 13).unwrap();
+        *hart.regs.get_mut(Register::X3) = 0x4000;
+        hart.privilege = Privilege::User;
+        hart.pc = 0x2000;
+
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.regs.get(Register::X1), 0x1234_5678);
+        assert_eq!(hart.pc, 0x2004);
+
+        // the page fault is delegated to S-mode
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x100);
+        assert_eq!(hart.privilege, Privilege::Supervisor);
+        assert_eq!(csr::read(&mut hart, csr::SCAUSE), Ok(13));
+        assert_eq!(csr::read(&mut hart, csr::STVAL), Ok(0x4000));
+        assert_eq!(csr::read(&mut hart, csr::SEPC), Ok(0x2004));
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::missing_safety_doc)]
 pub unsafe trait Unsigned: Bitcast<Self::Signed> + As<Self::Signed> + Sized {
     type Signed: Bitcast<Self> + As<Self>;
 }
@@ -67,7 +68,7 @@ macro_rules! impl_uint {
         impl Bitcast<$st> for $ut {
             #[inline(always)]
             fn bitcast(self) -> $st {
-                unsafe { core::mem::transmute(self) }
+                self as $st
             }
         }
     };
@@ -88,6 +89,7 @@ impl_uint! {
     u16 => i16;
     u32 => i32;
     u64 => i64;
+    u128 => i128;
 }
 
 macro_rules! impl_zero_one {
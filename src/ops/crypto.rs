@@ -0,0 +1,1019 @@
+use crate::{decode::U7, num::Unsigned};
+
+pub trait Pack {
+    fn pack(self, other: Self) -> Self;
+}
+
+pub trait Packh {
+    fn packh(self, other: Self) -> Self;
+}
+
+pub trait Packw {
+    fn packw(self, other: Self) -> Self;
+}
+
+pub trait Brev8 {
+    fn brev8(self) -> Self;
+}
+
+pub trait Zip {
+    fn zip(self) -> Self;
+}
+
+pub trait Unzip {
+    fn unzip(self) -> Self;
+}
+
+pub trait Xperm4 {
+    fn xperm4(self, other: Self) -> Self;
+}
+
+pub trait Xperm8 {
+    fn xperm8(self, other: Self) -> Self;
+}
+
+/// `BS` selects the byte of `other` fed to the S-box, as in all the byte-select instructions.
+pub trait Aes32esi {
+    fn aes32esi<const BS: u8>(self, other: Self) -> Self;
+}
+
+pub trait Aes32esmi {
+    fn aes32esmi<const BS: u8>(self, other: Self) -> Self;
+}
+
+pub trait Aes32dsi {
+    fn aes32dsi<const BS: u8>(self, other: Self) -> Self;
+}
+
+pub trait Aes32dsmi {
+    fn aes32dsmi<const BS: u8>(self, other: Self) -> Self;
+}
+
+pub trait Aes64es {
+    fn aes64es(self, other: Self) -> Self;
+}
+
+pub trait Aes64esm {
+    fn aes64esm(self, other: Self) -> Self;
+}
+
+pub trait Aes64ds {
+    fn aes64ds(self, other: Self) -> Self;
+}
+
+pub trait Aes64dsm {
+    fn aes64dsm(self, other: Self) -> Self;
+}
+
+pub trait Aes64im {
+    fn aes64im(self) -> Self;
+}
+
+pub trait Aes64ks1i {
+    fn aes64ks1i(self, rnum: U7) -> Self;
+}
+
+pub trait Aes64ks2 {
+    fn aes64ks2(self, other: Self) -> Self;
+}
+
+pub trait Sha256sig0 {
+    fn sha256sig0(self) -> Self;
+}
+
+pub trait Sha256sig1 {
+    fn sha256sig1(self) -> Self;
+}
+
+pub trait Sha256sum0 {
+    fn sha256sum0(self) -> Self;
+}
+
+pub trait Sha256sum1 {
+    fn sha256sum1(self) -> Self;
+}
+
+pub trait Sha512sig0 {
+    fn sha512sig0(self) -> Self;
+}
+
+pub trait Sha512sig1 {
+    fn sha512sig1(self) -> Self;
+}
+
+pub trait Sha512sum0 {
+    fn sha512sum0(self) -> Self;
+}
+
+pub trait Sha512sum1 {
+    fn sha512sum1(self) -> Self;
+}
+
+/// RV32 halves of the SHA-512 functions, `self` holds the half being computed and `other` the
+/// opposite one.
+pub trait Sha512sig0h {
+    fn sha512sig0h(self, other: Self) -> Self;
+}
+
+pub trait Sha512sig0l {
+    fn sha512sig0l(self, other: Self) -> Self;
+}
+
+pub trait Sha512sig1h {
+    fn sha512sig1h(self, other: Self) -> Self;
+}
+
+pub trait Sha512sig1l {
+    fn sha512sig1l(self, other: Self) -> Self;
+}
+
+pub trait Sha512sum0r {
+    fn sha512sum0r(self, other: Self) -> Self;
+}
+
+pub trait Sha512sum1r {
+    fn sha512sum1r(self, other: Self) -> Self;
+}
+
+pub trait Sm3p0 {
+    fn sm3p0(self) -> Self;
+}
+
+pub trait Sm3p1 {
+    fn sm3p1(self) -> Self;
+}
+
+pub trait Sm4ed {
+    fn sm4ed<const BS: u8>(self, other: Self) -> Self;
+}
+
+pub trait Sm4ks {
+    fn sm4ks<const BS: u8>(self, other: Self) -> Self;
+}
+
+const AES_SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const AES_INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+const SM4_SBOX: [u8; 256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+/// `MixColumns` coefficients of row 0, the other rows rotate them.
+const MIX: [u8; 4] = [0x02, 0x03, 0x01, 0x01];
+const INV_MIX: [u8; 4] = [0x0e, 0x0b, 0x0d, 0x09];
+
+/// State bytes gathered by `ShiftRows` into the two low columns.
+const SHIFT_ROWS: [usize; 8] = [0, 5, 10, 15, 4, 9, 14, 3];
+const INV_SHIFT_ROWS: [usize; 8] = [0, 13, 10, 7, 4, 1, 14, 11];
+
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+#[inline(always)]
+fn gfmul(a: u8, b: u8) -> u8 {
+    (0..4)
+        .fold((0, a), |(acc, a), i| {
+            let acc = if (b >> i) & 1 != 0 { acc ^ a } else { acc };
+            (acc, (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 })
+        })
+        .0
+}
+
+#[inline(always)]
+fn mix_column(column: u32, coefficients: [u8; 4]) -> u32 {
+    let bytes = column.to_le_bytes();
+    u32::from_le_bytes(core::array::from_fn(|row| {
+        (0..4).fold(0, |acc, i| {
+            acc ^ gfmul(bytes[i], coefficients[(4 + i - row) % 4])
+        })
+    }))
+}
+
+#[inline(always)]
+fn mix_columns(columns: u64, coefficients: [u8; 4]) -> u64 {
+    mix_column(columns as u32, coefficients) as u64
+        | (mix_column((columns >> 32) as u32, coefficients) as u64) << 32
+}
+
+#[inline(always)]
+fn sub_word(word: u32, sbox: &[u8; 256]) -> u32 {
+    u32::from_le_bytes(word.to_le_bytes().map(|b| sbox[b as usize]))
+}
+
+/// Single byte AES round step of the `aes32*` instructions.
+#[inline(always)]
+fn aes32(rs1: u32, rs2: u32, bs: u8, sbox: &[u8; 256], mix: Option<[u8; 4]>) -> u32 {
+    let shamt = bs as u32 * 8;
+    let so = sbox[(rs2 >> shamt) as u8 as usize] as u32;
+    let mixed = match mix {
+        Some(coefficients) => mix_column(so, coefficients),
+        None => so,
+    };
+    rs1 ^ mixed.rotate_left(shamt)
+}
+
+/// Half AES round of the `aes64*` instructions on the state `rs2:rs1`.
+#[inline(always)]
+fn aes64(rs1: u64, rs2: u64, rows: &[usize; 8], sbox: &[u8; 256], mix: Option<[u8; 4]>) -> u64 {
+    let state = ((rs2 as u128) << 64 | rs1 as u128).to_le_bytes();
+    let columns = u64::from_le_bytes(core::array::from_fn(|i| sbox[state[rows[i]] as usize]));
+    match mix {
+        Some(coefficients) => mix_columns(columns, coefficients),
+        None => columns,
+    }
+}
+
+/// Looks up the `width`-bit elements of `lut` indexed by the elements of `indices`, out of range
+/// indices produce zero.
+#[inline(always)]
+fn xperm(lut: u64, indices: u64, width: u32, bits: u32) -> u64 {
+    let mask = (1 << width) - 1;
+    (0..bits).step_by(width as usize).fold(0, |acc, i| {
+        let index = ((indices >> i) & mask) as u32 * width;
+        if index < bits {
+            acc | ((lut >> index) & mask) << i
+        } else {
+            acc
+        }
+    })
+}
+
+/// SM4 round step, `linear` is the linear transformation of the encryption or key schedule.
+#[inline(always)]
+fn sm4(rs1: u32, rs2: u32, bs: u8, linear: fn(u32) -> u32) -> u32 {
+    let shamt = bs as u32 * 8;
+    let x = SM4_SBOX[(rs2 >> shamt) as u8 as usize] as u32;
+    rs1 ^ linear(x).rotate_left(shamt)
+}
+
+macro_rules! impl_crypto {
+    ($t:ty) => {
+        impl Packh for $t {
+            #[inline(always)]
+            fn packh(self, other: Self) -> Self {
+                (self & 0xff) | (other & 0xff) << 8
+            }
+        }
+
+        impl Brev8 for $t {
+            #[inline(always)]
+            fn brev8(self) -> Self {
+                self.swap_bytes().reverse_bits()
+            }
+        }
+
+        impl Xperm4 for $t {
+            #[inline(always)]
+            fn xperm4(self, other: Self) -> Self {
+                xperm(self as u64, other as u64, 4, <$t>::BITS) as $t
+            }
+        }
+
+        impl Xperm8 for $t {
+            #[inline(always)]
+            fn xperm8(self, other: Self) -> Self {
+                xperm(self as u64, other as u64, 8, <$t>::BITS) as $t
+            }
+        }
+
+        impl_crypto!(@word $t {
+            Sha256sig0::sha256sig0 => |x| x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3);
+            Sha256sig1::sha256sig1 => |x| x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10);
+            Sha256sum0::sha256sum0 => |x| x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22);
+            Sha256sum1::sha256sum1 => |x| x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25);
+            Sm3p0::sm3p0 => |x| x ^ x.rotate_left(9) ^ x.rotate_left(17);
+            Sm3p1::sm3p1 => |x| x ^ x.rotate_left(15) ^ x.rotate_left(23);
+        });
+
+        impl Sm4ed for $t {
+            #[inline(always)]
+            fn sm4ed<const BS: u8>(self, other: Self) -> Self {
+                let linear = |x: u32| {
+                    x ^ x.rotate_left(2) ^ x.rotate_left(10) ^ x.rotate_left(18) ^ x.rotate_left(24)
+                };
+                sm4(self as u32, other as u32, BS, linear) as i32 as <$t as Unsigned>::Signed as $t
+            }
+        }
+
+        impl Sm4ks for $t {
+            #[inline(always)]
+            fn sm4ks<const BS: u8>(self, other: Self) -> Self {
+                let linear = |x: u32| x ^ x.rotate_left(13) ^ x.rotate_left(23);
+                sm4(self as u32, other as u32, BS, linear) as i32 as <$t as Unsigned>::Signed as $t
+            }
+        }
+    };
+    // Functions on 32-bit words, sign-extended on RV64.
+    (@word $t:ty { $($tr:ident :: $meth:ident => |$x:ident| $e:expr;)* }) => {
+        $(
+            impl $tr for $t {
+                #[inline(always)]
+                fn $meth(self) -> Self {
+                    let $x = self as u32;
+                    $e as i32 as <$t as Unsigned>::Signed as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_crypto!(u32);
+impl_crypto!(u64);
+
+impl Pack for u32 {
+    #[inline(always)]
+    fn pack(self, other: Self) -> Self {
+        (self & 0xffff) | other << 16
+    }
+}
+
+impl Pack for u64 {
+    #[inline(always)]
+    fn pack(self, other: Self) -> Self {
+        (self & 0xffff_ffff) | other << 32
+    }
+}
+
+impl Packw for u64 {
+    #[inline(always)]
+    fn packw(self, other: Self) -> Self {
+        ((self & 0xffff) | (other & 0xffff) << 16) as u32 as i32 as i64 as u64
+    }
+}
+
+impl Zip for u32 {
+    #[inline(always)]
+    fn zip(self) -> Self {
+        (0..16).fold(0, |acc, i| {
+            acc | ((self >> i) & 1) << (2 * i) | ((self >> (i + 16)) & 1) << (2 * i + 1)
+        })
+    }
+}
+
+impl Unzip for u32 {
+    #[inline(always)]
+    fn unzip(self) -> Self {
+        (0..16).fold(0, |acc, i| {
+            acc | ((self >> (2 * i)) & 1) << i | ((self >> (2 * i + 1)) & 1) << (i + 16)
+        })
+    }
+}
+
+impl Aes32esi for u32 {
+    #[inline(always)]
+    fn aes32esi<const BS: u8>(self, other: Self) -> Self {
+        aes32(self, other, BS, &AES_SBOX, None)
+    }
+}
+
+impl Aes32esmi for u32 {
+    #[inline(always)]
+    fn aes32esmi<const BS: u8>(self, other: Self) -> Self {
+        aes32(self, other, BS, &AES_SBOX, Some(MIX))
+    }
+}
+
+impl Aes32dsi for u32 {
+    #[inline(always)]
+    fn aes32dsi<const BS: u8>(self, other: Self) -> Self {
+        aes32(self, other, BS, &AES_INV_SBOX, None)
+    }
+}
+
+impl Aes32dsmi for u32 {
+    #[inline(always)]
+    fn aes32dsmi<const BS: u8>(self, other: Self) -> Self {
+        aes32(self, other, BS, &AES_INV_SBOX, Some(INV_MIX))
+    }
+}
+
+impl Sha512sig0h for u32 {
+    #[inline(always)]
+    fn sha512sig0h(self, other: Self) -> Self {
+        (self >> 1) ^ (self >> 7) ^ (self >> 8) ^ (other << 31) ^ (other << 24)
+    }
+}
+
+impl Sha512sig0l for u32 {
+    #[inline(always)]
+    fn sha512sig0l(self, other: Self) -> Self {
+        (self >> 1) ^ (self >> 7) ^ (self >> 8) ^ (other << 31) ^ (other << 25) ^ (other << 24)
+    }
+}
+
+impl Sha512sig1h for u32 {
+    #[inline(always)]
+    fn sha512sig1h(self, other: Self) -> Self {
+        (self << 3) ^ (self >> 6) ^ (self >> 19) ^ (other >> 29) ^ (other << 13)
+    }
+}
+
+impl Sha512sig1l for u32 {
+    #[inline(always)]
+    fn sha512sig1l(self, other: Self) -> Self {
+        (self << 3) ^ (self >> 6) ^ (self >> 19) ^ (other >> 29) ^ (other << 26) ^ (other << 13)
+    }
+}
+
+impl Sha512sum0r for u32 {
+    #[inline(always)]
+    fn sha512sum0r(self, other: Self) -> Self {
+        (self << 25) ^ (self << 30) ^ (self >> 28) ^ (other >> 7) ^ (other >> 2) ^ (other << 4)
+    }
+}
+
+impl Sha512sum1r for u32 {
+    #[inline(always)]
+    fn sha512sum1r(self, other: Self) -> Self {
+        (self << 23) ^ (self >> 14) ^ (self >> 18) ^ (other >> 9) ^ (other << 18) ^ (other << 14)
+    }
+}
+
+impl Aes64es for u64 {
+    #[inline(always)]
+    fn aes64es(self, other: Self) -> Self {
+        aes64(self, other, &SHIFT_ROWS, &AES_SBOX, None)
+    }
+}
+
+impl Aes64esm for u64 {
+    #[inline(always)]
+    fn aes64esm(self, other: Self) -> Self {
+        aes64(self, other, &SHIFT_ROWS, &AES_SBOX, Some(MIX))
+    }
+}
+
+impl Aes64ds for u64 {
+    #[inline(always)]
+    fn aes64ds(self, other: Self) -> Self {
+        aes64(self, other, &INV_SHIFT_ROWS, &AES_INV_SBOX, None)
+    }
+}
+
+impl Aes64dsm for u64 {
+    #[inline(always)]
+    fn aes64dsm(self, other: Self) -> Self {
+        aes64(self, other, &INV_SHIFT_ROWS, &AES_INV_SBOX, Some(INV_MIX))
+    }
+}
+
+impl Aes64im for u64 {
+    #[inline(always)]
+    fn aes64im(self) -> Self {
+        mix_columns(self, INV_MIX)
+    }
+}
+
+impl Aes64ks1i for u64 {
+    /// `rnum` must be at most `0xA`, the caller rejects the reserved values.
+    #[inline(always)]
+    fn aes64ks1i(self, rnum: U7) -> Self {
+        let rnum = (rnum.as_u8() & 0xf) as usize;
+        let word = (self >> 32) as u32;
+        let word = if rnum == 0xa {
+            word
+        } else {
+            word.rotate_right(8)
+        };
+        let word = sub_word(word, &AES_SBOX) ^ RCON.get(rnum).copied().unwrap_or(0) as u32;
+        (word as u64) << 32 | word as u64
+    }
+}
+
+impl Aes64ks2 for u64 {
+    #[inline(always)]
+    fn aes64ks2(self, other: Self) -> Self {
+        let low = (self >> 32) as u32 ^ other as u32;
+        let high = low ^ (other >> 32) as u32;
+        (high as u64) << 32 | low as u64
+    }
+}
+
+impl Sha512sig0 for u64 {
+    #[inline(always)]
+    fn sha512sig0(self) -> Self {
+        self.rotate_right(1) ^ self.rotate_right(8) ^ (self >> 7)
+    }
+}
+
+impl Sha512sig1 for u64 {
+    #[inline(always)]
+    fn sha512sig1(self) -> Self {
+        self.rotate_right(19) ^ self.rotate_right(61) ^ (self >> 6)
+    }
+}
+
+impl Sha512sum0 for u64 {
+    #[inline(always)]
+    fn sha512sum0(self) -> Self {
+        self.rotate_right(28) ^ self.rotate_right(34) ^ self.rotate_right(39)
+    }
+}
+
+impl Sha512sum1 for u64 {
+    #[inline(always)]
+    fn sha512sum1(self) -> Self {
+        self.rotate_right(14) ^ self.rotate_right(18) ^ self.rotate_right(41)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const K256: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+
+    const H256: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    const K512: [u64; 80] = [
+        0x428a2f98d728ae22,
+        0x7137449123ef65cd,
+        0xb5c0fbcfec4d3b2f,
+        0xe9b5dba58189dbbc,
+        0x3956c25bf348b538,
+        0x59f111f1b605d019,
+        0x923f82a4af194f9b,
+        0xab1c5ed5da6d8118,
+        0xd807aa98a3030242,
+        0x12835b0145706fbe,
+        0x243185be4ee4b28c,
+        0x550c7dc3d5ffb4e2,
+        0x72be5d74f27b896f,
+        0x80deb1fe3b1696b1,
+        0x9bdc06a725c71235,
+        0xc19bf174cf692694,
+        0xe49b69c19ef14ad2,
+        0xefbe4786384f25e3,
+        0x0fc19dc68b8cd5b5,
+        0x240ca1cc77ac9c65,
+        0x2de92c6f592b0275,
+        0x4a7484aa6ea6e483,
+        0x5cb0a9dcbd41fbd4,
+        0x76f988da831153b5,
+        0x983e5152ee66dfab,
+        0xa831c66d2db43210,
+        0xb00327c898fb213f,
+        0xbf597fc7beef0ee4,
+        0xc6e00bf33da88fc2,
+        0xd5a79147930aa725,
+        0x06ca6351e003826f,
+        0x142929670a0e6e70,
+        0x27b70a8546d22ffc,
+        0x2e1b21385c26c926,
+        0x4d2c6dfc5ac42aed,
+        0x53380d139d95b3df,
+        0x650a73548baf63de,
+        0x766a0abb3c77b2a8,
+        0x81c2c92e47edaee6,
+        0x92722c851482353b,
+        0xa2bfe8a14cf10364,
+        0xa81a664bbc423001,
+        0xc24b8b70d0f89791,
+        0xc76c51a30654be30,
+        0xd192e819d6ef5218,
+        0xd69906245565a910,
+        0xf40e35855771202a,
+        0x106aa07032bbd1b8,
+        0x19a4c116b8d2d0c8,
+        0x1e376c085141ab53,
+        0x2748774cdf8eeb99,
+        0x34b0bcb5e19b48a8,
+        0x391c0cb3c5c95a63,
+        0x4ed8aa4ae3418acb,
+        0x5b9cca4f7763e373,
+        0x682e6ff3d6b2b8a3,
+        0x748f82ee5defb2fc,
+        0x78a5636f43172f60,
+        0x84c87814a1f0ab72,
+        0x8cc702081a6439ec,
+        0x90befffa23631e28,
+        0xa4506cebde82bde9,
+        0xbef9a3f7b2c67915,
+        0xc67178f2e372532b,
+        0xca273eceea26619c,
+        0xd186b8c721c0c207,
+        0xeada7dd6cde0eb1e,
+        0xf57d4f7fee6ed178,
+        0x06f067aa72176fba,
+        0x0a637dc5a2c898a6,
+        0x113f9804bef90dae,
+        0x1b710b35131c471b,
+        0x28db77f523047d84,
+        0x32caab7b40c72493,
+        0x3c9ebe0a15c9bebc,
+        0x431d67c49c100d4c,
+        0x4cc5d4becb3e42b6,
+        0x597f299cfc657e2a,
+        0x5fcb6fab3ad6faec,
+        0x6c44198c4a475817,
+    ];
+
+    const H512: [u64; 8] = [
+        0x6a09e667f3bcc908,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+
+    const AES_KEY: u128 = 0x000102030405060708090a0b0c0d0e0f;
+    const AES_PLAIN: u128 = 0x00112233445566778899aabbccddeeff;
+    const AES_CIPHER: u128 = 0x69c4e0d86a7b0430d8cdb78070b4c55a;
+
+    /// Splits a big-endian block into the little-endian doublewords loaded by a RV64 hart.
+    fn le64(block: u128) -> [u64; 2] {
+        let bytes = block.to_be_bytes();
+        core::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+    }
+
+    fn le32(block: u128) -> [u32; 4] {
+        let [low, high] = le64(block);
+        [
+            low as u32,
+            (low >> 32) as u32,
+            high as u32,
+            (high >> 32) as u32,
+        ]
+    }
+
+    fn aes128_key_schedule() -> [[u64; 2]; 11] {
+        let mut keys = [le64(AES_KEY); 11];
+        for round in 0..10 {
+            let [k0, k1] = keys[round];
+            let t = k1.aes64ks1i(U7::new_truncate(round as u8));
+            let k0 = t.aes64ks2(k0);
+            keys[round + 1] = [k0, k0.aes64ks2(k1)];
+        }
+        keys
+    }
+
+    #[test]
+    fn aes64_fips197() {
+        let keys = aes128_key_schedule();
+        assert_eq!(keys[10], le64(0x13111d7fe3944a17f307a78b4d2b30c5));
+
+        let [mut s0, mut s1] = le64(AES_PLAIN);
+        s0 ^= keys[0][0];
+        s1 ^= keys[0][1];
+        for (round, key) in keys.iter().enumerate().skip(1) {
+            (s0, s1) = if round == 10 {
+                (s0.aes64es(s1), s1.aes64es(s0))
+            } else {
+                (s0.aes64esm(s1), s1.aes64esm(s0))
+            };
+            s0 ^= key[0];
+            s1 ^= key[1];
+        }
+        assert_eq!([s0, s1], le64(AES_CIPHER));
+
+        s0 ^= keys[10][0];
+        s1 ^= keys[10][1];
+        for round in (0..10).rev() {
+            let [k0, k1] = keys[round];
+            (s0, s1) = if round == 0 {
+                (s0.aes64ds(s1) ^ k0, s1.aes64ds(s0) ^ k1)
+            } else {
+                (
+                    s0.aes64dsm(s1) ^ k0.aes64im(),
+                    s1.aes64dsm(s0) ^ k1.aes64im(),
+                )
+            };
+        }
+        assert_eq!([s0, s1], le64(AES_PLAIN));
+    }
+
+    #[test]
+    fn aes32_fips197() {
+        type Step = fn(u32, u32) -> u32;
+        const ESI: [Step; 4] = [
+            u32::aes32esi::<0>,
+            u32::aes32esi::<1>,
+            u32::aes32esi::<2>,
+            u32::aes32esi::<3>,
+        ];
+        const ESMI: [Step; 4] = [
+            u32::aes32esmi::<0>,
+            u32::aes32esmi::<1>,
+            u32::aes32esmi::<2>,
+            u32::aes32esmi::<3>,
+        ];
+        const DSI: [Step; 4] = [
+            u32::aes32dsi::<0>,
+            u32::aes32dsi::<1>,
+            u32::aes32dsi::<2>,
+            u32::aes32dsi::<3>,
+        ];
+        const DSMI: [Step; 4] = [
+            u32::aes32dsmi::<0>,
+            u32::aes32dsmi::<1>,
+            u32::aes32dsmi::<2>,
+            u32::aes32dsmi::<3>,
+        ];
+
+        // Column `j` takes row `r` from column `j + r` encrypting and `j - r` decrypting.
+        fn round(state: [u32; 4], key: [u32; 4], steps: &[Step; 4], stride: usize) -> [u32; 4] {
+            core::array::from_fn(|j| {
+                (0..4).fold(key[j], |t, r| steps[r](t, state[(j + stride * r) % 4]))
+            })
+        }
+
+        let keys = aes128_key_schedule()
+            .map(|[k0, k1]| [k0 as u32, (k0 >> 32) as u32, k1 as u32, (k1 >> 32) as u32]);
+        let xor = |a: [u32; 4], b: [u32; 4]| -> [u32; 4] { core::array::from_fn(|i| a[i] ^ b[i]) };
+
+        let mut state = xor(le32(AES_PLAIN), keys[0]);
+        for (i, key) in keys.iter().enumerate().skip(1) {
+            state = round(state, *key, if i == 10 { &ESI } else { &ESMI }, 1);
+        }
+        assert_eq!(state, le32(AES_CIPHER));
+
+        state = xor(state, keys[10]);
+        for i in (0..10).rev() {
+            state = if i == 0 {
+                round(state, keys[0], &DSI, 3)
+            } else {
+                // Decryption round keys go through InvMixColumns like the state
+                let [k0, k1] = [
+                    (keys[i][1] as u64) << 32 | keys[i][0] as u64,
+                    (keys[i][3] as u64) << 32 | keys[i][2] as u64,
+                ]
+                .map(|k| k.aes64im());
+                let key = [k0 as u32, (k0 >> 32) as u32, k1 as u32, (k1 >> 32) as u32];
+                round(state, key, &DSMI, 3)
+            };
+        }
+        assert_eq!(state, le32(AES_PLAIN));
+    }
+
+    /// Single block message "abc" padded for SHA-256 and SM3.
+    fn abc_block32() -> [u32; 16] {
+        let mut block = [0; 16];
+        block[0] = 0x61626380;
+        block[15] = 24;
+        block
+    }
+
+    #[test]
+    fn sha256_abc() {
+        let mut w = [0u32; 64];
+        w[..16].copy_from_slice(&abc_block32());
+        for i in 16..64 {
+            w[i] = w[i - 7]
+                .wrapping_add(w[i - 16])
+                .wrapping_add(w[i - 2].sha256sig1())
+                .wrapping_add(w[i - 15].sha256sig0());
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = H256;
+        for i in 0..64 {
+            let t1 = h
+                .wrapping_add(e.sha256sum1())
+                .wrapping_add((e & f) ^ (!e & g))
+                .wrapping_add(K256[i])
+                .wrapping_add(w[i]);
+            let t2 = a.sha256sum0().wrapping_add((a & b) ^ (a & c) ^ (b & c));
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        let digest: [u32; 8] =
+            core::array::from_fn(|i| H256[i].wrapping_add([a, b, c, d, e, f, g, h][i]));
+        assert_eq!(
+            digest,
+            [
+                0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+                0xf20015ad,
+            ]
+        );
+
+        // RV64 computes on the low word and sign-extends
+        assert_eq!(
+            0xdead_beef_8000_0000u64.sha256sig0(),
+            0x8000_0000u32.sha256sig0() as i32 as u64
+        );
+    }
+
+    #[test]
+    fn sha512_abc() {
+        let mut w = [0u64; 80];
+        w[0] = 0x6162638000000000;
+        w[15] = 24;
+        for i in 16..80 {
+            w[i] = w[i - 7]
+                .wrapping_add(w[i - 16])
+                .wrapping_add(w[i - 2].sha512sig1())
+                .wrapping_add(w[i - 15].sha512sig0());
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = H512;
+        for i in 0..80 {
+            let t1 = h
+                .wrapping_add(e.sha512sum1())
+                .wrapping_add((e & f) ^ (!e & g))
+                .wrapping_add(K512[i])
+                .wrapping_add(w[i]);
+            let t2 = a.sha512sum0().wrapping_add((a & b) ^ (a & c) ^ (b & c));
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        let digest: [u64; 8] =
+            core::array::from_fn(|i| H512[i].wrapping_add([a, b, c, d, e, f, g, h][i]));
+        assert_eq!(
+            digest,
+            [
+                0xddaf35a193617aba,
+                0xcc417349ae204131,
+                0x12e6fa4e89a97ea2,
+                0x0a9eeee64b55d39a,
+                0x2192992a274fc1a8,
+                0x36ba3c23a3feebbd,
+                0x454d4423643ce80e,
+                0x2a9ac94fa54ca49f,
+            ]
+        );
+
+        // The RV32 instructions compute the same functions one half at a time
+        for x in w {
+            let (high, low) = ((x >> 32) as u32, x as u32);
+            let halves = |high: u32, low: u32| (high as u64) << 32 | low as u64;
+            assert_eq!(
+                halves(high.sha512sig0h(low), low.sha512sig0l(high)),
+                x.sha512sig0()
+            );
+            assert_eq!(
+                halves(high.sha512sig1h(low), low.sha512sig1l(high)),
+                x.sha512sig1()
+            );
+            assert_eq!(
+                halves(high.sha512sum0r(low), low.sha512sum0r(high)),
+                x.sha512sum0()
+            );
+            assert_eq!(
+                halves(high.sha512sum1r(low), low.sha512sum1r(high)),
+                x.sha512sum1()
+            );
+        }
+    }
+
+    #[test]
+    fn sm3_abc() {
+        const IV: [u32; 8] = [
+            0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d,
+            0xb0fb0e4e,
+        ];
+
+        let mut w = [0u32; 68];
+        w[..16].copy_from_slice(&abc_block32());
+        for j in 16..68 {
+            w[j] = (w[j - 16] ^ w[j - 9] ^ w[j - 3].rotate_left(15)).sm3p1()
+                ^ w[j - 13].rotate_left(7)
+                ^ w[j - 6];
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = IV;
+        for j in 0..64 {
+            let (t, ff, gg) = if j < 16 {
+                (0x79cc4519u32, a ^ b ^ c, e ^ f ^ g)
+            } else {
+                (0x7a879d8a, (a & b) | (a & c) | (b & c), (e & f) | (!e & g))
+            };
+            let ss1 = a
+                .rotate_left(12)
+                .wrapping_add(e)
+                .wrapping_add(t.rotate_left(j % 32))
+                .rotate_left(7);
+            let ss2 = ss1 ^ a.rotate_left(12);
+            let j = j as usize;
+            let tt1 = ff
+                .wrapping_add(d)
+                .wrapping_add(ss2)
+                .wrapping_add(w[j] ^ w[j + 4]);
+            let tt2 = gg.wrapping_add(h).wrapping_add(ss1).wrapping_add(w[j]);
+            (d, c, b, a) = (c, b.rotate_left(9), a, tt1);
+            (h, g, f, e) = (g, f.rotate_left(19), e, tt2.sm3p0());
+        }
+        let digest: [u32; 8] = core::array::from_fn(|i| IV[i] ^ [a, b, c, d, e, f, g, h][i]);
+        assert_eq!(
+            digest,
+            [
+                0x66c7f0f4, 0x62eeedd9, 0xd1f2d46b, 0xdc10e4e2, 0x4167c487, 0x5cf2f7a2, 0x297da02b,
+                0x8f4ba8e0,
+            ]
+        );
+    }
+
+    #[test]
+    fn sm4_standard() {
+        const FK: [u32; 4] = [0xa3b1bac6, 0x56aa3350, 0x677d9197, 0xb27022dc];
+        const BLOCK: [u32; 4] = [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210];
+
+        let round = |acc: u32, x: u32, key: bool| -> u32 {
+            if key {
+                acc.sm4ks::<0>(x).sm4ks::<1>(x).sm4ks::<2>(x).sm4ks::<3>(x)
+            } else {
+                acc.sm4ed::<0>(x).sm4ed::<1>(x).sm4ed::<2>(x).sm4ed::<3>(x)
+            }
+        };
+
+        let mut k: Vec<u32> = (0..4).map(|i| BLOCK[i] ^ FK[i]).collect();
+        for i in 0..32 {
+            let ck = u32::from_be_bytes(core::array::from_fn(|j| ((4 * i + j) * 7) as u8));
+            let next = round(k[i], k[i + 1] ^ k[i + 2] ^ k[i + 3] ^ ck, true);
+            k.push(next);
+        }
+
+        let mut x = BLOCK.to_vec();
+        for i in 0..32 {
+            let next = round(x[i], x[i + 1] ^ x[i + 2] ^ x[i + 3] ^ k[i + 4], false);
+            x.push(next);
+        }
+        assert_eq!(
+            [x[35], x[34], x[33], x[32]],
+            [0x681edf34, 0xd206965e, 0x86b3e94f, 0x536e4246]
+        );
+
+        // RV64 sign-extends the 32-bit result
+        assert_eq!(
+            0u64.sm4ed::<3>(0x8000_0000_0000_0000),
+            0u32.sm4ed::<3>(0) as i32 as u64
+        );
+    }
+
+    #[test]
+    fn zbkb_zbkx() {
+        assert_eq!(Pack::pack(0xdead_1234u32, 0xbeef_5678), 0x5678_1234);
+        assert_eq!(Pack::pack(u64::MAX, 1), 0x1_ffff_ffff);
+        assert_eq!(Packh::packh(0x1234u32, 0x5678), 0x7834);
+        assert_eq!(Packw::packw(0x1234u64, 0x8765), 0xffff_ffff_8765_1234);
+        assert_eq!(Brev8::brev8(0x0102_0380u32), 0x8040_c001);
+        assert_eq!(Zip::zip(0xffff_0000u32), 0xaaaa_aaaa);
+        assert_eq!(Unzip::unzip(0xaaaa_aaaau32), 0xffff_0000);
+        assert_eq!(Unzip::unzip(Zip::zip(0x1234_5678u32)), 0x1234_5678);
+        assert_eq!(Xperm8::xperm8(0x4433_2211u32, 0x0400_0103), 0x0011_2244);
+        assert_eq!(
+            Xperm4::xperm4(0xfedc_ba98_7654_3210u64, 0x0123_4567_89ab_cdef),
+            0x0123_4567_89ab_cdef
+        );
+        assert_eq!(Xperm4::xperm4(0x7654_3210u32, 0x0000_00f8), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    decode::{U12, U5},
-    num::{As, Bitcast, Unsigned},
+    decode::{U12, U7},
+    num::{As, Bitcast, Unsigned, Zero},
 };
 
 pub trait Add {
@@ -68,23 +68,193 @@ pub trait Andi {
 }
 
 pub trait Slli {
-    fn slli(self, other: U5) -> Self;
+    fn slli(self, other: U7) -> Self;
 }
 
 pub trait Srli {
-    fn srli(self, other: U5) -> Self;
+    fn srli(self, other: U7) -> Self;
 }
 
 pub trait Srai {
-    fn srai(self, other: U5) -> Self;
+    fn srai(self, other: U7) -> Self;
+}
+
+pub trait Mul {
+    fn mul(self, other: Self) -> Self;
+}
+
+pub trait Mulh {
+    fn mulh(self, other: Self) -> Self;
+}
+
+pub trait Mulhsu {
+    fn mulhsu(self, other: Self) -> Self;
+}
+
+pub trait Mulhu {
+    fn mulhu(self, other: Self) -> Self;
+}
+
+pub trait Div {
+    fn div(self, other: Self) -> Self;
+}
+
+pub trait Divu {
+    fn divu(self, other: Self) -> Self;
+}
+
+pub trait Rem {
+    fn rem(self, other: Self) -> Self;
+}
+
+pub trait Remu {
+    fn remu(self, other: Self) -> Self;
+}
+
+pub trait Min {
+    fn min(self, other: Self) -> Self;
+}
+
+pub trait Max {
+    fn max(self, other: Self) -> Self;
+}
+
+pub trait Minu {
+    fn minu(self, other: Self) -> Self;
+}
+
+pub trait Maxu {
+    fn maxu(self, other: Self) -> Self;
+}
+
+pub trait Sh1add {
+    fn sh1add(self, other: Self) -> Self;
+}
+
+pub trait Sh2add {
+    fn sh2add(self, other: Self) -> Self;
+}
+
+pub trait Sh3add {
+    fn sh3add(self, other: Self) -> Self;
+}
+
+pub trait Andn {
+    fn andn(self, other: Self) -> Self;
+}
+
+pub trait Orn {
+    fn orn(self, other: Self) -> Self;
+}
+
+pub trait Xnor {
+    fn xnor(self, other: Self) -> Self;
+}
+
+pub trait Clz {
+    fn clz(self) -> Self;
+}
+
+pub trait Ctz {
+    fn ctz(self) -> Self;
+}
+
+pub trait Cpop {
+    fn cpop(self) -> Self;
+}
+
+pub trait SextB {
+    fn sext_b(self) -> Self;
+}
+
+pub trait SextH {
+    fn sext_h(self) -> Self;
+}
+
+pub trait ZextH {
+    fn zext_h(self) -> Self;
+}
+
+pub trait Rol {
+    fn rol(self, other: Self) -> Self;
+}
+
+pub trait Ror {
+    fn ror(self, other: Self) -> Self;
+}
+
+pub trait Rori {
+    fn rori(self, other: U7) -> Self;
+}
+
+pub trait OrcB {
+    fn orc_b(self) -> Self;
+}
+
+pub trait Rev8 {
+    fn rev8(self) -> Self;
+}
+
+pub trait Clmul {
+    fn clmul(self, other: Self) -> Self;
+}
+
+pub trait Clmulh {
+    fn clmulh(self, other: Self) -> Self;
+}
+
+pub trait Clmulr {
+    fn clmulr(self, other: Self) -> Self;
+}
+
+pub trait Bclr {
+    fn bclr(self, other: Self) -> Self;
+}
+
+pub trait Bext {
+    fn bext(self, other: Self) -> Self;
+}
+
+pub trait Binv {
+    fn binv(self, other: Self) -> Self;
+}
+
+pub trait Bset {
+    fn bset(self, other: Self) -> Self;
+}
+
+pub trait Bclri {
+    fn bclri(self, other: U7) -> Self;
+}
+
+pub trait Bexti {
+    fn bexti(self, other: U7) -> Self;
+}
+
+pub trait Binvi {
+    fn binvi(self, other: U7) -> Self;
+}
+
+pub trait Bseti {
+    fn bseti(self, other: U7) -> Self;
+}
+
+pub trait CzeroEqz {
+    fn czero_eqz(self, other: Self) -> Self;
+}
+
+pub trait CzeroNez {
+    fn czero_nez(self, other: Self) -> Self;
 }
 
 pub trait ShiftBits {
     type Type;
 }
 
+#[allow(dead_code)]
 pub trait Shift: ShiftBits + Sll + Srl + Sra {}
 
+#[allow(dead_code)]
 pub trait BaseMath:
     Add
     + Sub
@@ -130,24 +300,128 @@ pub trait Sraw {
     fn sraw(self, other: Self) -> Self;
 }
 
+pub trait Mulw {
+    fn mulw(self, other: Self) -> Self;
+}
+
+pub trait Divw {
+    fn divw(self, other: Self) -> Self;
+}
+
+pub trait Divuw {
+    fn divuw(self, other: Self) -> Self;
+}
+
+pub trait Remw {
+    fn remw(self, other: Self) -> Self;
+}
+
+pub trait Remuw {
+    fn remuw(self, other: Self) -> Self;
+}
+
 pub trait Addiw {
     fn addiw(self, other: U12) -> Self;
 }
 
 pub trait Slliw {
-    fn slliw(self, other: U5) -> Self;
+    fn slliw(self, other: U7) -> Self;
 }
 
 pub trait Srliw {
-    fn srliw(self, other: U5) -> Self;
+    fn srliw(self, other: U7) -> Self;
 }
 
 pub trait Sraiw {
-    fn sraiw(self, other: U5) -> Self;
+    fn sraiw(self, other: U7) -> Self;
+}
+
+pub trait AddUw {
+    fn add_uw(self, other: Self) -> Self;
+}
+
+pub trait Sh1addUw {
+    fn sh1add_uw(self, other: Self) -> Self;
+}
+
+pub trait Sh2addUw {
+    fn sh2add_uw(self, other: Self) -> Self;
+}
+
+pub trait Sh3addUw {
+    fn sh3add_uw(self, other: Self) -> Self;
 }
 
+pub trait SlliUw {
+    fn slli_uw(self, other: U7) -> Self;
+}
+
+pub trait Rolw {
+    fn rolw(self, other: Self) -> Self;
+}
+
+pub trait Rorw {
+    fn rorw(self, other: Self) -> Self;
+}
+
+pub trait Roriw {
+    fn roriw(self, other: U7) -> Self;
+}
+
+pub trait Clzw {
+    fn clzw(self) -> Self;
+}
+
+pub trait Ctzw {
+    fn ctzw(self) -> Self;
+}
+
+pub trait Cpopw {
+    fn cpopw(self) -> Self;
+}
+
+pub trait Addd {
+    fn addd(self, other: Self) -> Self;
+}
+
+pub trait Subd {
+    fn subd(self, other: Self) -> Self;
+}
+
+pub trait Slld {
+    fn slld(self, other: Self) -> Self;
+}
+
+pub trait Srld {
+    fn srld(self, other: Self) -> Self;
+}
+
+pub trait Srad {
+    fn srad(self, other: Self) -> Self;
+}
+
+pub trait Addid {
+    fn addid(self, other: U12) -> Self;
+}
+
+pub trait Sllid {
+    fn sllid(self, other: U7) -> Self;
+}
+
+pub trait Srlid {
+    fn srlid(self, other: U7) -> Self;
+}
+
+pub trait Sraid {
+    fn sraid(self, other: U7) -> Self;
+}
+
+#[allow(dead_code)]
 pub trait MathW: BaseMath + Addw + Subw + Sllw + Srlw + Sraw {}
 
+#[allow(dead_code)]
+pub trait MathD: MathW + Addd + Subd + Slld + Srld + Srad {}
+
 macro_rules! impl_ops {
     ($t:ty) => {
         impl Add for $t {
@@ -181,12 +455,8 @@ macro_rules! impl_ops {
         impl Sra for $t {
             #[inline(always)]
             fn sra(self, other: Self) -> Self {
-                unsafe {
-                    core::mem::transmute(
-                        core::mem::transmute::<_, <$t as Unsigned>::Signed>(self)
-                            .wrapping_shr(other as <$t as ShiftBits>::Type),
-                    )
-                }
+                (self as <$t as Unsigned>::Signed).wrapping_shr(other as <$t as ShiftBits>::Type)
+                    as $t
             }
         }
     };
@@ -194,6 +464,245 @@ macro_rules! impl_ops {
 
 impl_ops!(u32);
 impl_ops!(u64);
+impl_ops!(u128);
+
+macro_rules! impl_muldiv {
+    ($t:ty => $wide:ty, $swide:ty) => {
+        impl Mul for $t {
+            #[inline(always)]
+            fn mul(self, other: Self) -> Self {
+                <$t>::wrapping_mul(self, other)
+            }
+        }
+
+        impl Mulh for $t {
+            #[inline(always)]
+            fn mulh(self, other: Self) -> Self {
+                type Signed = <$t as Unsigned>::Signed;
+                ((self as Signed as $swide * other as Signed as $swide) >> <$t>::BITS) as $t
+            }
+        }
+
+        impl Mulhsu for $t {
+            #[inline(always)]
+            fn mulhsu(self, other: Self) -> Self {
+                type Signed = <$t as Unsigned>::Signed;
+                ((self as Signed as $swide * other as $swide) >> <$t>::BITS) as $t
+            }
+        }
+
+        impl Mulhu for $t {
+            #[inline(always)]
+            fn mulhu(self, other: Self) -> Self {
+                ((self as $wide * other as $wide) >> <$t>::BITS) as $t
+            }
+        }
+
+        impl Div for $t {
+            #[inline(always)]
+            fn div(self, other: Self) -> Self {
+                type Signed = <$t as Unsigned>::Signed;
+                if other == 0 {
+                    <$t>::MAX
+                } else {
+                    (self as Signed).wrapping_div(other as Signed) as $t
+                }
+            }
+        }
+
+        impl Divu for $t {
+            #[inline(always)]
+            fn divu(self, other: Self) -> Self {
+                if other == 0 {
+                    <$t>::MAX
+                } else {
+                    self / other
+                }
+            }
+        }
+
+        impl Rem for $t {
+            #[inline(always)]
+            fn rem(self, other: Self) -> Self {
+                type Signed = <$t as Unsigned>::Signed;
+                if other == 0 {
+                    self
+                } else {
+                    (self as Signed).wrapping_rem(other as Signed) as $t
+                }
+            }
+        }
+
+        impl Remu for $t {
+            #[inline(always)]
+            fn remu(self, other: Self) -> Self {
+                if other == 0 {
+                    self
+                } else {
+                    self % other
+                }
+            }
+        }
+    };
+}
+
+impl_muldiv!(u32 => u64, i64);
+impl_muldiv!(u64 => u128, i128);
+
+macro_rules! impl_bitmanip {
+    ($t:ty) => {
+        impl Sh1add for $t {
+            #[inline(always)]
+            fn sh1add(self, other: Self) -> Self {
+                other.wrapping_add(self << 1)
+            }
+        }
+
+        impl Sh2add for $t {
+            #[inline(always)]
+            fn sh2add(self, other: Self) -> Self {
+                other.wrapping_add(self << 2)
+            }
+        }
+
+        impl Sh3add for $t {
+            #[inline(always)]
+            fn sh3add(self, other: Self) -> Self {
+                other.wrapping_add(self << 3)
+            }
+        }
+
+        impl Clz for $t {
+            #[inline(always)]
+            fn clz(self) -> Self {
+                self.leading_zeros() as $t
+            }
+        }
+
+        impl Ctz for $t {
+            #[inline(always)]
+            fn ctz(self) -> Self {
+                self.trailing_zeros() as $t
+            }
+        }
+
+        impl Cpop for $t {
+            #[inline(always)]
+            fn cpop(self) -> Self {
+                self.count_ones() as $t
+            }
+        }
+
+        impl SextB for $t {
+            #[inline(always)]
+            fn sext_b(self) -> Self {
+                self as i8 as <$t as Unsigned>::Signed as $t
+            }
+        }
+
+        impl SextH for $t {
+            #[inline(always)]
+            fn sext_h(self) -> Self {
+                self as i16 as <$t as Unsigned>::Signed as $t
+            }
+        }
+
+        impl ZextH for $t {
+            #[inline(always)]
+            fn zext_h(self) -> Self {
+                self as u16 as $t
+            }
+        }
+
+        impl Rol for $t {
+            #[inline(always)]
+            fn rol(self, other: Self) -> Self {
+                self.rotate_left((other % <$t>::BITS as $t) as u32)
+            }
+        }
+
+        impl Ror for $t {
+            #[inline(always)]
+            fn ror(self, other: Self) -> Self {
+                self.rotate_right((other % <$t>::BITS as $t) as u32)
+            }
+        }
+
+        impl OrcB for $t {
+            #[inline(always)]
+            fn orc_b(self) -> Self {
+                <$t>::from_ne_bytes(self.to_ne_bytes().map(|b| if b == 0 { 0 } else { 0xff }))
+            }
+        }
+
+        impl Rev8 for $t {
+            #[inline(always)]
+            fn rev8(self) -> Self {
+                self.swap_bytes()
+            }
+        }
+
+        impl Clmul for $t {
+            #[inline(always)]
+            fn clmul(self, other: Self) -> Self {
+                clmul_wide(self as u64, other as u64, <$t>::BITS) as $t
+            }
+        }
+
+        impl Clmulh for $t {
+            #[inline(always)]
+            fn clmulh(self, other: Self) -> Self {
+                (clmul_wide(self as u64, other as u64, <$t>::BITS) >> <$t>::BITS) as $t
+            }
+        }
+
+        impl Clmulr for $t {
+            #[inline(always)]
+            fn clmulr(self, other: Self) -> Self {
+                (clmul_wide(self as u64, other as u64, <$t>::BITS) >> (<$t>::BITS - 1)) as $t
+            }
+        }
+
+        impl Bclr for $t {
+            #[inline(always)]
+            fn bclr(self, other: Self) -> Self {
+                self & !(1 << (other % <$t>::BITS as $t))
+            }
+        }
+
+        impl Bext for $t {
+            #[inline(always)]
+            fn bext(self, other: Self) -> Self {
+                (self >> (other % <$t>::BITS as $t)) & 1
+            }
+        }
+
+        impl Binv for $t {
+            #[inline(always)]
+            fn binv(self, other: Self) -> Self {
+                self ^ (1 << (other % <$t>::BITS as $t))
+            }
+        }
+
+        impl Bset for $t {
+            #[inline(always)]
+            fn bset(self, other: Self) -> Self {
+                self | (1 << (other % <$t>::BITS as $t))
+            }
+        }
+    };
+}
+
+/// Carry-less product of two `bits`-wide operands.
+#[inline(always)]
+fn clmul_wide(a: u64, b: u64, bits: u32) -> u128 {
+    (0..bits)
+        .filter(|i| (b >> i) & 1 != 0)
+        .fold(0, |acc, i| acc ^ ((a as u128) << i))
+}
+
+impl_bitmanip!(u32);
+impl_bitmanip!(u64);
 
 impl ShiftBits for u32 {
     type Type = u32;
@@ -203,6 +712,10 @@ impl ShiftBits for u64 {
     type Type = u32;
 }
 
+impl ShiftBits for u128 {
+    type Type = u32;
+}
+
 impl<T> Slt for T
 where
     T: Unsigned,
@@ -228,6 +741,118 @@ where
     }
 }
 
+impl<T> Min for T
+where
+    T: Unsigned + Copy,
+    <T as Unsigned>::Signed: core::cmp::Ord,
+{
+    #[inline(always)]
+    fn min(self, other: Self) -> Self {
+        if Bitcast::<<T as Unsigned>::Signed>::bitcast(self)
+            < Bitcast::<<T as Unsigned>::Signed>::bitcast(other)
+        {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl<T> Max for T
+where
+    T: Unsigned + Copy,
+    <T as Unsigned>::Signed: core::cmp::Ord,
+{
+    #[inline(always)]
+    fn max(self, other: Self) -> Self {
+        if Bitcast::<<T as Unsigned>::Signed>::bitcast(self)
+            < Bitcast::<<T as Unsigned>::Signed>::bitcast(other)
+        {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<T> Minu for T
+where
+    T: core::cmp::Ord,
+{
+    #[inline(always)]
+    fn minu(self, other: Self) -> Self {
+        core::cmp::min(self, other)
+    }
+}
+
+impl<T> Maxu for T
+where
+    T: core::cmp::Ord,
+{
+    #[inline(always)]
+    fn maxu(self, other: Self) -> Self {
+        core::cmp::max(self, other)
+    }
+}
+
+impl<T> CzeroEqz for T
+where
+    T: Zero + PartialEq,
+{
+    #[inline(always)]
+    fn czero_eqz(self, other: Self) -> Self {
+        if other == T::zero() {
+            T::zero()
+        } else {
+            self
+        }
+    }
+}
+
+impl<T> CzeroNez for T
+where
+    T: Zero + PartialEq,
+{
+    #[inline(always)]
+    fn czero_nez(self, other: Self) -> Self {
+        if other == T::zero() {
+            self
+        } else {
+            T::zero()
+        }
+    }
+}
+
+impl<T> Andn for T
+where
+    T: core::ops::BitAnd<Output = T> + core::ops::Not<Output = T>,
+{
+    #[inline(always)]
+    fn andn(self, other: Self) -> Self {
+        self & !other
+    }
+}
+
+impl<T> Orn for T
+where
+    T: core::ops::BitOr<Output = T> + core::ops::Not<Output = T>,
+{
+    #[inline(always)]
+    fn orn(self, other: Self) -> Self {
+        self | !other
+    }
+}
+
+impl<T> Xnor for T
+where
+    T: core::ops::BitXor<Output = T> + core::ops::Not<Output = T>,
+{
+    #[inline(always)]
+    fn xnor(self, other: Self) -> Self {
+        !(self ^ other)
+    }
+}
+
 impl<T> Xor for T
 where
     T: core::ops::BitXor<Output = T>,
@@ -324,31 +949,81 @@ where
 
 impl<T> Slli for T
 where
-    T: Sll + From<U5>,
+    T: Sll + From<U7>,
 {
     #[inline(always)]
-    fn slli(self, other: U5) -> Self {
+    fn slli(self, other: U7) -> Self {
         Sll::sll(self, other.into())
     }
 }
 
-impl<T: Srl + From<U5>> Srli for T {
+impl<T: Srl + From<U7>> Srli for T {
     #[inline(always)]
-    fn srli(self, other: U5) -> Self {
+    fn srli(self, other: U7) -> Self {
         Srl::srl(self, other.into())
     }
 }
 
 impl<T> Srai for T
 where
-    T: Sra + From<U5>,
+    T: Sra + From<U7>,
 {
     #[inline(always)]
-    fn srai(self, other: U5) -> Self {
+    fn srai(self, other: U7) -> Self {
         Sra::sra(self, other.into())
     }
 }
 
+impl<T> Rori for T
+where
+    T: Ror + From<U7>,
+{
+    #[inline(always)]
+    fn rori(self, other: U7) -> Self {
+        Ror::ror(self, other.into())
+    }
+}
+
+impl<T> Bclri for T
+where
+    T: Bclr + From<U7>,
+{
+    #[inline(always)]
+    fn bclri(self, other: U7) -> Self {
+        Bclr::bclr(self, other.into())
+    }
+}
+
+impl<T> Bexti for T
+where
+    T: Bext + From<U7>,
+{
+    #[inline(always)]
+    fn bexti(self, other: U7) -> Self {
+        Bext::bext(self, other.into())
+    }
+}
+
+impl<T> Binvi for T
+where
+    T: Binv + From<U7>,
+{
+    #[inline(always)]
+    fn binvi(self, other: U7) -> Self {
+        Binv::binv(self, other.into())
+    }
+}
+
+impl<T> Bseti for T
+where
+    T: Bset + From<U7>,
+{
+    #[inline(always)]
+    fn bseti(self, other: U7) -> Self {
+        Bset::bset(self, other.into())
+    }
+}
+
 impl<T: ShiftBits + Sll + Srl + Sra> Shift for T {}
 impl<T> BaseMath for T where
     T: Add
@@ -374,49 +1049,215 @@ impl<T> BaseMath for T where
 {
 }
 
+/// Implements the operations of `$wide` working on its low `$narrow` bits, the result is
+/// sign-extended back to `$wide`.
 macro_rules! forward_mathw {
-    () => {};
-    (@wrap $t:ident :: $meth:ident => ($meth32:expr) (|$name:ident| $b:expr) : $bt:ty) => {
-        impl $t for u64 {
+    ($wide:ty => $narrow:ty {}) => {};
+    (@wrap $wide:ty => $narrow:ty, $t:ident :: $meth:ident => ($op:expr) (|$name:ident| $b:expr) : $bt:ty) => {
+        impl $t for $wide {
             #[inline(always)]
             fn $meth(self, $name: $bt) -> Self {
-                crate::ops::Imm::imm($meth32(self as u32, $b))
+                $op(self as $narrow, $b) as <$narrow as Unsigned>::Signed
+                    as <$wide as Unsigned>::Signed as $wide
             }
         }
     };
-    (i12 $t:ident :: $meth:ident => $meth32:expr) => {
-        forward_mathw!(@wrap $t :: $meth => ($meth32) (|other| other) : U12);
-    };
-    (i5 $t:ident :: $meth:ident => $meth32:expr) => {
-        forward_mathw!(@wrap $t :: $meth => ($meth32) (|other| other) : U5);
+    ($wide:ty => $narrow:ty { i12 $t:ident :: $meth:ident => $op:expr; $($tt:tt)* }) => {
+        forward_mathw!(@wrap $wide => $narrow, $t :: $meth => ($op) (|other| other) : U12);
+        forward_mathw!($wide => $narrow { $($tt)* });
     };
-    ($t:ident :: $meth:ident => $meth32:expr) => {
-        forward_mathw!(@wrap $t :: $meth => ($meth32) (|other| other as u32) : Self);
+    ($wide:ty => $narrow:ty { i7 $t:ident :: $meth:ident => $op:expr; $($tt:tt)* }) => {
+        forward_mathw!(@wrap $wide => $narrow, $t :: $meth => ($op) (|other| other) : U7);
+        forward_mathw!($wide => $narrow { $($tt)* });
     };
-    (i12 $t:ident :: $meth:ident => $meth32:expr; $($tt:tt)*) => {
-        forward_mathw!(i12 $t :: $meth => $meth32);
-        forward_mathw!($($tt)*);
-    };
-    (i5 $t:ident :: $meth:ident => $meth32:expr; $($tt:tt)*) => {
-        forward_mathw!(i5 $t :: $meth => $meth32);
-        forward_mathw!($($tt)*);
+    ($wide:ty => $narrow:ty { unary $t:ident :: $meth:ident => $op:expr; $($tt:tt)* }) => {
+        impl $t for $wide {
+            #[inline(always)]
+            fn $meth(self) -> Self {
+                $op(self as $narrow) as <$narrow as Unsigned>::Signed
+                    as <$wide as Unsigned>::Signed as $wide
+            }
+        }
+        forward_mathw!($wide => $narrow { $($tt)* });
     };
-    ($t:ident :: $meth:ident => $meth32:expr; $($tt:tt)*) => {
-        forward_mathw!($t :: $meth => $meth32);
-        forward_mathw!($($tt)*);
+    ($wide:ty => $narrow:ty { $t:ident :: $meth:ident => $op:expr; $($tt:tt)* }) => {
+        forward_mathw!(@wrap $wide => $narrow, $t :: $meth => ($op) (|other| other as $narrow) : Self);
+        forward_mathw!($wide => $narrow { $($tt)* });
     };
 }
 
-forward_mathw! {
+forward_mathw!(u64 => u32 {
         Addw::addw => Add::add;
         Subw::subw => Sub::sub;
         Sllw::sllw => Sll::sll;
         Srlw::srlw => Srl::srl;
         Sraw::sraw => Sra::sra;
+        Mulw::mulw => Mul::mul;
+        Divw::divw => Div::div;
+        Divuw::divuw => Divu::divu;
+        Remw::remw => Rem::rem;
+        Remuw::remuw => Remu::remu;
+        Rolw::rolw => Rol::rol;
+        Rorw::rorw => Ror::ror;
     i12 Addiw::addiw => Addi::addi;
-    i5  Slliw::slliw => Slli::slli;
-    i5  Srliw::srliw => Srli::srli;
-    i5  Sraiw::sraiw => Srai::srai;
-}
+    i7  Slliw::slliw => Slli::slli;
+    i7  Srliw::srliw => Srli::srli;
+    i7  Sraiw::sraiw => Srai::srai;
+    i7  Roriw::roriw => Rori::rori;
+  unary Clzw::clzw => Clz::clz;
+  unary Ctzw::ctzw => Ctz::ctz;
+  unary Cpopw::cpopw => Cpop::cpop;
+});
+
+forward_mathw!(u128 => u32 {
+        Addw::addw => Add::add;
+        Subw::subw => Sub::sub;
+        Sllw::sllw => Sll::sll;
+        Srlw::srlw => Srl::srl;
+        Sraw::sraw => Sra::sra;
+    i12 Addiw::addiw => Addi::addi;
+    i7  Slliw::slliw => Slli::slli;
+    i7  Srliw::srliw => Srli::srli;
+    i7  Sraiw::sraiw => Srai::srai;
+});
+
+forward_mathw!(u128 => u64 {
+        Addd::addd => Add::add;
+        Subd::subd => Sub::sub;
+        Slld::slld => Sll::sll;
+        Srld::srld => Srl::srl;
+        Srad::srad => Sra::sra;
+    i12 Addid::addid => Addi::addi;
+    i7  Sllid::sllid => Slli::slli;
+    i7  Srlid::srlid => Srli::srli;
+    i7  Sraid::sraid => Srai::srai;
+});
 
 impl MathW for u64 {}
+
+impl MathW for u128 {}
+
+impl MathD for u128 {}
+
+impl AddUw for u64 {
+    #[inline(always)]
+    fn add_uw(self, other: Self) -> Self {
+        other.wrapping_add(self as u32 as u64)
+    }
+}
+
+impl Sh1addUw for u64 {
+    #[inline(always)]
+    fn sh1add_uw(self, other: Self) -> Self {
+        other.wrapping_add((self as u32 as u64) << 1)
+    }
+}
+
+impl Sh2addUw for u64 {
+    #[inline(always)]
+    fn sh2add_uw(self, other: Self) -> Self {
+        other.wrapping_add((self as u32 as u64) << 2)
+    }
+}
+
+impl Sh3addUw for u64 {
+    #[inline(always)]
+    fn sh3add_uw(self, other: Self) -> Self {
+        other.wrapping_add((self as u32 as u64) << 3)
+    }
+}
+
+impl SlliUw for u64 {
+    #[inline(always)]
+    fn slli_uw(self, other: U7) -> Self {
+        (self as u32 as u64) << (other.as_u32() & 0b111111)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mulh() {
+        assert_eq!(Mulh::mulh(-2i32 as u32, 3), u32::MAX);
+        assert_eq!(Mulh::mulh(i64::MIN as u64, i64::MIN as u64), 1 << 62);
+        assert_eq!(Mulhsu::mulhsu(-1i32 as u32, u32::MAX), u32::MAX);
+        assert_eq!(Mulhsu::mulhsu(i64::MIN as u64, u64::MAX), i64::MIN as u64);
+        assert_eq!(Mulhu::mulhu(u32::MAX, u32::MAX), u32::MAX - 1);
+        assert_eq!(Mulhu::mulhu(u64::MAX, 2), 1);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(Div::div(42u32, 0), u32::MAX);
+        assert_eq!(Divu::divu(42u64, 0), u64::MAX);
+        assert_eq!(Rem::rem(-42i32 as u32, 0), -42i32 as u32);
+        assert_eq!(Remu::remu(42u64, 0), 42);
+        assert_eq!(Divw::divw(42u64, 1 << 32), u64::MAX);
+        assert_eq!(Remuw::remuw(0x8000_0000u64, 0), 0xffff_ffff_8000_0000);
+    }
+
+    #[test]
+    fn division_overflow() {
+        assert_eq!(Div::div(i32::MIN as u32, -1i32 as u32), i32::MIN as u32);
+        assert_eq!(Rem::rem(i64::MIN as u64, -1i64 as u64), 0);
+        assert_eq!(
+            Divw::divw(i32::MIN as u64, u64::MAX),
+            i32::MIN as i64 as u64
+        );
+        assert_eq!(Remw::remw(i32::MIN as u64, u64::MAX), 0);
+    }
+
+    #[test]
+    fn zbb() {
+        assert_eq!(Clz::clz(1u32), 31);
+        assert_eq!(Clz::clz(0u64), 64);
+        assert_eq!(Ctz::ctz(0u32), 32);
+        assert_eq!(Cpop::cpop(u64::MAX), 64);
+        assert_eq!(SextB::sext_b(0x80u32), 0xffff_ff80);
+        assert_eq!(SextH::sext_h(0x7fffu64), 0x7fff);
+        assert_eq!(ZextH::zext_h(u32::MAX), 0xffff);
+        assert_eq!(Rol::rol(0x8000_0001u32, 33), 0x0000_0003);
+        assert_eq!(Ror::ror(1u64, 1), 1 << 63);
+        assert_eq!(OrcB::orc_b(0x0010_2000u32), 0x00ff_ff00);
+        assert_eq!(Rev8::rev8(0x0102_0304u32), 0x0403_0201);
+        assert_eq!(Andn::andn(0b1100u32, 0b1010), 0b0100);
+        assert_eq!(Xnor::xnor(0u64, 0), u64::MAX);
+        assert_eq!(Clzw::clzw(0xffff_ffff_0000_0001u64), 31);
+        assert_eq!(Rorw::rorw(1u64, 1), 0xffff_ffff_8000_0000);
+    }
+
+    #[test]
+    fn zbc() {
+        assert_eq!(Clmul::clmul(0b101u32, 0b11), 0b1111);
+        assert_eq!(Clmulh::clmulh(u32::MAX, u32::MAX), 0x5555_5555);
+        assert_eq!(Clmul::clmul(u32::MAX, u32::MAX), 0x5555_5555);
+        assert_eq!(Clmulr::clmulr(1u64 << 63, 1u64 << 63), 1 << 63);
+        assert_eq!(Clmulh::clmulh(1u64 << 63, 2), 1);
+    }
+
+    #[test]
+    fn zba_zbs() {
+        assert_eq!(Sh3add::sh3add(2u32, 1), 17);
+        assert_eq!(AddUw::add_uw(0xffff_ffff_0000_0001u64, 1), 2);
+        assert_eq!(Sh2addUw::sh2add_uw(u64::MAX, 0), 0x3_ffff_fffc);
+        assert_eq!(
+            SlliUw::slli_uw(u64::MAX, U7::new_truncate(32)),
+            u64::MAX << 32
+        );
+        assert_eq!(Bset::bset(0u32, 33), 2);
+        assert_eq!(Bclr::bclr(u64::MAX, 63), u64::MAX >> 1);
+        assert_eq!(Binv::binv(1u64, 0), 0);
+        assert_eq!(Bext::bext(0x8000_0000u32, 31), 1);
+        assert_eq!(Bexti::bexti(1u64 << 40, U7::new_truncate(40)), 1);
+    }
+
+    #[test]
+    fn zicond() {
+        assert_eq!(CzeroEqz::czero_eqz(42u32, 0), 0);
+        assert_eq!(CzeroEqz::czero_eqz(42u64, 1 << 63), 42);
+        assert_eq!(CzeroNez::czero_nez(42u32, 0), 42);
+        assert_eq!(CzeroNez::czero_nez(42u64, 1), 0);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    mem::{I16, I32, U16, U32, U64},
+    mem::{I16, I32, I64, U128, U16, U32, U64},
     num::{As, Bitcast, Unsigned},
 };
 
@@ -28,7 +28,15 @@ pub trait Lwu {
 }
 
 pub trait Ld {
-    fn ld(value: U64) -> Self;
+    fn ld(value: I64) -> Self;
+}
+
+pub trait Ldu {
+    fn ldu(value: U64) -> Self;
+}
+
+pub trait Lq {
+    fn lq(value: U128) -> Self;
 }
 
 pub trait Sb {
@@ -47,6 +55,10 @@ pub trait Sd {
     fn sd(self) -> U64;
 }
 
+pub trait Sq {
+    fn sq(self) -> U128;
+}
+
 pub trait Imm {
     fn imm(value: u32) -> Self;
 }
@@ -116,15 +128,36 @@ where
 }
 
 impl<T> Ld for T
+where
+    T: Unsigned,
+    <T as Unsigned>::Signed: From<i64>,
+{
+    #[inline(always)]
+    fn ld(value: I64) -> Self {
+        Bitcast::bitcast(<<T as Unsigned>::Signed as From<i64>>::from(value.as_i64()))
+    }
+}
+
+impl<T> Ldu for T
 where
     T: From<u64>,
 {
     #[inline(always)]
-    fn ld(value: U64) -> Self {
+    fn ldu(value: U64) -> Self {
         value.as_u64().into()
     }
 }
 
+impl<T> Lq for T
+where
+    T: From<u128>,
+{
+    #[inline(always)]
+    fn lq(value: U128) -> Self {
+        value.as_u128().into()
+    }
+}
+
 impl<T> Sb for T
 where
     T: As<u8>,
@@ -165,6 +198,16 @@ where
     }
 }
 
+impl<T> Sq for T
+where
+    T: As<u128>,
+{
+    #[inline(always)]
+    fn sq(self) -> U128 {
+        U128::new(self.r#as())
+    }
+}
+
 impl Imm for u32 {
     #[inline(always)]
     fn imm(value: u32) -> Self {
@@ -175,6 +218,13 @@ impl Imm for u32 {
 impl Imm for u64 {
     #[inline(always)]
     fn imm(value: u32) -> Self {
-        unsafe { core::mem::transmute(core::mem::transmute::<_, i32>(value) as i64) }
+        value as i32 as i64 as u64
+    }
+}
+
+impl Imm for u128 {
+    #[inline(always)]
+    fn imm(value: u32) -> Self {
+        value as i32 as i128 as u128
     }
 }
@@ -1,7 +1,9 @@
 mod branch;
+mod crypto;
 mod math;
 mod mem;
 
 pub use branch::*;
+pub use crypto::*;
 pub use math::*;
 pub use mem::*;
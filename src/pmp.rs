@@ -0,0 +1,370 @@
+use crate::{hart::Privilege, mmu::Access};
+
+/// Machine mode lockdown, rule locking bypass and whitelist policy bits of `mseccfg` (Smepmp).
+pub const MML: u64 = 1 << 0;
+pub const MMWP: u64 = 1 << 1;
+pub const RLB: u64 = 1 << 2;
+
+/// Largest number of PMP entries, the CSRs of the unimplemented ones read as zero.
+pub const MAX_ENTRIES: usize = 64;
+
+const R: u8 = 1 << 0;
+const W: u8 = 1 << 1;
+const X: u8 = 1 << 2;
+const A: u8 = 0b11 << 3;
+const L: u8 = 1 << 7;
+
+// address-matching modes of the A field
+const OFF: u8 = 0;
+const TOR: u8 = 1;
+const NA4: u8 = 2;
+
+/// Physical memory protection entries, their `pmpcfg` byte and `pmpaddr` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pmp {
+    entries: usize,
+    cfg: [u8; MAX_ENTRIES],
+    addr: [u64; MAX_ENTRIES],
+    /// Smepmp bits of `mseccfg`.
+    mseccfg: u64,
+}
+
+impl Pmp {
+    /// No PMP entries, all the accesses are allowed.
+    pub const NONE: Self = Self::new(0);
+
+    /// `entries` PMP entries, all disabled after reset.
+    #[inline]
+    pub const fn new(entries: usize) -> Self {
+        assert!(entries <= MAX_ENTRIES, "too many PMP entries");
+        Self {
+            entries,
+            cfg: [0; MAX_ENTRIES],
+            addr: [0; MAX_ENTRIES],
+            mseccfg: 0,
+        }
+    }
+
+    #[inline]
+    fn locked(&self, i: usize) -> bool {
+        self.cfg[i] & L != 0 && self.mseccfg & RLB == 0
+    }
+
+    #[inline]
+    pub fn cfg(&self, i: usize) -> u8 {
+        if i < self.entries {
+            self.cfg[i]
+        } else {
+            0
+        }
+    }
+
+    #[inline]
+    pub fn addr(&self, i: usize) -> u64 {
+        if i < self.entries {
+            self.addr[i]
+        } else {
+            0
+        }
+    }
+
+    /// Writes the configuration of entry `i`, ignored when it is locked. The reserved bits are
+    /// cleared, as is W without R outside of Smepmp lockdown. In lockdown, new executable M-mode
+    /// or locked shared rules need RLB.
+    pub fn set_cfg(&mut self, i: usize, cfg: u8) {
+        if i >= self.entries || self.locked(i) {
+            return;
+        }
+        let mut cfg = cfg & (L | A | X | W | R);
+        if self.mseccfg & MML == 0 {
+            if cfg & (R | W) == W {
+                cfg &= !W;
+            }
+        } else if self.mseccfg & RLB == 0
+            && cfg & L != 0
+            && ((cfg & X != 0 && cfg & (R | W) != R | W) || cfg & (R | W) == W)
+        {
+            return;
+        }
+        self.cfg[i] = cfg;
+    }
+
+    /// Writes the address of entry `i`, ignored when it is locked or the top of a locked TOR
+    /// region.
+    pub fn set_addr(&mut self, i: usize, addr: u64) {
+        let top_of_locked =
+            i + 1 < self.entries && self.locked(i + 1) && (self.cfg[i + 1] & A) >> 3 == TOR;
+        if i < self.entries && !self.locked(i) && !top_of_locked {
+            self.addr[i] = addr;
+        }
+    }
+
+    #[inline]
+    pub fn mseccfg(&self) -> u64 {
+        self.mseccfg
+    }
+
+    /// MML and MMWP are sticky, and RLB cannot be set back once an entry is locked.
+    pub fn set_mseccfg(&mut self, value: u64) {
+        let sticky = self.mseccfg & (MML | MMWP);
+        let mut rlb = value & RLB;
+        if self.mseccfg & RLB == 0 && self.cfg[..self.entries].iter().any(|cfg| cfg & L != 0) {
+            rlb = 0;
+        }
+        self.mseccfg = sticky | (value & (MML | MMWP)) | rlb;
+    }
+
+    /// Bytes matched by entry `i`, empty when it is off.
+    fn range(&self, i: usize) -> core::ops::Range<u128> {
+        let addr = self.addr[i] as u128;
+        match (self.cfg[i] & A) >> 3 {
+            OFF => 0..0,
+            TOR => {
+                let bottom = if i == 0 { 0 } else { self.addr[i - 1] as u128 };
+                bottom << 2..addr << 2
+            }
+            NA4 => addr << 2..(addr << 2) + 4,
+            // NAPOT, the trailing ones of the address encode the size
+            _ => {
+                let size = 1u128 << (self.addr[i].trailing_ones() + 3);
+                let base = (addr << 2) & !(size - 1);
+                base..base + size
+            }
+        }
+    }
+
+    /// Permissions of a matching rule for an access performed with `privilege`.
+    fn permissions(&self, cfg: u8, privilege: Privilege) -> u8 {
+        let machine = privilege == Privilege::Machine;
+        let rwx = cfg & (R | W | X);
+        if self.mseccfg & MML == 0 {
+            return if machine && cfg & L == 0 {
+                R | W | X
+            } else {
+                rwx
+            };
+        }
+        // the Smepmp lockdown truth table, W without R encodes the shared regions
+        let shared = rwx & (R | W) == W;
+        match (cfg & L != 0, machine) {
+            (false, true) if shared => R | W,
+            // shared data, only writable from S-mode and U-mode when X is set
+            (false, false) if shared && rwx & X != 0 => R | W,
+            (false, false) if shared => R,
+            (false, true) => 0,
+            (false, false) => rwx,
+            // shared code, M-mode can also read it when X is set
+            (true, true) if shared && rwx & X != 0 => R | X,
+            (true, _) if shared => X,
+            // shared read-only data
+            (true, _) if rwx == R | W | X => R,
+            (true, true) => rwx,
+            (true, false) => 0,
+        }
+    }
+
+    /// Whether the `size` bytes wide access at the physical address `addr`, performed with
+    /// `privilege`, is allowed. The lowest-numbered entry matching any of its bytes decides,
+    /// and it fails when that entry does not hold all of them.
+    pub fn allows(&self, addr: usize, size: usize, access: Access, privilege: Privilege) -> bool {
+        let required = match access {
+            Access::Fetch => X,
+            Access::Load => R,
+            Access::Store => W,
+            Access::ShadowStack => R | W,
+        };
+        let bytes = addr as u128..addr as u128 + size as u128;
+        for i in 0..self.entries {
+            let range = self.range(i);
+            if range.is_empty() || bytes.end <= range.start || range.end <= bytes.start {
+                continue;
+            }
+            return range.start <= bytes.start
+                && bytes.end <= range.end
+                && self.permissions(self.cfg[i], privilege) & required == required;
+        }
+        match privilege {
+            Privilege::Machine if self.mseccfg & MMWP != 0 => false,
+            // in lockdown, M-mode only executes from the regions of a rule
+            Privilege::Machine => self.mseccfg & MML == 0 || access != Access::Fetch,
+            _ => self.entries == 0,
+        }
+    }
+}
+
+impl Default for Pmp {
+    #[inline]
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{csr, decode::U12, error::Error, hart::Hart, mmu::Mmu};
+
+    const A_TOR: u8 = TOR << 3;
+    const A_NA4: u8 = NA4 << 3;
+    const A_NAPOT: u8 = 0b11 << 3;
+
+    #[test]
+    fn matching() {
+        let mut pmp = Pmp::new(16);
+        // [0x1000, 0x2000) read-write, [0x2000, 0x2004) execute-only and [0x4000, 0x6000)
+        // read-only
+        pmp.set_addr(0, 0x1000 >> 2);
+        pmp.set_addr(1, 0x2000 >> 2);
+        pmp.set_addr(2, 0x2000 >> 2);
+        pmp.set_addr(3, (0x4000 >> 2) | 0x3ff);
+        pmp.set_addr(4, 0x4000 >> 2);
+        pmp.set_cfg(1, A_TOR | R | W);
+        pmp.set_cfg(2, A_NA4 | X);
+        pmp.set_cfg(3, A_NAPOT | R);
+        pmp.set_cfg(4, A_NAPOT | R | W | X);
+
+        let user = |addr, size, access| pmp.allows(addr, size, access, Privilege::User);
+        assert!(user(0x1000, 8, Access::Store));
+        assert!(user(0x1ffc, 4, Access::Load));
+        assert!(!user(0x1000, 4, Access::Fetch));
+        assert!(user(0x2000, 4, Access::Fetch));
+        assert!(!user(0x2000, 4, Access::Load));
+        assert!(user(0x5ff8, 8, Access::Load));
+        // the lowest-numbered entry decides, even if a later one allows the access
+        assert!(!user(0x4000, 4, Access::Store));
+        // the access must be held by a single entry
+        assert!(!user(0x1ffc, 8, Access::Load));
+        // S-mode and U-mode accesses matching no entry fail, M-mode ones succeed
+        assert!(!user(0x8000, 4, Access::Load));
+        assert!(!pmp.allows(0, 4, Access::Load, Privilege::Supervisor));
+        assert!(pmp.allows(0x8000, 4, Access::Fetch, Privilege::Machine));
+        assert!(pmp.allows(0x2000, 4, Access::Store, Privilege::Machine));
+
+        // W without R is reserved
+        pmp.set_cfg(5, A_NA4 | W);
+        assert_eq!(pmp.cfg(5), A_NA4);
+        // no entries, no protection
+        assert!(Pmp::NONE.allows(0x8000, 4, Access::Load, Privilege::User));
+    }
+
+    #[test]
+    fn locking() {
+        let mut pmp = Pmp::new(16);
+        pmp.set_addr(0, 0x1000 >> 2);
+        pmp.set_addr(1, 0x2000 >> 2);
+        pmp.set_cfg(1, L | A_TOR | R | X);
+
+        // locked entries also apply to M-mode
+        assert!(pmp.allows(0x1000, 4, Access::Load, Privilege::Machine));
+        assert!(!pmp.allows(0x1000, 4, Access::Store, Privilege::Machine));
+        // and can neither be reconfigured nor moved, not even from below
+        pmp.set_cfg(1, A_TOR | R | W | X);
+        pmp.set_addr(1, 0x3000 >> 2);
+        pmp.set_addr(0, 0);
+        assert_eq!(pmp.cfg(1), L | A_TOR | R | X);
+        assert_eq!((pmp.addr(0), pmp.addr(1)), (0x1000 >> 2, 0x2000 >> 2));
+        // RLB cannot be set once an entry is locked
+        pmp.set_mseccfg(RLB);
+        assert_eq!(pmp.mseccfg(), 0);
+
+        // unless RLB was set first
+        let mut pmp = Pmp::new(16);
+        pmp.set_mseccfg(RLB);
+        pmp.set_cfg(0, L | A_NA4 | R);
+        pmp.set_cfg(0, A_NA4 | R | W);
+        assert_eq!(pmp.cfg(0), A_NA4 | R | W);
+    }
+
+    #[test]
+    fn lockdown() {
+        let mut pmp = Pmp::new(16);
+        pmp.set_mseccfg(RLB);
+        pmp.set_mseccfg(RLB | MML);
+        for (i, cfg) in [L | R | X, R | W, W, L | W, L | R | W | X]
+            .into_iter()
+            .enumerate()
+        {
+            pmp.set_addr(i, (0x1000 * (i as u64 + 1)) >> 2 | 0x1ff);
+            pmp.set_cfg(i, A_NAPOT | cfg);
+        }
+        let allows = |addr, access, privilege| pmp.allows(addr, 4, access, privilege);
+        let (m, u) = (Privilege::Machine, Privilege::User);
+
+        // M-mode-only code
+        assert!(allows(0x1000, Access::Fetch, m));
+        assert!(!allows(0x1000, Access::Fetch, u));
+        // S/U-mode-only data, M-mode cannot access it
+        assert!(allows(0x2000, Access::Store, u));
+        assert!(!allows(0x2000, Access::Load, m));
+        // shared data, read-only for U-mode
+        assert!(allows(0x3000, Access::Store, m));
+        assert!(allows(0x3000, Access::Load, u));
+        assert!(!allows(0x3000, Access::Store, u));
+        // shared code
+        assert!(allows(0x4000, Access::Fetch, m));
+        assert!(allows(0x4000, Access::Fetch, u));
+        assert!(!allows(0x4000, Access::Load, m));
+        // shared read-only data
+        assert!(allows(0x5000, Access::Load, m));
+        assert!(!allows(0x5000, Access::Fetch, u));
+        // M-mode cannot execute outside of the rules
+        assert!(allows(0x8000, Access::Load, m));
+        assert!(!allows(0x8000, Access::Fetch, m));
+
+        // MML and MMWP are sticky, without RLB new executable M-mode rules are ignored
+        pmp.set_mseccfg(MMWP);
+        assert_eq!(pmp.mseccfg(), MML | MMWP);
+        assert!(!pmp.allows(0x8000, 4, Access::Load, m));
+        pmp.set_cfg(6, L | A_NA4 | X);
+        assert_eq!(pmp.cfg(6), 0);
+        pmp.set_cfg(6, L | A_NA4 | R);
+        assert_eq!(pmp.cfg(6), L | A_NA4 | R);
+    }
+
+    #[test]
+    fn csrs() {
+        let mut hart = Hart::<u32>::default().with_pmp(16);
+        csr::write(&mut hart, csr::PMPADDR0, 0x800).unwrap();
+        csr::write(&mut hart, csr::PMPCFG0, 0x6000_0f00 | (A_TOR | R) as u32).unwrap();
+        // the reserved bits are cleared
+        assert_eq!(
+            csr::read(&mut hart, csr::PMPCFG0),
+            Ok(0x0000_0f00 | (A_TOR | R) as u32)
+        );
+        assert_eq!(csr::read(&mut hart, csr::PMPADDR0), Ok(0x800));
+        // the entries past the 16th read as zero
+        let pmpcfg4 = U12::new_truncate(csr::PMPCFG0.as_u16() + 4);
+        csr::write(&mut hart, pmpcfg4, u32::MAX).unwrap();
+        assert_eq!(csr::read(&mut hart, pmpcfg4), Ok(0));
+
+        // S-mode and U-mode accesses are checked once translated
+        hart.privilege = Privilege::User;
+        let mmu = Mmu::data(&hart);
+        let memory = [0u8; 0x4000];
+        assert_eq!(mmu.translate(0x1ffc, 4, Access::Load, &memory), Ok(0x1ffc));
+        assert_eq!(
+            mmu.translate(0x1ffc, 4, Access::Store, &memory),
+            Err(Error::StoreAccessFault(0x1ffc))
+        );
+        assert_eq!(
+            mmu.translate(0x2000, 4, Access::Load, &memory),
+            Err(Error::LoadAccessFault(0x2000))
+        );
+
+        // there are no odd pmpcfg on RV64, and the even ones hold 8 entries
+        let mut hart = Hart::<u64>::default().with_pmp(64);
+        let pmpcfg1 = U12::new_truncate(csr::PMPCFG0.as_u16() + 1);
+        assert!(hart.csrs.check(pmpcfg1, Privilege::Machine, true).is_err());
+        let pmpcfg14 = U12::new_truncate(csr::PMPCFG0.as_u16() + 14);
+        csr::write(&mut hart, pmpcfg14, (R as u64) << 56).unwrap();
+        assert_eq!(hart.pmp.cfg(63), R);
+        let pmpaddr63 = U12::new_truncate(csr::PMPADDR0.as_u16() + 63);
+        csr::write(&mut hart, pmpaddr63, u64::MAX).unwrap();
+        assert_eq!(csr::read(&mut hart, pmpaddr63), Ok((1 << 54) - 1));
+
+        // mseccfg legalizes its Smepmp bits
+        csr::write(&mut hart, csr::MSECCFG, MML).unwrap();
+        csr::write(&mut hart, csr::MSECCFG, 0).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MSECCFG), Ok(MML));
+    }
+}
@@ -0,0 +1,117 @@
+use core::ops::{Shl, Shr};
+
+use crate::{
+    csr,
+    decode::U12,
+    hart::{Hart, Privilege},
+    num::As,
+};
+
+/// Pointer masking mode field of `mseccfg`, `menvcfg` and `senvcfg`, it only exists on RV64.
+pub const PMM: u64 = 0b11 << 32;
+
+/// Ignored high bits of the effective address of the integer loads and stores, selected by the
+/// Smmpm, Smnpm and Ssnpm extensions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PointerMask {
+    pmlen: u32,
+}
+
+impl PointerMask {
+    pub const NONE: Self = Self { pmlen: 0 };
+
+    /// Decodes a PMM field, the reserved encoding disables masking.
+    #[inline]
+    pub const fn from_pmm(pmm: u64) -> Self {
+        let pmlen = match (pmm & PMM) >> 32 {
+            0b10 => 7,
+            0b11 => 16,
+            _ => 0,
+        };
+        Self { pmlen }
+    }
+
+    /// Clears the top PMLEN bits of `addr`, physical addresses are zero-extended.
+    #[inline(always)]
+    pub fn apply<T>(self, addr: T) -> T
+    where
+        T: Shl<u32, Output = T> + Shr<u32, Output = T>,
+    {
+        (addr << self.pmlen) >> self.pmlen
+    }
+
+    /// Replaces the top PMLEN bits of `addr` by the sign of the remaining ones, as virtual
+    /// addresses are.
+    #[inline(always)]
+    pub fn apply_signed(self, addr: u64) -> u64 {
+        (((addr << self.pmlen) as i64) >> self.pmlen) as u64
+    }
+}
+
+#[inline(always)]
+fn csr_bits<T: Copy + As<u64>>(hart: &Hart<T>, addr: U12) -> u64 {
+    hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as())
+}
+
+/// Pointer masking of the accesses performed with `privilege`: `mseccfg` selects it for M-mode,
+/// `menvcfg` for S-mode and `senvcfg` for U-mode. Instruction fetches are never masked.
+pub fn pointer_mask<T: Copy + As<u64>>(hart: &Hart<T>, privilege: Privilege) -> PointerMask {
+    PointerMask::from_pmm(match privilege {
+        Privilege::Machine => csr_bits(hart, csr::MSECCFG),
+        Privilege::Supervisor => csr_bits(hart, csr::MENVCFG),
+        Privilege::User => csr_bits(hart, csr::SENVCFG),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masking() {
+        let addr = 0xabcd_ef01_2345_6789_u64;
+        assert_eq!(PointerMask::NONE.apply(addr), addr);
+        assert_eq!(
+            PointerMask::from_pmm(0b10 << 32).apply(addr),
+            0x01cd_ef01_2345_6789
+        );
+        assert_eq!(
+            PointerMask::from_pmm(0b11 << 32).apply(addr),
+            0x0000_ef01_2345_6789
+        );
+        assert_eq!(PointerMask::from_pmm(0b01 << 32), PointerMask::NONE);
+        assert_eq!(
+            PointerMask::from_pmm(0b11 << 32).apply_signed(addr),
+            0xffff_ef01_2345_6789
+        );
+    }
+
+    #[test]
+    fn per_privilege() {
+        let mut hart = Hart::<u64>::default();
+        assert_eq!(pointer_mask(&hart, hart.privilege), PointerMask::NONE);
+
+        csr::write(&mut hart, csr::MSECCFG, 0b11 << 32).unwrap();
+        csr::write(&mut hart, csr::SENVCFG, 0b10 << 32).unwrap();
+        assert_eq!(
+            pointer_mask(&hart, hart.privilege),
+            PointerMask { pmlen: 16 }
+        );
+        hart.privilege = Privilege::Supervisor;
+        assert_eq!(pointer_mask(&hart, hart.privilege), PointerMask::NONE);
+        hart.privilege = Privilege::User;
+        assert_eq!(
+            pointer_mask(&hart, hart.privilege),
+            PointerMask { pmlen: 7 }
+        );
+
+        // PMM is WARL, the reserved encoding reads back as disabled
+        csr::write(&mut hart, csr::MENVCFG, (0b01 << 32) | 0b1000).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MENVCFG), Ok(0b1000));
+
+        // RV32 has no pointer masking
+        let mut hart = Hart::<u32>::default();
+        csr::write(&mut hart, csr::MSECCFG, u32::MAX).unwrap();
+        assert_eq!(pointer_mask(&hart, hart.privilege), PointerMask::NONE);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{decode::U5, num::Zero};
+use crate::{decode::U5, error::Error, num::Zero};
 
 #[allow(dead_code)]
 #[repr(u8)]
@@ -43,20 +43,49 @@ pub enum ZeroOrRegister {
     Register(Register),
 }
 
+/// Integer register file, `len` is the number of registers above `x0`: 31, or 15 for the E base.
+#[derive(Debug)]
+pub struct Registers<T> {
+    regs: [T; 31],
+    len: u8,
+}
+
+/// Floating-point register file, values narrower than 64 bits are stored NaN-boxed.
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct Registers<T>([T; 31]);
+pub struct FRegisters([u64; 32]);
+
+/// Vector register file, the 32 registers are stored back to back so that register groups are
+/// contiguous, elements are little-endian.
+#[derive(Debug, Clone)]
+pub struct VRegisters {
+    bytes: Vec<u8>,
+    vlenb: usize,
+}
 
-const _: [(); 0] = [(); ((Register::X31 as usize + 1) * core::mem::size_of::<u32>())
+// The length takes the slot left by x0.
+const _: [(); 0] = [(); ((Register::X31 as usize + 2) * core::mem::size_of::<u32>())
     - core::mem::size_of::<Registers<u32>>()];
 
-const _: [(); 0] = [(); ((Register::X31 as usize + 1) * core::mem::size_of::<u64>())
+const _: [(); 0] = [(); ((Register::X31 as usize + 2) * core::mem::size_of::<u64>())
     - core::mem::size_of::<Registers<u64>>()];
 
 impl<T: Copy + Default> Registers<T> {
     #[inline(always)]
     pub fn new() -> Self {
-        Self([Default::default(); 31])
+        Self {
+            regs: [Default::default(); 31],
+            len: Register::X31 as u8 + 1,
+        }
+    }
+
+    /// Register file of the RV32E and RV64E bases, with `x1` to `x15` only.
+    #[inline(always)]
+    pub fn embedded() -> Self {
+        Self {
+            len: Register::X15 as u8 + 1,
+            ..Self::new()
+        }
     }
 }
 
@@ -64,13 +93,14 @@ impl<T: Copy> Registers<T> {
     #[inline(always)]
     #[cfg(debug_assertions)]
     pub fn get(&self, reg: Register) -> T {
-        self.0[reg as usize]
+        debug_assert!((reg as u8) < self.len, "register out of the register file");
+        self.regs[reg as usize]
     }
 
     #[inline(always)]
     #[cfg(not(debug_assertions))]
     pub fn get(&self, reg: Register) -> T {
-        unsafe { *self.0.get_unchecked(reg as usize) }
+        unsafe { *self.regs.get_unchecked(reg as usize) }
     }
 }
 
@@ -78,13 +108,102 @@ impl<T> Registers<T> {
     #[inline(always)]
     #[cfg(debug_assertions)]
     pub fn get_mut(&mut self, reg: Register) -> &mut T {
-        &mut self.0[reg as usize]
+        debug_assert!((reg as u8) < self.len, "register out of the register file");
+        &mut self.regs[reg as usize]
     }
 
     #[inline(always)]
     #[cfg(not(debug_assertions))]
     pub fn get_mut(&mut self, reg: Register) -> &mut T {
-        unsafe { self.0.get_unchecked_mut(reg as usize) }
+        unsafe { self.regs.get_unchecked_mut(reg as usize) }
+    }
+
+    /// Decodes a register field, naming a register outside of the register file is illegal.
+    #[inline(always)]
+    pub const fn decode(&self, value: U5) -> Result<ZeroOrRegister, Error> {
+        if value.as_u8() > self.len {
+            Err(Error::InvalidOpCode)
+        } else {
+            Ok(ZeroOrRegister::from_u5(value))
+        }
+    }
+}
+
+impl FRegisters {
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self([0; 32])
+    }
+
+    #[inline(always)]
+    pub const fn get(&self, reg: U5) -> u64 {
+        self.0[reg.as_u8() as usize]
+    }
+
+    #[inline(always)]
+    pub fn get_mut(&mut self, reg: U5) -> &mut u64 {
+        &mut self.0[reg.as_u8() as usize]
+    }
+}
+
+impl VRegisters {
+    /// Register file of `vlen` bits registers.
+    #[inline]
+    pub fn new(vlen: usize) -> Self {
+        let vlenb = vlen / 8;
+        Self {
+            bytes: vec![0; vlenb * 32],
+            vlenb,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn vlenb(&self) -> usize {
+        self.vlenb
+    }
+
+    /// Byte range of element `index` of `eew` bits in the group starting at `reg`.
+    #[inline(always)]
+    fn range(&self, reg: u8, index: usize, eew: u32) -> core::ops::Range<usize> {
+        let size = (eew / 8) as usize;
+        let start = reg as usize * self.vlenb + index * size;
+        start..start + size
+    }
+
+    /// Reads element `index` of `eew` bits, zero-extended, of the group starting at `reg`.
+    #[inline]
+    pub fn get(&self, reg: u8, index: usize, eew: u32) -> u64 {
+        let range = self.range(reg, index, eew);
+        let mut bytes = [0; 8];
+        bytes[..range.len()].copy_from_slice(&self.bytes[range]);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Writes the low `eew` bits of `value` to element `index` of the group starting at `reg`.
+    #[inline]
+    pub fn set(&mut self, reg: u8, index: usize, eew: u32, value: u64) {
+        let range = self.range(reg, index, eew);
+        let len = range.len();
+        self.bytes[range].copy_from_slice(&value.to_le_bytes()[..len]);
+    }
+
+    /// Bit `index` of the mask held in `reg`.
+    #[inline]
+    pub fn mask(&self, reg: u8, index: usize) -> bool {
+        (self.bytes[reg as usize * self.vlenb + index / 8] >> (index % 8)) & 1 != 0
+    }
+
+    #[inline]
+    pub fn set_mask(&mut self, reg: u8, index: usize, value: bool) {
+        let byte = &mut self.bytes[reg as usize * self.vlenb + index / 8];
+        *byte = (*byte & !(1 << (index % 8))) | ((value as u8) << (index % 8));
+    }
+}
+
+impl Default for FRegisters {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -130,7 +249,7 @@ impl ZeroOrRegister {
         if raw == 0 {
             Self::Zero
         } else {
-            Self::Register(core::mem::transmute(raw.wrapping_sub(1)))
+            Self::Register(core::mem::transmute::<u8, Register>(raw.wrapping_sub(1)))
         }
     }
 
@@ -185,6 +304,33 @@ mod tests {
         assert_eq!(20, x1.fetch(&regs));
     }
 
+    #[test]
+    fn test_fregisters() {
+        let mut fregs = FRegisters::default();
+        let f0 = U5::new_truncate(0);
+        *fregs.get_mut(f0) = 20;
+        assert_eq!(fregs.get(f0), 20);
+        assert_eq!(fregs.get(U5::new_truncate(31)), 0);
+    }
+
+    #[test]
+    fn test_vregisters() {
+        let mut vregs = VRegisters::new(128);
+        assert_eq!(vregs.vlenb(), 16);
+        vregs.set(2, 3, 32, 0x1234_5678_9abc);
+        assert_eq!(vregs.get(2, 3, 32), 0x5678_9abc);
+        assert_eq!(vregs.get(2, 6, 16), 0x9abc);
+        // groups continue into the next register
+        vregs.set(2, 4, 32, 0xdead_beef);
+        assert_eq!(vregs.get(3, 0, 32), 0xdead_beef);
+        assert!(vregs.mask(3, 0));
+        vregs.set_mask(0, 9, true);
+        assert!(vregs.mask(0, 9));
+        assert_eq!(vregs.get(0, 1, 8), 0b10);
+        vregs.set_mask(0, 9, false);
+        assert_eq!(vregs.get(0, 0, 64), 0);
+    }
+
     #[test]
     fn test_zero_or_register() {
         let mut regs = Registers::default();
@@ -203,4 +349,18 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_embedded() {
+        let regs = Registers::<u32>::embedded();
+        assert_eq!(
+            regs.decode(U5::new_truncate(15)),
+            Ok(ZeroOrRegister::Register(Register::X15))
+        );
+        assert_eq!(regs.decode(U5::new_truncate(16)), Err(Error::InvalidOpCode));
+        assert_eq!(
+            Registers::<u64>::new().decode(U5::new_truncate(31)),
+            Ok(ZeroOrRegister::Register(Register::X31))
+        );
+    }
 }
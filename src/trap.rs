@@ -0,0 +1,492 @@
+use crate::{
+    cfi, csr,
+    decode::U12,
+    error::Error,
+    hart::{Hart, Privilege},
+    num::As,
+};
+
+/// Supervisor interrupt enable of `mstatus`.
+pub const SIE: u64 = 1 << 1;
+/// Machine interrupt enable of `mstatus`.
+pub const MIE: u64 = 1 << 3;
+/// Interrupt enable of `mstatus` before the last trap into S-mode.
+pub const SPIE: u64 = 1 << 5;
+/// Interrupt enable of `mstatus` before the last trap into M-mode.
+pub const MPIE: u64 = 1 << 7;
+/// Privilege mode of `mstatus` before the last trap into S-mode, set for S-mode.
+pub const SPP: u64 = 1 << 8;
+/// Privilege mode of `mstatus` before the last trap into M-mode.
+pub const MPP: u64 = 0b11 << 11;
+/// Loads and stores of M-mode use the privilege mode in MPP.
+pub const MPRV: u64 = 1 << 17;
+/// S-mode can access the U-mode pages.
+pub const SUM: u64 = 1 << 18;
+/// Loads can read the execute-only pages.
+pub const MXR: u64 = 1 << 19;
+/// Accesses to `satp` and SFENCE.VMA are illegal in S-mode.
+pub const TVM: u64 = 1 << 20;
+/// WFI is illegal in S-mode.
+pub const TW: u64 = 1 << 21;
+/// SRET is illegal in S-mode.
+pub const TSR: u64 = 1 << 22;
+/// Expected landing pad before the last trap into S-mode.
+pub const SPELP: u64 = 1 << 23;
+/// Expected landing pad before the last trap into M-mode, bit 9 of `mstatush` on RV32.
+pub const MPELP: u64 = 1 << 41;
+/// Fields of `mstatus` visible through `sstatus`.
+pub const SSTATUS_MASK: u64 = SIE | SPIE | SPP | SUM | MXR | SPELP;
+
+const INSTRUCTION_ADDRESS_MISALIGNED: u64 = 0;
+const INSTRUCTION_ACCESS_FAULT: u64 = 1;
+const ILLEGAL_INSTRUCTION: u64 = 2;
+const BREAKPOINT: u64 = 3;
+const LOAD_ADDRESS_MISALIGNED: u64 = 4;
+const LOAD_ACCESS_FAULT: u64 = 5;
+const STORE_ADDRESS_MISALIGNED: u64 = 6;
+const STORE_ACCESS_FAULT: u64 = 7;
+/// ECALL from U-mode, the S-mode and M-mode causes follow the privilege encoding.
+const ENVIRONMENT_CALL: u64 = 8;
+const INSTRUCTION_PAGE_FAULT: u64 = 12;
+const LOAD_PAGE_FAULT: u64 = 13;
+const STORE_PAGE_FAULT: u64 = 15;
+const SOFTWARE_CHECK: u64 = 18;
+
+// interrupt causes, their bit in `mip` and `mie` is the same
+pub const SUPERVISOR_SOFTWARE: u64 = 1;
+pub const MACHINE_SOFTWARE: u64 = 3;
+pub const SUPERVISOR_TIMER: u64 = 5;
+pub const MACHINE_TIMER: u64 = 7;
+pub const SUPERVISOR_EXTERNAL: u64 = 9;
+pub const MACHINE_EXTERNAL: u64 = 11;
+/// Interrupts in decreasing priority order.
+const PRIORITY: [u64; 6] = [
+    MACHINE_EXTERNAL,
+    MACHINE_SOFTWARE,
+    MACHINE_TIMER,
+    SUPERVISOR_EXTERNAL,
+    SUPERVISOR_SOFTWARE,
+    SUPERVISOR_TIMER,
+];
+
+#[inline(always)]
+fn csr_bits<T: Copy + As<u64>>(hart: &Hart<T>, addr: U12) -> u64 {
+    hart.csrs.get(addr).map_or(0, |entry| entry.value.r#as())
+}
+
+/// Sets a CSR bypassing its mask, for the fields updated by the hart itself.
+#[inline(always)]
+fn set_csr<T>(hart: &mut Hart<T>, addr: U12, value: u64)
+where
+    u64: As<T>,
+{
+    if let Some(entry) = hart.csrs.get_mut(addr) {
+        entry.value = value.r#as();
+    }
+}
+
+/// `mstatus` with, on RV32, its upper half from `mstatush`.
+#[inline(always)]
+fn mstatus<T: Copy + As<u64>>(hart: &Hart<T>) -> u64 {
+    let mstatus = csr_bits(hart, csr::MSTATUS);
+    if core::mem::size_of::<T>() == 4 {
+        mstatus | csr_bits(hart, csr::MSTATUSH) << 32
+    } else {
+        mstatus
+    }
+}
+
+#[inline(always)]
+fn set_mstatus<T>(hart: &mut Hart<T>, value: u64)
+where
+    u64: As<T>,
+{
+    set_csr(hart, csr::MSTATUS, value);
+    if core::mem::size_of::<T>() == 4 {
+        set_csr(hart, csr::MSTATUSH, value >> 32);
+    }
+}
+
+/// `mcause` and `mtval` of the exception raised by the instruction `encoded`.
+fn exception<T>(hart: &Hart<T>, error: Error, encoded: u32) -> (u64, u64)
+where
+    T: Copy + As<u64>,
+{
+    match error {
+        Error::InvalidOpCode => (ILLEGAL_INSTRUCTION, encoded as u64),
+        Error::InstructionAddressMisaligned(addr) => (INSTRUCTION_ADDRESS_MISALIGNED, addr as u64),
+        Error::InstructionAccessFault(addr) => (INSTRUCTION_ACCESS_FAULT, addr as u64),
+        Error::Breakpoint => (BREAKPOINT, hart.pc.r#as()),
+        Error::LoadAddressMisaligned(addr) => (LOAD_ADDRESS_MISALIGNED, addr as u64),
+        Error::LoadAccessFault(addr) => (LOAD_ACCESS_FAULT, addr as u64),
+        Error::StoreAddressMisaligned(addr) => (STORE_ADDRESS_MISALIGNED, addr as u64),
+        Error::StoreAccessFault(addr) => (STORE_ACCESS_FAULT, addr as u64),
+        Error::EnvironmentCall => (ENVIRONMENT_CALL + hart.privilege as u64, 0),
+        Error::InstructionPageFault(addr) => (INSTRUCTION_PAGE_FAULT, addr as u64),
+        Error::LoadPageFault(addr) => (LOAD_PAGE_FAULT, addr as u64),
+        Error::StorePageFault(addr) => (STORE_PAGE_FAULT, addr as u64),
+        Error::SoftwareCheck(tval) => (SOFTWARE_CHECK, tval as u64),
+    }
+}
+
+/// Delivers the exception raised by the instruction `encoded` at the pc. Exceptions from S-mode
+/// and U-mode whose bit is set in `medeleg` go to S-mode, the others to M-mode: the hart jumps to
+/// the `xtvec` base, vectored mode only offsets interrupts.
+pub fn take<T>(hart: &mut Hart<T>, error: Error, encoded: u32)
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    let (cause, tval) = exception(hart, error, encoded);
+    let delegated =
+        hart.privilege != Privilege::Machine && (csr_bits(hart, csr::MEDELEG) >> cause) & 1 != 0;
+    let xtvec = enter(hart, delegated, cause.r#as(), tval);
+    hart.pc = (xtvec & !0b11).r#as();
+}
+
+/// Takes the pending and enabled interrupt of highest priority, if any, before the instruction
+/// at the pc. Those destined to M-mode go first: they are enabled below M-mode, and in M-mode by
+/// `mstatus.MIE`. The ones delegated by `mideleg` are enabled in U-mode, and in S-mode by
+/// `mstatus.SIE`.
+pub fn interrupt<T>(hart: &mut Hart<T>) -> bool
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+    u128: As<T>,
+{
+    let mstatus = csr_bits(hart, csr::MSTATUS);
+    let pending = csr_bits(hart, csr::MIP) & csr_bits(hart, csr::MIE);
+    let mideleg = csr_bits(hart, csr::MIDELEG);
+    let machine = match hart.privilege {
+        Privilege::Machine if mstatus & MIE == 0 => 0,
+        _ => pending & !mideleg,
+    };
+    let supervisor = match hart.privilege {
+        Privilege::Machine => 0,
+        Privilege::Supervisor if mstatus & SIE == 0 => 0,
+        _ => pending & mideleg,
+    };
+    let (pending, delegated) = match (machine, supervisor) {
+        (0, 0) => return false,
+        (0, supervisor) => (supervisor, true),
+        (machine, _) => (machine, false),
+    };
+    let Some(code) = PRIORITY.into_iter().find(|code| (pending >> code) & 1 != 0) else {
+        return false;
+    };
+
+    let xlen = core::mem::size_of::<T>() * 8;
+    let cause = ((1u128 << (xlen - 1)) | code as u128).r#as();
+    let xtvec = enter(hart, delegated, cause, 0);
+    // vectored mode jumps to the base plus 4 times the cause
+    let offset = if xtvec & 0b11 == 1 { 4 * code } else { 0 };
+    hart.pc = ((xtvec & !0b11) + offset).r#as();
+    true
+}
+
+/// Sets or clears the bit of the interrupt `code` in `mip`, as the interrupt controllers do.
+pub fn set_pending<T>(hart: &mut Hart<T>, code: u64, pending: bool)
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    let mip = csr_bits(hart, csr::MIP) & !(1 << code);
+    set_csr(hart, csr::MIP, mip | (pending as u64) << code);
+}
+
+/// Enters M-mode, or S-mode when `delegated`, saving the state of the trapped privilege mode, and
+/// returns the `xtvec` of the handler. The expected landing pad moves to xPELP, the handler
+/// starts without one.
+fn enter<T>(hart: &mut Hart<T>, delegated: bool, cause: T, tval: u64) -> u64
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    let mstatus = mstatus(hart);
+    let elp = core::mem::take(&mut hart.cfi.elp);
+    let (epc, xcause, xtval, xtvec) = if delegated {
+        let spie = if mstatus & SIE != 0 { SPIE } else { 0 };
+        let spp = if hart.privilege == Privilege::Supervisor {
+            SPP
+        } else {
+            0
+        };
+        let spelp = if elp { SPELP } else { 0 };
+        let mstatus = (mstatus & !(SIE | SPIE | SPP | SPELP)) | spie | spp | spelp;
+        set_mstatus(hart, mstatus);
+        hart.privilege = Privilege::Supervisor;
+        (csr::SEPC, csr::SCAUSE, csr::STVAL, csr::STVEC)
+    } else {
+        let mpie = if mstatus & MIE != 0 { MPIE } else { 0 };
+        let mpp = (hart.privilege as u64) << 11;
+        let mpelp = if elp { MPELP } else { 0 };
+        let mstatus = (mstatus & !(MIE | MPIE | MPP | MPELP)) | mpie | mpp | mpelp;
+        set_mstatus(hart, mstatus);
+        hart.privilege = Privilege::Machine;
+        (csr::MEPC, csr::MCAUSE, csr::MTVAL, csr::MTVEC)
+    };
+    set_csr(hart, epc, hart.pc.r#as());
+    if let Some(entry) = hart.csrs.get_mut(xcause) {
+        entry.value = cause;
+    }
+    set_csr(hart, xtval, tval);
+    csr_bits(hart, xtvec)
+}
+
+/// MRET: restores the privilege mode, interrupt enable and expected landing pad saved in
+/// `mstatus` and returns to `mepc`, MPP is left to the least privileged mode. The landing pad is
+/// only expected when landing pads are enforced in the restored mode.
+pub fn mret<T>(hart: &mut Hart<T>) -> Result<(), Error>
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    if hart.privilege != Privilege::Machine {
+        return Err(Error::InvalidOpCode);
+    }
+    let mstatus = mstatus(hart);
+    let privilege = match (mstatus & MPP) >> 11 {
+        0b11 => Privilege::Machine,
+        0b01 => Privilege::Supervisor,
+        _ => Privilege::User,
+    };
+    let mie = if mstatus & MPIE != 0 { MIE } else { 0 };
+    let mut restored = (mstatus & !(MIE | MPP | MPELP)) | mie | MPIE;
+    if privilege != Privilege::Machine {
+        restored &= !MPRV;
+    }
+    set_mstatus(hart, restored);
+    hart.privilege = privilege;
+    hart.cfi.elp = mstatus & MPELP != 0 && cfi::landing_pads(hart);
+    hart.pc = csr_bits(hart, csr::MEPC).r#as();
+    Ok(())
+}
+
+/// SRET: the S-mode counterpart of MRET, illegal in U-mode and, when TSR is set, in S-mode.
+pub fn sret<T>(hart: &mut Hart<T>) -> Result<(), Error>
+where
+    T: Copy + As<u64>,
+    u64: As<T>,
+{
+    let mstatus = mstatus(hart);
+    match hart.privilege {
+        Privilege::User => return Err(Error::InvalidOpCode),
+        Privilege::Supervisor if mstatus & TSR != 0 => return Err(Error::InvalidOpCode),
+        _ => {}
+    }
+    let privilege = if mstatus & SPP != 0 {
+        Privilege::Supervisor
+    } else {
+        Privilege::User
+    };
+    let sie = if mstatus & SPIE != 0 { SIE } else { 0 };
+    set_mstatus(hart, (mstatus & !(SIE | SPP | MPRV | SPELP)) | sie | SPIE);
+    hart.privilege = privilege;
+    hart.cfi.elp = mstatus & SPELP != 0 && cfi::landing_pads(hart);
+    hart.pc = csr_bits(hart, csr::SEPC).r#as();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trap_and_return() {
+        let mut hart = Hart::<u32>::new(0x100);
+        csr::write(&mut hart, csr::MTVEC, 0x801).unwrap();
+        csr::write(&mut hart, csr::MSTATUS, MIE as u32).unwrap();
+        hart.privilege = Privilege::User;
+
+        // exceptions always go to the base, even in vectored mode
+        take(&mut hart, Error::EnvironmentCall, 0x0000_0073);
+        assert_eq!(hart.pc, 0x800);
+        assert_eq!(hart.privilege, Privilege::Machine);
+        assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(0x100));
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(8));
+        assert_eq!(csr::read(&mut hart, csr::MTVAL), Ok(0));
+        assert_eq!(csr::read(&mut hart, csr::MSTATUS), Ok(MPIE as u32));
+
+        // nested trap from M-mode
+        hart.pc = 0x804;
+        take(&mut hart, Error::InvalidOpCode, 0xffff_ffff);
+        assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(0x804));
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(2));
+        assert_eq!(csr::read(&mut hart, csr::MTVAL), Ok(0xffff_ffff));
+        assert_eq!(csr::read(&mut hart, csr::MSTATUS), Ok(MPP as u32));
+
+        mret(&mut hart).unwrap();
+        assert_eq!(hart.pc, 0x804);
+        assert_eq!(hart.privilege, Privilege::Machine);
+        assert_eq!(csr::read(&mut hart, csr::MSTATUS), Ok(MPIE as u32));
+
+        // MRET is illegal below M-mode
+        hart.privilege = Privilege::User;
+        assert_eq!(mret(&mut hart), Err(Error::InvalidOpCode));
+    }
+
+    #[test]
+    fn delegation() {
+        let mut hart = Hart::<u32>::new(0x100);
+        csr::write(&mut hart, csr::MTVEC, 0x800).unwrap();
+        csr::write(&mut hart, csr::STVEC, 0x400).unwrap();
+        csr::write(
+            &mut hart,
+            csr::MEDELEG,
+            (1 << ENVIRONMENT_CALL) | (1 << LOAD_PAGE_FAULT),
+        )
+        .unwrap();
+        csr::write(&mut hart, csr::SSTATUS, SIE as u32).unwrap();
+        hart.privilege = Privilege::User;
+
+        take(&mut hart, Error::LoadPageFault(0x1234), 0);
+        assert_eq!(hart.pc, 0x400);
+        assert_eq!(hart.privilege, Privilege::Supervisor);
+        assert_eq!(csr::read(&mut hart, csr::SEPC), Ok(0x100));
+        assert_eq!(csr::read(&mut hart, csr::SCAUSE), Ok(13));
+        assert_eq!(csr::read(&mut hart, csr::STVAL), Ok(0x1234));
+        assert_eq!(csr::read(&mut hart, csr::SSTATUS), Ok(SPIE as u32));
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(0));
+
+        // the ECALL from S-mode is not delegated, its cause is not the one from U-mode
+        take(&mut hart, Error::EnvironmentCall, 0x0000_0073);
+        assert_eq!(hart.pc, 0x800);
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(9));
+        assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(0x400));
+        // nor are the exceptions raised in M-mode
+        hart.pc = 0x804;
+        take(&mut hart, Error::LoadPageFault(0), 0);
+        assert_eq!(hart.pc, 0x800);
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(13));
+
+        hart.privilege = Privilege::Supervisor;
+        sret(&mut hart).unwrap();
+        assert_eq!(hart.pc, 0x100);
+        assert_eq!(hart.privilege, Privilege::User);
+        assert_eq!(csr::read(&mut hart, csr::SSTATUS), Ok((SIE | SPIE) as u32));
+
+        // SRET is illegal in U-mode, and in S-mode when trapped by TSR
+        assert_eq!(sret(&mut hart), Err(Error::InvalidOpCode));
+        hart.privilege = Privilege::Supervisor;
+        csr::write(&mut hart, csr::MSTATUS, TSR as u32).unwrap();
+        assert_eq!(sret(&mut hart), Err(Error::InvalidOpCode));
+    }
+
+    #[test]
+    fn firmware_handler() {
+        use crate::isa;
+
+        let mut memory = [0u8; 0x80];
+        let program: [u32; 4] = [
+            0x0000_0073, // ecall
+            0x0000_0000, // illegal
+            0x1000_2083, // lw x1, 0x100(x0)
+            0x0010_0073, // ebreak
+        ];
+        // csrr t0, mepc; addi t0, t0, 4; csrw mepc, t0; mret
+        let handler: [u32; 4] = [0x3410_22f3, 0x0042_8293, 0x3412_9073, 0x3020_0073];
+        for (i, word) in program.iter().enumerate() {
+            memory[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        for (i, word) in handler.iter().enumerate() {
+            memory[0x40 + i * 4..0x44 + i * 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        let mut hart = Hart::<u32>::new(0);
+        csr::write(&mut hart, csr::MTVEC, 0x40).unwrap();
+        csr::write(&mut hart, csr::MSTATUS, 0).unwrap();
+        hart.privilege = Privilege::User;
+        for (pc, cause, tval) in [(0, 8, 0), (4, 2, 0), (8, 5, 0x100), (12, 3, 12)] {
+            isa::step(&mut hart, &mut memory);
+            assert_eq!(hart.pc, 0x40);
+            assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(pc));
+            assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(cause));
+            assert_eq!(csr::read(&mut hart, csr::MTVAL), Ok(tval));
+            for _ in 0..handler.len() {
+                isa::step(&mut hart, &mut memory);
+            }
+            assert_eq!(hart.pc, pc + 4);
+            assert_eq!(hart.privilege, Privilege::User);
+        }
+        assert_eq!(hart.counters.instret, 16);
+
+        // fetching outside of memory
+        hart.pc = 0x80;
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x40);
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(1));
+        assert_eq!(csr::read(&mut hart, csr::MTVAL), Ok(0x80));
+    }
+
+    #[test]
+    fn landing_pad_across_interrupt() {
+        use crate::{isa, registers::Register};
+
+        let mut memory = [0u8; 0x200];
+        // jalr x6 at 0, a nop at its target and mret in the handler
+        memory[..4].copy_from_slice(&0x0003_00e7u32.to_le_bytes());
+        memory[0x40..0x44].copy_from_slice(&0x0000_0013u32.to_le_bytes());
+        memory[0x100..0x104].copy_from_slice(&0x3020_0073u32.to_le_bytes());
+        let mut hart = Hart::<u32>::new(0);
+        *hart.regs.get_mut(Register::X6) = 0x40;
+        csr::write(&mut hart, csr::MTVEC, 0x100).unwrap();
+        // mseccfg.MLPE enforces landing pads in M-mode
+        csr::write(&mut hart, csr::MSECCFG, 0x400).unwrap();
+        csr::write(&mut hart, csr::MIE, 1 << MACHINE_TIMER).unwrap();
+        csr::write(&mut hart, csr::MSTATUS, MIE as u32).unwrap();
+
+        // the timer interrupt lands between the jump and its target, ELP goes to mstatush.MPELP
+        isa::step(&mut hart, &mut memory);
+        assert!(hart.cfi.elp);
+        set_pending(&mut hart, MACHINE_TIMER, true);
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x100);
+        assert!(!hart.cfi.elp);
+        assert_eq!(csr::read(&mut hart, csr::MSTATUSH), Ok(1 << 9));
+
+        // MRET restores it, and the target still needs a landing pad
+        set_pending(&mut hart, MACHINE_TIMER, false);
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x40);
+        assert!(hart.cfi.elp);
+        assert_eq!(csr::read(&mut hart, csr::MSTATUSH), Ok(0));
+        isa::step(&mut hart, &mut memory);
+        assert_eq!(hart.pc, 0x100);
+        assert_eq!(csr::read(&mut hart, csr::MCAUSE), Ok(18));
+        assert_eq!(csr::read(&mut hart, csr::MTVAL), Ok(2));
+    }
+
+    #[test]
+    fn supervisor_landing_pad() {
+        let mut hart = Hart::<u64>::new(0x100);
+        csr::write(&mut hart, csr::MEDELEG, 1 << 13).unwrap();
+        // menvcfg.LPE enforces landing pads in S-mode
+        csr::write(&mut hart, csr::MENVCFG, 1 << 2).unwrap();
+        hart.privilege = Privilege::Supervisor;
+        hart.cfi.elp = true;
+
+        take(&mut hart, Error::LoadPageFault(0), 0);
+        assert!(!hart.cfi.elp);
+        assert_eq!(csr::read(&mut hart, csr::SSTATUS), Ok(SPELP | SPP));
+        sret(&mut hart).unwrap();
+        assert!(hart.cfi.elp);
+        assert_eq!(csr::read(&mut hart, csr::SSTATUS), Ok(SPIE));
+    }
+
+    #[test]
+    fn warl_fields() {
+        let mut hart = Hart::<u64>::default();
+        csr::write(&mut hart, csr::MTVEC, 0x1003).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MTVEC), Ok(0x1001));
+        csr::write(&mut hart, csr::MEPC, 0x1003).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MEPC), Ok(0x1002));
+        csr::write(&mut hart, csr::MSTATUS, u64::MAX).unwrap();
+        assert_eq!(
+            csr::read(&mut hart, csr::MSTATUS),
+            Ok(SSTATUS_MASK | MIE | MPIE | MPP | MPRV | TVM | TW | TSR | MPELP)
+        );
+        assert_eq!(csr::read(&mut hart, csr::SSTATUS), Ok(SSTATUS_MASK));
+        csr::write(&mut hart, csr::MSTATUS, 0b10 << 11).unwrap();
+        assert_eq!(csr::read(&mut hart, csr::MSTATUS), Ok(0));
+    }
+}
@@ -0,0 +1,2042 @@
+use crate::{
+    decode::{VMem, I, U5, V},
+    error::Error,
+    mem,
+    mmu::{self, Mmu},
+    num::{As, Zero},
+    registers::{Registers, VRegisters, ZeroOrRegister},
+};
+
+/// Widest vector registers allowed by the specification, in bits.
+pub const MAX_VLEN: usize = 65536;
+
+// OP-V funct3 categories, OPFVV, OPFVF and OPCFG are not arithmetic on the integer unit
+const OPIVV: u8 = 0b000;
+const OPMVV: u8 = 0b010;
+const OPIVI: u8 = 0b011;
+const OPIVX: u8 = 0b100;
+const OPMVX: u8 = 0b110;
+
+// fixed-point rounding modes of vxrm
+const RNU: u8 = 0b00;
+const RNE: u8 = 0b01;
+const RDN: u8 = 0b10;
+const ROD: u8 = 0b11;
+
+/// Vector type register, unsupported settings leave only `vill` set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VType {
+    pub vill: bool,
+    pub vma: bool,
+    pub vta: bool,
+    pub vsew: u8,
+    pub vlmul: u8,
+}
+
+/// Vector unit state, `vl` and `vstart` count elements.
+#[derive(Debug, Clone)]
+pub struct Vector {
+    pub regs: VRegisters,
+    pub elen: u32,
+    pub vtype: VType,
+    pub vl: usize,
+    pub vstart: usize,
+    pub vxrm: u8,
+    pub vxsat: bool,
+}
+
+/// Result of an element, prestart elements and the inactive ones are resolved when committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Undisturbed,
+    Inactive,
+    Active(u64),
+}
+
+/// Second operand of the arithmetic instructions, scalars are truncated to the element width.
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    Vector(u8),
+    Scalar(u64),
+}
+
+/// Addressing of a vector memory access.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Unit,
+    FaultFirst,
+    Whole,
+    Mask,
+    Strided(u64),
+    /// Index register group and index width.
+    Indexed(u8, u32),
+}
+
+/// Vector memory access, field `f` of element `i` lives at element `i` of the group
+/// `vd + f * regs`.
+#[derive(Debug, Clone, Copy)]
+struct Access {
+    mode: Mode,
+    base: u64,
+    xlen: u32,
+    eew: u32,
+    fields: usize,
+    regs: usize,
+    /// Number of elements accessed.
+    evl: usize,
+    /// End of the tail of each destination group.
+    end: usize,
+}
+
+impl VType {
+    pub const ILLEGAL: Self = Self {
+        vill: true,
+        vma: false,
+        vta: false,
+        vsew: 0,
+        vlmul: 0,
+    };
+
+    /// Decodes the `vtypei` immediate or the `rs2` value of `vsetvl`, fractional groups must hold
+    /// at least one element of `elen` bits.
+    pub fn new(bits: u64, elen: u32) -> Self {
+        let vtype = Self {
+            vill: false,
+            vma: bits & 0x80 != 0,
+            vta: bits & 0x40 != 0,
+            vsew: ((bits >> 3) & 0b111) as u8,
+            vlmul: (bits & 0b111) as u8,
+        };
+        if bits >> 8 != 0
+            || vtype.vsew > 3
+            || vtype.vlmul == 0b100
+            || vtype.sew() > elen >> (-vtype.lmul_log2()).max(0)
+        {
+            Self::ILLEGAL
+        } else {
+            vtype
+        }
+    }
+
+    #[inline(always)]
+    pub const fn sew(&self) -> u32 {
+        8 << self.vsew
+    }
+
+    /// Base two logarithm of LMUL, negative for fractional groups.
+    #[inline(always)]
+    pub const fn lmul_log2(&self) -> i32 {
+        (((self.vlmul << 5) as i8) >> 5) as i32
+    }
+
+    /// Register value, `vill` is the most significant bit of an `xlen` bits register.
+    pub fn bits(&self, xlen: u32) -> u64 {
+        if self.vill {
+            1 << (xlen.min(64) - 1)
+        } else {
+            ((self.vma as u64) << 7)
+                | ((self.vta as u64) << 6)
+                | ((self.vsew as u64) << 3)
+                | self.vlmul as u64
+        }
+    }
+}
+
+impl Vector {
+    /// Vector unit with `vlen` bits registers and elements up to `elen` bits, `vtype` starts
+    /// illegal.
+    pub fn new(vlen: usize, elen: u32) -> Self {
+        assert!(matches!(elen, 32 | 64), "ELEN must be 32 or 64");
+        assert!(
+            vlen.is_power_of_two() && vlen >= elen as usize && vlen <= MAX_VLEN,
+            "VLEN must be a power of two between ELEN and 65536"
+        );
+        Self {
+            regs: VRegisters::new(vlen),
+            elen,
+            vtype: VType::ILLEGAL,
+            vl: 0,
+            vstart: 0,
+            vxrm: RNU,
+            vxsat: false,
+        }
+    }
+
+    #[inline(always)]
+    pub fn vlen(&self) -> usize {
+        self.regs.vlenb() * 8
+    }
+
+    /// Maximum vector length of the current `vtype`.
+    pub fn vlmax(&self) -> usize {
+        let elements = self.vlen() / self.vtype.sew() as usize;
+        let lmul = self.vtype.lmul_log2();
+        if lmul < 0 {
+            elements >> -lmul
+        } else {
+            elements << lmul
+        }
+    }
+
+    /// EMUL of `eew` bits elements keeping the current SEW/LMUL ratio.
+    #[inline(always)]
+    fn emul(&self, eew: u32) -> i32 {
+        self.vtype.lmul_log2() + eew.trailing_zeros() as i32
+            - self.vtype.sew().trailing_zeros() as i32
+    }
+
+    /// Elements of `eew` bits in a group of EMUL `emul`, fractional groups span a register.
+    #[inline(always)]
+    fn elements(&self, eew: u32, emul: i32) -> usize {
+        (self.vlen() / eew as usize) << emul.max(0)
+    }
+
+    /// Runs `f` on the active body elements among the first `len` ones, with `masked` elements
+    /// are active when their bit in `v0` is set.
+    fn collect(&self, masked: bool, len: usize, mut f: impl FnMut(usize) -> u64) -> Vec<Element> {
+        (0..len)
+            .map(|i| {
+                if i < self.vstart {
+                    Element::Undisturbed
+                } else if !masked || self.regs.mask(0, i) {
+                    Element::Active(f(i))
+                } else {
+                    Element::Inactive
+                }
+            })
+            .collect()
+    }
+
+    /// Writes `results` to the group at `vd`, agnostic inactive and tail elements up to `end`
+    /// are filled with ones. Nothing is written when there are no body elements.
+    fn commit(&mut self, vd: u8, eew: u32, results: &[Element], end: usize) {
+        if self.vstart >= results.len() {
+            return;
+        }
+        for (i, element) in results.iter().enumerate() {
+            match *element {
+                Element::Active(value) => self.regs.set(vd, i, eew, value),
+                Element::Inactive if self.vtype.vma => self.regs.set(vd, i, eew, u64::MAX),
+                _ => {}
+            }
+        }
+        if self.vtype.vta {
+            for i in results.len()..end {
+                self.regs.set(vd, i, eew, u64::MAX);
+            }
+        }
+    }
+
+    /// Like [`Vector::commit`] for results written as mask bits, the tail spans the register.
+    fn commit_mask(&mut self, vd: u8, results: &[Element]) {
+        if self.vstart >= results.len() {
+            return;
+        }
+        for (i, element) in results.iter().enumerate() {
+            match *element {
+                Element::Active(value) => self.regs.set_mask(vd, i, value != 0),
+                Element::Inactive if self.vtype.vma => self.regs.set_mask(vd, i, true),
+                _ => {}
+            }
+        }
+        if self.vtype.vta {
+            for i in results.len()..self.vlen() {
+                self.regs.set_mask(vd, i, true);
+            }
+        }
+    }
+}
+
+impl Default for Vector {
+    #[inline]
+    fn default() -> Self {
+        Self::new(128, 64)
+    }
+}
+
+impl Operand {
+    #[inline(always)]
+    fn get(self, regs: &VRegisters, index: usize, eew: u32) -> u64 {
+        match self {
+            Self::Vector(reg) => regs.get(reg, index, eew),
+            Self::Scalar(value) => truncate(value, eew),
+        }
+    }
+
+    #[inline(always)]
+    fn check(self, emul: i32) -> Result<(), Error> {
+        match self {
+            Self::Vector(reg) => check_group(reg, emul),
+            Self::Scalar(_) => Ok(()),
+        }
+    }
+}
+
+#[inline(always)]
+const fn truncate(value: u64, bits: u32) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        value & ((1 << bits) - 1)
+    }
+}
+
+#[inline(always)]
+const fn sign_extend(value: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+#[inline(always)]
+fn xlen<T>() -> u32 {
+    (core::mem::size_of::<T>() * 8).min(64) as u32
+}
+
+/// Groups span 2^`emul` registers and must start at a multiple of their size.
+#[inline(always)]
+fn check_group(reg: u8, emul: i32) -> Result<(), Error> {
+    if !(-3..=3).contains(&emul) || (emul > 0 && !reg.is_multiple_of(1 << emul)) {
+        Err(Error::InvalidOpCode)
+    } else {
+        Ok(())
+    }
+}
+
+/// Shifts `value` right by `shift` bits rounding as selected by `vxrm`.
+fn roundoff(value: i128, shift: u32, vxrm: u8) -> i128 {
+    if shift == 0 {
+        return value;
+    }
+    let bit = |n: u32| (value >> n) & 1;
+    let below = |n: u32| value & ((1 << n) - 1) != 0;
+    let round = match vxrm {
+        RNU => bit(shift - 1),
+        RNE => bit(shift - 1) & (below(shift - 1) as i128 | bit(shift)),
+        RDN => 0,
+        ROD => (bit(shift) == 0 && below(shift)) as i128,
+        _ => unreachable!("vxrm is two bits wide"),
+    };
+    (value >> shift) + round
+}
+
+fn saturate_unsigned(value: i128, bits: u32, saturated: &mut bool) -> u64 {
+    let max = truncate(u64::MAX, bits) as i128;
+    if value > max || value < 0 {
+        *saturated = true;
+        value.clamp(0, max) as u64
+    } else {
+        value as u64
+    }
+}
+
+fn saturate_signed(value: i128, bits: u32, saturated: &mut bool) -> u64 {
+    let max = (1i128 << (bits - 1)) - 1;
+    let min = -(1i128 << (bits - 1));
+    if value > max || value < min {
+        *saturated = true;
+    }
+    value.clamp(min, max) as u64
+}
+
+/// Executes `vsetvli`, `vsetivli` and `vsetvl`, an unsupported `vtype` sets `vill` and `vl=0`.
+pub fn config<T>(instruction: I, regs: &mut Registers<T>, vector: &mut Vector) -> Result<(), Error>
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    let imm = instruction.imm.as_u16() as u64;
+    let rd = regs.decode(instruction.rd)?;
+    let (bits, avl) = if imm >> 11 == 0 {
+        // vsetvli
+        (imm, None)
+    } else if imm >> 10 == 0b11 {
+        // vsetivli, the rs1 field is the application vector length
+        (imm & 0x3ff, Some(instruction.rs1.as_u8() as u64))
+    } else if imm >> 5 == 0b1000000 {
+        // vsetvl
+        let rs2 = regs.decode(U5::new_truncate(imm as u8))?;
+        (rs2.fetch(regs).r#as(), None)
+    } else {
+        return Err(Error::InvalidOpCode);
+    };
+
+    let avl = match avl {
+        Some(avl) => avl,
+        None => match regs.decode(instruction.rs1)? {
+            ZeroOrRegister::Register(reg) => reg.fetch(regs).r#as(),
+            // rs1=x0 and rd=x0 keep the current vector length
+            ZeroOrRegister::Zero if rd == ZeroOrRegister::Zero => vector.vl as u64,
+            ZeroOrRegister::Zero => u64::MAX,
+        },
+    };
+
+    vector.vtype = VType::new(bits, vector.elen);
+    vector.vl = if vector.vtype.vill {
+        0
+    } else {
+        avl.min(vector.vlmax() as u64) as usize
+    };
+    vector.vstart = 0;
+    if let ZeroOrRegister::Register(reg) = rd {
+        *regs.get_mut(reg) = (vector.vl as u64).r#as();
+    }
+    Ok(())
+}
+
+/// Executes the integer and fixed-point OP-V instructions, scalar operands are sign-extended
+/// from XLEN.
+pub fn math<T>(instruction: V, regs: &mut Registers<T>, vector: &mut Vector) -> Result<(), Error>
+where
+    T: Copy + Zero + As<u64>,
+    u64: As<T>,
+{
+    let xlen = xlen::<T>();
+    let scalar = match instruction.funct3.as_u8() {
+        OPIVX | OPMVX => sign_extend(regs.decode(instruction.vs1)?.fetch(regs).r#as(), xlen) as u64,
+        _ => 0,
+    };
+
+    if let Some(value) = execute(instruction, scalar, xlen, vector)? {
+        if let ZeroOrRegister::Register(reg) = regs.decode(instruction.vd)? {
+            *regs.get_mut(reg) = value.r#as();
+        }
+    }
+    vector.vstart = 0;
+    Ok(())
+}
+
+/// Returns the value written to `x[rd]` by the instructions with a scalar destination.
+fn execute(v: V, scalar: u64, xlen: u32, vector: &mut Vector) -> Result<Option<u64>, Error> {
+    let uimm = v.vs1.as_u8() as u64;
+    // vmv<nr>r.v does not depend on vtype
+    if v.funct3.as_u8() == OPIVI && v.funct6.as_u8() == 0b100111 {
+        return move_whole(v, vector).map(|()| None);
+    }
+    if vector.vtype.vill {
+        return Err(Error::InvalidOpCode);
+    }
+
+    match v.funct3.as_u8() {
+        OPIVV => opi(v, Operand::Vector(v.vs1.as_u8()), 0, vector).map(|()| None),
+        OPIVX => opi(v, Operand::Scalar(scalar), truncate(scalar, xlen), vector).map(|()| None),
+        OPIVI => opi(
+            v,
+            Operand::Scalar(sign_extend(uimm, 5) as u64),
+            uimm,
+            vector,
+        )
+        .map(|()| None),
+        OPMVV => opm(v, Operand::Vector(v.vs1.as_u8()), vector),
+        OPMVX => opm(v, Operand::Scalar(scalar), vector),
+        // OPFVV and OPFVF, there is no vector floating-point unit
+        _ => Err(Error::InvalidOpCode),
+    }
+}
+
+/// OPIVV, OPIVX and OPIVI instructions, `offset` is the unsigned scalar or immediate used by
+/// the slides, the gathers and the immediate shifts.
+fn opi(v: V, op1: Operand, offset: u64, vector: &mut Vector) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let vxrm = vector.vxrm;
+    let masked = !v.vm;
+    let vs2 = v.vs2.as_u8();
+    let single = [sew, sew, sew];
+    let narrow = [sew, 2 * sew, sew];
+    let shift = if v.funct3.as_u8() == OPIVI {
+        Operand::Scalar(offset)
+    } else {
+        op1
+    };
+    let s = |value| sign_extend(value, sew);
+    let mut sat = false;
+
+    #[deny(unreachable_patterns)]
+    match (v.funct6.as_u8(), v.funct3.as_u8()) {
+        // VADD
+        (0b000000, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            a.wrapping_add(b)
+        }),
+        // VSUB
+        (0b000010, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            a.wrapping_sub(b)
+        }),
+        // VRSUB
+        (0b000011, OPIVX | OPIVI) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            b.wrapping_sub(a)
+        }),
+        // VMINU
+        (0b000100, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| a.min(b)),
+        // VMIN
+        (0b000101, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            if s(a) < s(b) {
+                a
+            } else {
+                b
+            }
+        }),
+        // VMAXU
+        (0b000110, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| a.max(b)),
+        // VMAX
+        (0b000111, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            if s(a) > s(b) {
+                a
+            } else {
+                b
+            }
+        }),
+        // VAND
+        (0b001001, _) => arith(vector, v, masked, op1, single, |a, b, _, _| a & b),
+        // VOR
+        (0b001010, _) => arith(vector, v, masked, op1, single, |a, b, _, _| a | b),
+        // VXOR
+        (0b001011, _) => arith(vector, v, masked, op1, single, |a, b, _, _| a ^ b),
+        // VRGATHER
+        (0b001100, OPIVV) => gather(vector, v, op1, sew),
+        (0b001100, _) => gather(vector, v, Operand::Scalar(offset), 64),
+        // VRGATHEREI16
+        (0b001110, OPIVV) => gather(vector, v, op1, 16),
+        // VSLIDEUP
+        (0b001110, _) => {
+            let skip = usize::try_from(offset).unwrap_or(usize::MAX);
+            permute(vector, v, skip, |regs, i| regs.get(vs2, i - skip, sew))
+        }
+        // VSLIDEDOWN
+        (0b001111, OPIVX | OPIVI) => {
+            let vlmax = vector.vlmax() as u64;
+            permute(vector, v, 0, |regs, i| {
+                match (i as u64).checked_add(offset) {
+                    Some(j) if j < vlmax => regs.get(vs2, j as usize, sew),
+                    _ => 0,
+                }
+            })
+        }
+        // VADC
+        (0b010000, _) if masked => arith(vector, v, false, op1, single, |a, b, _, m| {
+            a.wrapping_add(b).wrapping_add(m as u64)
+        }),
+        // VMADC
+        (0b010001, _) => mask_op(vector, v, false, op1, |a, b, m| {
+            (a as u128 + b as u128 + (masked && m) as u128) >> sew != 0
+        }),
+        // VSBC
+        (0b010010, OPIVV | OPIVX) if masked => {
+            arith(vector, v, false, op1, single, |a, b, _, m| {
+                a.wrapping_sub(b).wrapping_sub(m as u64)
+            })
+        }
+        // VMSBC
+        (0b010011, OPIVV | OPIVX) => mask_op(vector, v, false, op1, |a, b, m| {
+            (a as i128 - b as i128 - (masked && m) as i128) < 0
+        }),
+        // VMERGE
+        (0b010111, _) if masked => arith(
+            vector,
+            v,
+            false,
+            op1,
+            single,
+            |a, b, _, m| if m { b } else { a },
+        ),
+        // VMV.V
+        (0b010111, _) if vs2 == 0 => arith(vector, v, false, op1, single, |_, b, _, _| b),
+        // VMSEQ
+        (0b011000, _) => mask_op(vector, v, masked, op1, |a, b, _| a == b),
+        // VMSNE
+        (0b011001, _) => mask_op(vector, v, masked, op1, |a, b, _| a != b),
+        // VMSLTU
+        (0b011010, OPIVV | OPIVX) => mask_op(vector, v, masked, op1, |a, b, _| a < b),
+        // VMSLT
+        (0b011011, OPIVV | OPIVX) => mask_op(vector, v, masked, op1, |a, b, _| s(a) < s(b)),
+        // VMSLEU
+        (0b011100, _) => mask_op(vector, v, masked, op1, |a, b, _| a <= b),
+        // VMSLE
+        (0b011101, _) => mask_op(vector, v, masked, op1, |a, b, _| s(a) <= s(b)),
+        // VMSGTU
+        (0b011110, OPIVX | OPIVI) => mask_op(vector, v, masked, op1, |a, b, _| a > b),
+        // VMSGT
+        (0b011111, OPIVX | OPIVI) => mask_op(vector, v, masked, op1, |a, b, _| s(a) > s(b)),
+        // VSADDU
+        (0b100000, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            saturate_unsigned(a as i128 + b as i128, sew, &mut sat)
+        }),
+        // VSADD
+        (0b100001, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            saturate_signed(s(a) as i128 + s(b) as i128, sew, &mut sat)
+        }),
+        // VSSUBU
+        (0b100010, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            saturate_unsigned(a as i128 - b as i128, sew, &mut sat)
+        }),
+        // VSSUB
+        (0b100011, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            saturate_signed(s(a) as i128 - s(b) as i128, sew, &mut sat)
+        }),
+        // VSLL
+        (0b100101, _) => arith(vector, v, masked, shift, single, |a, b, _, _| {
+            a << (b & (sew - 1) as u64)
+        }),
+        // VSMUL
+        (0b100111, OPIVV | OPIVX) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            let product = roundoff(s(a) as i128 * s(b) as i128, sew - 1, vxrm);
+            saturate_signed(product, sew, &mut sat)
+        }),
+        // VSRL
+        (0b101000, _) => arith(vector, v, masked, shift, single, |a, b, _, _| {
+            a >> (b & (sew - 1) as u64)
+        }),
+        // VSRA
+        (0b101001, _) => arith(vector, v, masked, shift, single, |a, b, _, _| {
+            (s(a) >> (b & (sew - 1) as u64)) as u64
+        }),
+        // VSSRL
+        (0b101010, _) => arith(vector, v, masked, shift, single, |a, b, _, _| {
+            roundoff(a as i128, (b & (sew - 1) as u64) as u32, vxrm) as u64
+        }),
+        // VSSRA
+        (0b101011, _) => arith(vector, v, masked, shift, single, |a, b, _, _| {
+            roundoff(s(a) as i128, (b & (sew - 1) as u64) as u32, vxrm) as u64
+        }),
+        // VNSRL
+        (0b101100, _) => arith(vector, v, masked, shift, narrow, |a, b, _, _| {
+            a >> (b & (2 * sew - 1) as u64)
+        }),
+        // VNSRA
+        (0b101101, _) => arith(vector, v, masked, shift, narrow, |a, b, _, _| {
+            (sign_extend(a, 2 * sew) >> (b & (2 * sew - 1) as u64)) as u64
+        }),
+        // VNCLIPU
+        (0b101110, _) => arith(vector, v, masked, shift, narrow, |a, b, _, _| {
+            let value = roundoff(a as i128, (b & (2 * sew - 1) as u64) as u32, vxrm);
+            saturate_unsigned(value, sew, &mut sat)
+        }),
+        // VNCLIP
+        (0b101111, _) => arith(vector, v, masked, shift, narrow, |a, b, _, _| {
+            let value = sign_extend(a, 2 * sew) as i128;
+            let value = roundoff(value, (b & (2 * sew - 1) as u64) as u32, vxrm);
+            saturate_signed(value, sew, &mut sat)
+        }),
+        // VWREDSUMU
+        (0b110000, OPIVV) => reduce(vector, v, 2 * sew, |acc, e| acc.wrapping_add(e)),
+        // VWREDSUM
+        (0b110001, OPIVV) => reduce(vector, v, 2 * sew, |acc, e| acc.wrapping_add(s(e) as u64)),
+        _ => Err(Error::InvalidOpCode),
+    }?;
+
+    vector.vxsat |= sat;
+    Ok(())
+}
+
+/// OPMVV and OPMVX instructions.
+fn opm(v: V, op1: Operand, vector: &mut Vector) -> Result<Option<u64>, Error> {
+    let sew = vector.vtype.sew();
+    let vxrm = vector.vxrm;
+    let masked = !v.vm;
+    let vs2 = v.vs2.as_u8();
+    let single = [sew, sew, sew];
+    let wide = [2 * sew, sew, sew];
+    let wide_w = [2 * sew, 2 * sew, sew];
+    let s = |value| sign_extend(value, sew);
+
+    #[deny(unreachable_patterns)]
+    match (v.funct6.as_u8(), v.funct3.as_u8()) {
+        // VREDSUM
+        (0b000000, OPMVV) => reduce(vector, v, sew, |acc, e| acc.wrapping_add(e)),
+        // VREDAND
+        (0b000001, OPMVV) => reduce(vector, v, sew, |acc, e| acc & e),
+        // VREDOR
+        (0b000010, OPMVV) => reduce(vector, v, sew, |acc, e| acc | e),
+        // VREDXOR
+        (0b000011, OPMVV) => reduce(vector, v, sew, |acc, e| acc ^ e),
+        // VREDMINU
+        (0b000100, OPMVV) => reduce(vector, v, sew, |acc, e| acc.min(e)),
+        // VREDMIN
+        (0b000101, OPMVV) => reduce(vector, v, sew, |acc, e| if s(e) < s(acc) { e } else { acc }),
+        // VREDMAXU
+        (0b000110, OPMVV) => reduce(vector, v, sew, |acc, e| acc.max(e)),
+        // VREDMAX
+        (0b000111, OPMVV) => reduce(vector, v, sew, |acc, e| if s(e) > s(acc) { e } else { acc }),
+        // VAADDU
+        (0b001000, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            roundoff(a as i128 + b as i128, 1, vxrm) as u64
+        }),
+        // VAADD
+        (0b001001, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            roundoff(s(a) as i128 + s(b) as i128, 1, vxrm) as u64
+        }),
+        // VASUBU
+        (0b001010, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            roundoff(a as i128 - b as i128, 1, vxrm) as u64
+        }),
+        // VASUB
+        (0b001011, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            roundoff(s(a) as i128 - s(b) as i128, 1, vxrm) as u64
+        }),
+        // VSLIDE1UP
+        (0b001110, OPMVX) => {
+            let x = op1.get(&vector.regs, 0, sew);
+            permute(vector, v, 0, |regs, i| {
+                if i == 0 {
+                    x
+                } else {
+                    regs.get(vs2, i - 1, sew)
+                }
+            })
+        }
+        // VSLIDE1DOWN
+        (0b001111, OPMVX) => {
+            let x = op1.get(&vector.regs, 0, sew);
+            let vl = vector.vl;
+            permute(vector, v, 0, |regs, i| {
+                if i + 1 == vl {
+                    x
+                } else {
+                    regs.get(vs2, i + 1, sew)
+                }
+            })
+        }
+        // VWXUNARY0
+        (0b010000, OPMVV) => return scalar_unary(v, vector).map(Some),
+        // VMV.S.X
+        (0b010000, OPMVX) if v.vm && vs2 == 0 => {
+            if vector.vstart < vector.vl {
+                let x = op1.get(&vector.regs, 0, sew);
+                let end = vector.elements(sew, 0);
+                vector.commit(v.vd.as_u8(), sew, &[Element::Active(x)], end);
+            }
+            Ok(())
+        }
+        // VXUNARY0
+        (0b010010, OPMVV) => extend(vector, v),
+        // VMUNARY0
+        (0b010100, OPMVV) => mask_unary(vector, v),
+        // VCOMPRESS
+        (0b010111, OPMVV) if v.vm => compress(vector, v),
+        // VMANDN, VMAND, VMOR, VMXOR, VMORN, VMNAND, VMNOR, VMXNOR
+        (0b011000..=0b011111, OPMVV) if v.vm => mask_logical(vector, v),
+        // VDIVU
+        (0b100000, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            a.checked_div(b).unwrap_or(u64::MAX)
+        }),
+        // VDIV
+        (0b100001, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            if b == 0 {
+                u64::MAX
+            } else {
+                s(a).wrapping_div(s(b)) as u64
+            }
+        }),
+        // VREMU
+        (0b100010, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            a.checked_rem(b).unwrap_or(a)
+        }),
+        // VREM
+        (0b100011, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            if b == 0 {
+                a
+            } else {
+                s(a).wrapping_rem(s(b)) as u64
+            }
+        }),
+        // VMULHU
+        (0b100100, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            ((a as u128 * b as u128) >> sew) as u64
+        }),
+        // VMUL
+        (0b100101, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            a.wrapping_mul(b)
+        }),
+        // VMULHSU
+        (0b100110, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            ((s(a) as i128 * b as i128) >> sew) as u64
+        }),
+        // VMULH
+        (0b100111, _) => arith(vector, v, masked, op1, single, |a, b, _, _| {
+            ((s(a) as i128 * s(b) as i128) >> sew) as u64
+        }),
+        // VMADD
+        (0b101001, _) => arith(vector, v, masked, op1, single, |a, b, d, _| {
+            b.wrapping_mul(d).wrapping_add(a)
+        }),
+        // VNMSUB
+        (0b101011, _) => arith(vector, v, masked, op1, single, |a, b, d, _| {
+            a.wrapping_sub(b.wrapping_mul(d))
+        }),
+        // VMACC
+        (0b101101, _) => arith(vector, v, masked, op1, single, |a, b, d, _| {
+            b.wrapping_mul(a).wrapping_add(d)
+        }),
+        // VNMSAC
+        (0b101111, _) => arith(vector, v, masked, op1, single, |a, b, d, _| {
+            d.wrapping_sub(b.wrapping_mul(a))
+        }),
+        // VWADDU
+        (0b110000, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| a.wrapping_add(b)),
+        // VWADD
+        (0b110001, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| {
+            s(a).wrapping_add(s(b)) as u64
+        }),
+        // VWSUBU
+        (0b110010, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| a.wrapping_sub(b)),
+        // VWSUB
+        (0b110011, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| {
+            s(a).wrapping_sub(s(b)) as u64
+        }),
+        // VWADDU.W
+        (0b110100, _) => arith(vector, v, masked, op1, wide_w, |a, b, _, _| {
+            a.wrapping_add(b)
+        }),
+        // VWADD.W
+        (0b110101, _) => arith(vector, v, masked, op1, wide_w, |a, b, _, _| {
+            a.wrapping_add(s(b) as u64)
+        }),
+        // VWSUBU.W
+        (0b110110, _) => arith(vector, v, masked, op1, wide_w, |a, b, _, _| {
+            a.wrapping_sub(b)
+        }),
+        // VWSUB.W
+        (0b110111, _) => arith(vector, v, masked, op1, wide_w, |a, b, _, _| {
+            a.wrapping_sub(s(b) as u64)
+        }),
+        // VWMULU
+        (0b111000, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| a.wrapping_mul(b)),
+        // VWMULSU
+        (0b111010, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| {
+            s(a).wrapping_mul(b as i64) as u64
+        }),
+        // VWMUL
+        (0b111011, _) => arith(vector, v, masked, op1, wide, |a, b, _, _| {
+            s(a).wrapping_mul(s(b)) as u64
+        }),
+        // VWMACCU
+        (0b111100, _) => arith(vector, v, masked, op1, wide, |a, b, d, _| {
+            d.wrapping_add(a.wrapping_mul(b))
+        }),
+        // VWMACC
+        (0b111101, _) => arith(vector, v, masked, op1, wide, |a, b, d, _| {
+            d.wrapping_add(s(a).wrapping_mul(s(b)) as u64)
+        }),
+        // VWMACCUS
+        (0b111110, OPMVX) => arith(vector, v, masked, op1, wide, |a, b, d, _| {
+            d.wrapping_add((b as i64).wrapping_mul(s(a)) as u64)
+        }),
+        // VWMACCSU
+        (0b111111, _) => arith(vector, v, masked, op1, wide, |a, b, d, _| {
+            d.wrapping_add(s(b).wrapping_mul(a as i64) as u64)
+        }),
+        _ => Err(Error::InvalidOpCode),
+    }
+    .map(|()| None)
+}
+
+/// Element-wise operation, `eew` holds the element widths of `vd`, `vs2` and `op1`. `f`
+/// receives `vs2[i]`, `op1[i]`, the old `vd[i]` and the `v0` mask bit, elements are active
+/// when `masked` is clear or their mask bit is set.
+fn arith(
+    vector: &mut Vector,
+    v: V,
+    masked: bool,
+    op1: Operand,
+    eew: [u32; 3],
+    mut f: impl FnMut(u64, u64, u64, bool) -> u64,
+) -> Result<(), Error> {
+    let (vd, vs2) = (v.vd.as_u8(), v.vs2.as_u8());
+    let [dest, src2, src1] = eew;
+    if eew.iter().any(|width| !(8..=vector.elen).contains(width)) || (!v.vm && vd == 0) {
+        return Err(Error::InvalidOpCode);
+    }
+    let emul = vector.emul(dest);
+    check_group(vd, emul)?;
+    check_group(vs2, vector.emul(src2))?;
+    op1.check(vector.emul(src1))?;
+
+    let regs = &vector.regs;
+    let results = vector.collect(masked, vector.vl, |i| {
+        f(
+            regs.get(vs2, i, src2),
+            op1.get(regs, i, src1),
+            regs.get(vd, i, dest),
+            regs.mask(0, i),
+        )
+    });
+    let end = vector.elements(dest, emul);
+    vector.commit(vd, dest, &results, end);
+    Ok(())
+}
+
+/// Element-wise operation writing a mask, `f` receives `vs2[i]`, `op1[i]` and the `v0` mask bit.
+fn mask_op(
+    vector: &mut Vector,
+    v: V,
+    masked: bool,
+    op1: Operand,
+    mut f: impl FnMut(u64, u64, bool) -> bool,
+) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let lmul = vector.vtype.lmul_log2();
+    let vs2 = v.vs2.as_u8();
+    check_group(vs2, lmul)?;
+    op1.check(lmul)?;
+
+    let regs = &vector.regs;
+    let results = vector.collect(masked, vector.vl, |i| {
+        f(
+            regs.get(vs2, i, sew),
+            op1.get(regs, i, sew),
+            regs.mask(0, i),
+        ) as u64
+    });
+    vector.commit_mask(v.vd.as_u8(), &results);
+    Ok(())
+}
+
+/// Element-wise operation reading `vs2` at any index, elements below `skip` are left undisturbed.
+fn permute(
+    vector: &mut Vector,
+    v: V,
+    skip: usize,
+    mut f: impl FnMut(&VRegisters, usize) -> u64,
+) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let lmul = vector.vtype.lmul_log2();
+    let vd = v.vd.as_u8();
+    if !v.vm && vd == 0 {
+        return Err(Error::InvalidOpCode);
+    }
+    check_group(vd, lmul)?;
+    check_group(v.vs2.as_u8(), lmul)?;
+
+    let regs = &vector.regs;
+    let mut results = vector.collect(!v.vm, vector.vl, |i| if i < skip { 0 } else { f(regs, i) });
+    for element in results.iter_mut().take(skip) {
+        *element = Element::Undisturbed;
+    }
+    let end = vector.elements(sew, lmul);
+    vector.commit(vd, sew, &results, end);
+    Ok(())
+}
+
+/// `vd[i] = vs2[index[i]]`, out of range indices read zero.
+fn gather(vector: &mut Vector, v: V, index: Operand, eew: u32) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let vlmax = vector.vlmax() as u64;
+    let vs2 = v.vs2.as_u8();
+    index.check(vector.emul(eew))?;
+    permute(vector, v, 0, |regs, i| match index.get(regs, i, eew) {
+        j if j < vlmax => regs.get(vs2, j as usize, sew),
+        _ => 0,
+    })
+}
+
+/// Reduction of the active elements of `vs2` into `vd[0]` starting from `vs1[0]`, the
+/// accumulator is `eew` bits wide.
+fn reduce(
+    vector: &mut Vector,
+    v: V,
+    eew: u32,
+    mut f: impl FnMut(u64, u64) -> u64,
+) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let vs2 = v.vs2.as_u8();
+    if eew > vector.elen || vector.vstart != 0 {
+        return Err(Error::InvalidOpCode);
+    }
+    check_group(vs2, vector.vtype.lmul_log2())?;
+    if vector.vl == 0 {
+        return Ok(());
+    }
+
+    let regs = &vector.regs;
+    let acc = (0..vector.vl)
+        .filter(|&i| v.vm || regs.mask(0, i))
+        .fold(regs.get(v.vs1.as_u8(), 0, eew), |acc, i| {
+            f(acc, regs.get(vs2, i, sew))
+        });
+    let end = vector.elements(eew, 0);
+    vector.commit(v.vd.as_u8(), eew, &[Element::Active(acc)], end);
+    Ok(())
+}
+
+/// `vmv.x.s`, `vcpop.m` and `vfirst.m`.
+fn scalar_unary(v: V, vector: &Vector) -> Result<u64, Error> {
+    let sew = vector.vtype.sew();
+    let vs2 = v.vs2.as_u8();
+    let regs = &vector.regs;
+    let mut set = (0..vector.vl).filter(|&i| (v.vm || regs.mask(0, i)) && regs.mask(vs2, i));
+
+    #[deny(unreachable_patterns)]
+    match v.vs1.as_u8() {
+        // VMV.X.S
+        0b00000 if v.vm => Ok(sign_extend(regs.get(vs2, 0, sew), sew) as u64),
+        // VCPOP
+        0b10000 if vector.vstart == 0 => Ok(set.count() as u64),
+        // VFIRST
+        0b10001 if vector.vstart == 0 => Ok(set.next().map_or(u64::MAX, |i| i as u64)),
+        _ => Err(Error::InvalidOpCode),
+    }
+}
+
+/// `vzext.vf<n>` and `vsext.vf<n>`.
+fn extend(vector: &mut Vector, v: V) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    #[deny(unreachable_patterns)]
+    let (factor, signed) = match v.vs1.as_u8() {
+        0b00010 => (8, false),
+        0b00011 => (8, true),
+        0b00100 => (4, false),
+        0b00101 => (4, true),
+        0b00110 => (2, false),
+        0b00111 => (2, true),
+        _ => return Err(Error::InvalidOpCode),
+    };
+    let src = sew / factor;
+    arith(
+        vector,
+        v,
+        !v.vm,
+        Operand::Scalar(0),
+        [sew, src, sew],
+        |a, _, _, _| {
+            if signed {
+                sign_extend(a, src) as u64
+            } else {
+                a
+            }
+        },
+    )
+}
+
+/// `vmsbf.m`, `vmsof.m`, `vmsif.m`, `viota.m` and `vid.v`.
+fn mask_unary(vector: &mut Vector, v: V) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let lmul = vector.vtype.lmul_log2();
+    let (vd, vs2) = (v.vd.as_u8(), v.vs2.as_u8());
+    let masked = !v.vm;
+    if masked && vd == 0 {
+        return Err(Error::InvalidOpCode);
+    }
+    let regs = &vector.regs;
+
+    #[deny(unreachable_patterns)]
+    match v.vs1.as_u8() {
+        // VMSBF, VMSOF, VMSIF
+        code @ 0b00001..=0b00011 if vector.vstart == 0 && vd != vs2 => {
+            let mut found = false;
+            let results = vector.collect(masked, vector.vl, |i| {
+                let bit = regs.mask(vs2, i);
+                let value = match code {
+                    0b00001 => !found && !bit,
+                    0b00010 => !found && bit,
+                    _ => !found,
+                };
+                found |= bit;
+                value as u64
+            });
+            vector.commit_mask(vd, &results);
+        }
+        // VIOTA
+        0b10000 if vector.vstart == 0 => {
+            check_group(vd, lmul)?;
+            let mut count = 0;
+            let results = vector.collect(masked, vector.vl, |i| {
+                let value = count;
+                count += regs.mask(vs2, i) as u64;
+                value
+            });
+            let end = vector.elements(sew, lmul);
+            vector.commit(vd, sew, &results, end);
+        }
+        // VID
+        0b10001 if vs2 == 0 => {
+            check_group(vd, lmul)?;
+            let results = vector.collect(masked, vector.vl, |i| i as u64);
+            let end = vector.elements(sew, lmul);
+            vector.commit(vd, sew, &results, end);
+        }
+        _ => return Err(Error::InvalidOpCode),
+    }
+    Ok(())
+}
+
+/// `vcompress.vm`, the elements after the packed ones are tail elements.
+fn compress(vector: &mut Vector, v: V) -> Result<(), Error> {
+    let sew = vector.vtype.sew();
+    let lmul = vector.vtype.lmul_log2();
+    let (vd, vs1, vs2) = (v.vd.as_u8(), v.vs1.as_u8(), v.vs2.as_u8());
+    if vector.vstart != 0 {
+        return Err(Error::InvalidOpCode);
+    }
+    check_group(vd, lmul)?;
+    check_group(vs2, lmul)?;
+
+    let regs = &vector.regs;
+    let results = (0..vector.vl)
+        .filter(|&i| regs.mask(vs1, i))
+        .map(|i| Element::Active(regs.get(vs2, i, sew)))
+        .collect::<Vec<_>>();
+    let end = vector.elements(sew, lmul);
+    vector.commit(vd, sew, &results, end);
+    Ok(())
+}
+
+/// Mask-register logical instructions, always unmasked.
+fn mask_logical(vector: &mut Vector, v: V) -> Result<(), Error> {
+    let (vs1, vs2) = (v.vs1.as_u8(), v.vs2.as_u8());
+    let f: fn(bool, bool) -> bool = match v.funct6.as_u8() {
+        0b011000 => |a, b| a & !b,
+        0b011001 => |a, b| a & b,
+        0b011010 => |a, b| a | b,
+        0b011011 => |a, b| a ^ b,
+        0b011100 => |a, b| a | !b,
+        0b011101 => |a, b| !(a & b),
+        0b011110 => |a, b| !(a | b),
+        _ => |a, b| !(a ^ b),
+    };
+
+    let regs = &vector.regs;
+    let results = vector.collect(false, vector.vl, |i| {
+        f(regs.mask(vs2, i), regs.mask(vs1, i)) as u64
+    });
+    vector.commit_mask(v.vd.as_u8(), &results);
+    Ok(())
+}
+
+/// `vmv<nr>r.v`, copies whole registers even when `vtype` is illegal.
+fn move_whole(v: V, vector: &mut Vector) -> Result<(), Error> {
+    let nr = v.vs1.as_u8() + 1;
+    if !v.vm || !matches!(nr, 1 | 2 | 4 | 8) {
+        return Err(Error::InvalidOpCode);
+    }
+    let (vd, vs2) = (v.vd.as_u8(), v.vs2.as_u8());
+    let emul = nr.trailing_zeros() as i32;
+    check_group(vd, emul)?;
+    check_group(vs2, emul)?;
+
+    let eew = if vector.vtype.vill {
+        8
+    } else {
+        vector.vtype.sew()
+    };
+    for i in vector.vstart..vector.elements(eew, emul) {
+        let value = vector.regs.get(vs2, i, eew);
+        vector.regs.set(vd, i, eew, value);
+    }
+    Ok(())
+}
+
+impl Access {
+    /// Decodes the addressing of a load or a store, `stride` is only used by strided accesses.
+    fn new(
+        instruction: VMem,
+        base: u64,
+        stride: u64,
+        xlen: u32,
+        vector: &Vector,
+        store: bool,
+    ) -> Result<Self, Error> {
+        let width = match instruction.width.as_u8() {
+            0b000 => 8,
+            0b101 => 16,
+            0b110 => 32,
+            0b111 => 64,
+            _ => return Err(Error::InvalidOpCode),
+        };
+        if instruction.mew || width > vector.elen {
+            return Err(Error::InvalidOpCode);
+        }
+        let vd = instruction.vd.as_u8();
+        let fields = instruction.nf.as_u8() as usize + 1;
+
+        #[deny(unreachable_patterns)]
+        let mode = match (instruction.mop.as_u8(), instruction.rs2.as_u8()) {
+            (0b00, 0b00000) => Mode::Unit,
+            (0b00, 0b01000) => Mode::Whole,
+            (0b00, 0b01011) => Mode::Mask,
+            (0b00, 0b10000) if !store => Mode::FaultFirst,
+            (0b00, _) => return Err(Error::InvalidOpCode),
+            (0b10, _) => Mode::Strided(stride),
+            (_, vs2) => Mode::Indexed(vs2, width),
+        };
+
+        let single = |eew: u32, evl: usize, end: usize| Self {
+            mode,
+            base,
+            xlen,
+            eew,
+            fields: 1,
+            regs: 1,
+            evl,
+            end,
+        };
+        match mode {
+            // nf encodes the number of registers, the whole group is accessed as bytes of `eew`
+            Mode::Whole => {
+                if !instruction.vm || !matches!(fields, 1 | 2 | 4 | 8) || (store && width != 8) {
+                    return Err(Error::InvalidOpCode);
+                }
+                check_group(vd, fields.trailing_zeros() as i32)?;
+                let evl = fields * vector.vlen() / width as usize;
+                return Ok(single(width, evl, evl));
+            }
+            _ if vector.vtype.vill => return Err(Error::InvalidOpCode),
+            Mode::Mask => {
+                if !instruction.vm || width != 8 || fields != 1 {
+                    return Err(Error::InvalidOpCode);
+                }
+                return Ok(single(8, vector.vl.div_ceil(8), vector.regs.vlenb()));
+            }
+            _ => {}
+        }
+
+        let (eew, emul) = match mode {
+            Mode::Indexed(vs2, index) => {
+                check_group(vs2, vector.emul(index))?;
+                (vector.vtype.sew(), vector.vtype.lmul_log2())
+            }
+            _ => (width, vector.emul(width)),
+        };
+        check_group(vd, emul)?;
+        let regs = 1 << emul.max(0);
+        if fields * regs > 8
+            || vd as usize + fields * regs > 32
+            || (!store && !instruction.vm && vd == 0)
+        {
+            return Err(Error::InvalidOpCode);
+        }
+
+        Ok(Self {
+            mode,
+            base,
+            xlen,
+            eew,
+            fields,
+            regs,
+            evl: vector.vl,
+            end: vector.elements(eew, emul),
+        })
+    }
+
+    /// Address of field `field` of element `i`.
+    fn address(&self, vector: &Vector, i: usize, field: usize) -> usize {
+        let size = (self.eew / 8) as u64;
+        let (i, field) = (i as u64, field as u64);
+        let offset = match self.mode {
+            Mode::Strided(stride) => i.wrapping_mul(stride).wrapping_add(field * size),
+            Mode::Indexed(vs2, eew) => vector.regs.get(vs2, i as usize, eew) + field * size,
+            _ => (i * self.fields as u64 + field) * size,
+        };
+        truncate(self.base.wrapping_add(offset), self.xlen) as usize
+    }
+
+    #[inline(always)]
+    fn register(&self, vd: u8, field: usize) -> u8 {
+        vd + (field * self.regs) as u8
+    }
+}
+
+fn read(mmu: &Mmu, memory: &[u8], vaddr: usize, eew: u32) -> Result<u64, Error> {
+    let addr = mmu.translate(vaddr as u64, eew as usize / 8, mmu::Access::Load, memory)?;
+    Ok(match eew {
+        8 => mem::read::<u8>(memory, addr)? as u64,
+        16 => mem::read::<mem::U16>(memory, addr)?.as_u16() as u64,
+        32 => mem::read::<mem::U32>(memory, addr)?.as_u32() as u64,
+        _ => mem::read::<mem::U64>(memory, addr)?.as_u64(),
+    })
+}
+
+fn write(mmu: &Mmu, memory: &mut [u8], vaddr: usize, eew: u32, value: u64) -> Result<(), Error> {
+    let addr = mmu.translate(vaddr as u64, eew as usize / 8, mmu::Access::Store, memory)?;
+    match eew {
+        8 => mem::write(&(value as u8), memory, addr),
+        16 => mem::write(&mem::U16::new(value as u16), memory, addr),
+        32 => mem::write(&mem::U32::new(value as u32), memory, addr),
+        _ => mem::write(&mem::U64::new(value), memory, addr),
+    }
+}
+
+/// Base address and stride of a vector memory access.
+fn operands<T>(instruction: VMem, regs: &Registers<T>) -> Result<(u64, u64), Error>
+where
+    T: Copy + Zero + As<u64>,
+{
+    let base = regs.decode(instruction.rs1)?.fetch(regs).r#as();
+    let stride = if instruction.mop.as_u8() == 0b10 {
+        sign_extend(
+            regs.decode(instruction.rs2)?.fetch(regs).r#as(),
+            xlen::<T>(),
+        ) as u64
+    } else {
+        0
+    };
+    Ok((base, stride))
+}
+
+/// Executes the vector loads, a faulting element is left in `vstart` unless it trims `vl` in a
+/// fault-only-first load.
+pub fn load<T>(
+    instruction: VMem,
+    regs: &Registers<T>,
+    vector: &mut Vector,
+    mmu: &Mmu,
+    memory: &[u8],
+) -> Result<(), Error>
+where
+    T: Copy + Zero + As<u64>,
+{
+    let (base, stride) = operands(instruction, regs)?;
+    let access = Access::new(instruction, base, stride, xlen::<T>(), vector, false)?;
+    let vd = instruction.vd.as_u8();
+
+    let mut fields = vec![Vec::with_capacity(access.evl); access.fields];
+    let mut fault = None;
+    'elements: for i in 0..access.evl {
+        let active = instruction.vm || vector.regs.mask(0, i);
+        for (field, results) in fields.iter_mut().enumerate() {
+            results.push(if i < vector.vstart {
+                Element::Undisturbed
+            } else if !active {
+                Element::Inactive
+            } else {
+                match read(mmu, memory, access.address(vector, i, field), access.eew) {
+                    Ok(value) => Element::Active(value),
+                    Err(error) => {
+                        fault = Some((i, error));
+                        break 'elements;
+                    }
+                }
+            });
+        }
+    }
+
+    let mut end = access.end;
+    if let Some((i, error)) = fault {
+        for results in fields.iter_mut() {
+            results.truncate(i);
+        }
+        if matches!(access.mode, Mode::FaultFirst) && i > 0 {
+            vector.vl = i;
+        } else {
+            end = i;
+            for (field, results) in fields.iter().enumerate() {
+                vector.commit(access.register(vd, field), access.eew, results, end);
+            }
+            vector.vstart = i;
+            return Err(error);
+        }
+    }
+
+    for (field, results) in fields.iter().enumerate() {
+        vector.commit(access.register(vd, field), access.eew, results, end);
+    }
+    vector.vstart = 0;
+    Ok(())
+}
+
+/// Executes the vector stores, a faulting element is left in `vstart`.
+pub fn store<T>(
+    instruction: VMem,
+    regs: &Registers<T>,
+    vector: &mut Vector,
+    mmu: &Mmu,
+    memory: &mut [u8],
+) -> Result<(), Error>
+where
+    T: Copy + Zero + As<u64>,
+{
+    let (base, stride) = operands(instruction, regs)?;
+    let access = Access::new(instruction, base, stride, xlen::<T>(), vector, true)?;
+    let vs3 = instruction.vd.as_u8();
+
+    for i in vector.vstart..access.evl {
+        if !instruction.vm && !vector.regs.mask(0, i) {
+            continue;
+        }
+        for field in 0..access.fields {
+            let value = vector.regs.get(access.register(vs3, field), i, access.eew);
+            let addr = access.address(vector, i, field);
+            if let Err(error) = write(mmu, memory, addr, access.eew, value) {
+                vector.vstart = i;
+                return Err(error);
+            }
+        }
+    }
+    vector.vstart = 0;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::Register;
+
+    const E8: u32 = 0b000_000;
+    const E16: u32 = 0b001_000;
+    const E32: u32 = 0b010_000;
+    const E64: u32 = 0b011_000;
+    const TA: u32 = 0b01_000_000;
+    const MA: u32 = 0b10_000_000;
+
+    fn op(funct6: u8, vm: bool, vs2: u8, vs1: u8, funct3: u8, vd: u8) -> V {
+        V::from_u32(
+            (funct6 as u32) << 26
+                | (vm as u32) << 25
+                | (vs2 as u32) << 20
+                | (vs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (vd as u32) << 7
+                | 0b1010111,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn access(nf: u8, mop: u8, vm: bool, rs2: u8, rs1: u8, width: u8, vd: u8) -> VMem {
+        VMem::from_u32(
+            (nf as u32) << 29
+                | (mop as u32) << 26
+                | (vm as u32) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (width as u32) << 12
+                | (vd as u32) << 7
+                | 0b0000111,
+        )
+    }
+
+    fn vsetvli(rd: u8, rs1: u8, vtypei: u32) -> I {
+        I::from_u32(vtypei << 20 | (rs1 as u32) << 15 | 0b111 << 12 | (rd as u32) << 7)
+    }
+
+    fn setup(avl: u64, vtypei: u32) -> (Registers<u64>, Vector) {
+        let mut regs = Registers::default();
+        let mut vector = Vector::default();
+        *regs.get_mut(Register::X1) = avl;
+        config(vsetvli(0, 1, vtypei), &mut regs, &mut vector).unwrap();
+        (regs, vector)
+    }
+
+    fn fill(vector: &mut Vector, reg: u8, eew: u32, values: &[u64]) {
+        for (i, value) in values.iter().enumerate() {
+            vector.regs.set(reg, i, eew, *value);
+        }
+    }
+
+    fn elements(vector: &Vector, reg: u8, eew: u32, len: usize) -> Vec<u64> {
+        (0..len).map(|i| vector.regs.get(reg, i, eew)).collect()
+    }
+
+    #[test]
+    fn vtype() {
+        let mut regs = Registers::<u64>::default();
+        let mut vector = Vector::default();
+        assert!(vector.vtype.vill);
+        *regs.get_mut(Register::X2) = 100;
+
+        config(vsetvli(1, 2, E32), &mut regs, &mut vector).unwrap();
+        assert_eq!((vector.vl, regs.get(Register::X1)), (4, 4));
+        // LMUL=8
+        config(vsetvli(1, 2, E8 | 0b011), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.vl, 100);
+        // rs1=x0 requests VLMAX, LMUL=1/2
+        config(vsetvli(1, 0, E16 | 0b111), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.vl, 4);
+        assert_eq!(vector.vtype.lmul_log2(), -1);
+        // rd=x0 and rs1=x0 keep vl
+        config(vsetvli(0, 0, E8 | TA | MA), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.vl, 4);
+        assert_eq!(vector.vtype.bits(64), 0b1100_0000);
+
+        // SEW=64 does not fit LMUL=1/2 with ELEN=64, reserved bits are illegal too
+        for vtypei in [E64 | 0b111, E8 | 0b100, 0x100] {
+            config(vsetvli(1, 2, vtypei), &mut regs, &mut vector).unwrap();
+            assert!(vector.vtype.vill);
+            assert_eq!(vector.vl, 0);
+            assert_eq!(vector.vtype.bits(32), 0x8000_0000);
+        }
+
+        // vsetivli
+        config(vsetvli(1, 3, 0b11 << 10 | E8), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.vl, 3);
+        // vsetvl
+        *regs.get_mut(Register::X3) = E16 as u64;
+        config(vsetvli(1, 2, 0b1000000 << 5 | 3), &mut regs, &mut vector).unwrap();
+        assert_eq!((vector.vl, vector.vtype.sew()), (8, 16));
+
+        let narrow = Vector::new(64, 32);
+        assert!(VType::new(E64 as u64, narrow.elen).vill);
+    }
+
+    #[test]
+    fn masking_and_tails() {
+        let (mut regs, mut vector) = setup(3, E32 | TA);
+        fill(&mut vector, 1, 32, &[1, 2, 3, 4]);
+        fill(&mut vector, 2, 32, &[10, 20, 30, 40]);
+        fill(&mut vector, 3, 32, &[7, 7, 7, 7]);
+        vector.regs.set(0, 0, 8, 0b101);
+
+        // vadd.vv v3, v2, v1, v0.t
+        math(op(0b000000, false, 2, 1, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [11, 7, 33, 0xffff_ffff]);
+
+        // vadd.vi v4, v2, -1 with agnostic inactive elements
+        vector.vtype.vma = true;
+        math(
+            op(0b000000, false, 2, 0b11111, OPIVI, 4),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(
+            elements(&vector, 4, 32, 4),
+            [9, 0xffff_ffff, 29, 0xffff_ffff]
+        );
+
+        // vrsub.vx v5, v2, x5
+        *regs.get_mut(Register::X5) = 100;
+        math(op(0b000011, true, 2, 5, OPIVX, 5), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 5, 32, 3), [90, 80, 70]);
+
+        // vmslt.vx v6, v2, x5 with x5=25
+        *regs.get_mut(Register::X5) = 25;
+        vector.vtype.vta = false;
+        math(op(0b011011, true, 2, 5, OPIVX, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(6, 0, 8), 0b011);
+
+        // vstart skips the leading elements and is cleared on completion
+        vector.vstart = 2;
+        math(op(0b001011, true, 2, 1, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 3), [11, 7, 30 ^ 3]);
+        assert_eq!(vector.vstart, 0);
+    }
+
+    #[test]
+    fn illegal() {
+        let mut regs = Registers::<u64>::default();
+        let mut vector = Vector::default();
+        let vadd = op(0b000000, true, 2, 1, OPIVV, 4);
+        assert_eq!(
+            math(vadd, &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+
+        let (mut regs, mut vector) = setup(8, E32 | 0b001);
+        // LMUL=2 groups start at even registers
+        assert_eq!(
+            math(op(0b000000, true, 2, 1, OPIVV, 4), &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+        assert_eq!(
+            math(op(0b000000, true, 2, 6, OPIVV, 4), &mut regs, &mut vector),
+            Ok(())
+        );
+        // masked destinations cannot overlap v0
+        assert_eq!(
+            math(op(0b000000, false, 2, 4, OPIVV, 0), &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+        // no vector floating point
+        assert_eq!(
+            math(op(0b000000, true, 2, 4, 0b001, 6), &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+        // widening needs 2*SEW <= ELEN
+        let (mut regs, mut vector) = setup(2, E64);
+        assert_eq!(
+            math(op(0b110000, true, 2, 4, OPMVV, 6), &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn fixed_point() {
+        let (mut regs, mut vector) = setup(4, E8);
+        fill(&mut vector, 1, 8, &[200, 3, 0x80, 7]);
+        fill(&mut vector, 2, 8, &[100, 4, 0x80, 0]);
+
+        // vsaddu.vv
+        math(op(0b100000, true, 1, 2, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 8, 4), [255, 7, 255, 7]);
+        assert!(vector.vxsat);
+
+        // vaaddu.vv rounding to nearest up then down
+        math(op(0b001000, true, 1, 2, OPMVV, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 8, 4), [150, 4, 0x80, 4]);
+        vector.vxrm = RDN;
+        math(op(0b001000, true, 1, 2, OPMVV, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 8, 4), [150, 3, 0x80, 3]);
+
+        // vsmul.vv saturates -1 * -1
+        vector.vxsat = false;
+        vector.vxrm = RNU;
+        math(op(0b100111, true, 1, 2, OPIVV, 5), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(5, 2, 8), 0x7f);
+        assert!(vector.vxsat);
+
+        // vnclipu.wi v6, v8, 2
+        vector.vxsat = false;
+        fill(&mut vector, 8, 16, &[0x0302, 0x0001, 0x0006, 0x00fe]);
+        math(op(0b101110, true, 8, 2, OPIVI, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 6, 8, 4), [0xc1, 0, 2, 0x40]);
+        assert!(!vector.vxsat);
+        // vnclip.wi v6, v8, 0
+        math(op(0b101111, true, 8, 0, OPIVI, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 6, 8, 4), [0x7f, 1, 6, 0x7f]);
+        assert!(vector.vxsat);
+
+        assert_eq!(roundoff(0b1011, 2, RNE), 0b11);
+        assert_eq!(roundoff(0b1010, 2, RNE), 0b10);
+        assert_eq!(roundoff(0b1000, 2, ROD), 0b10);
+        assert_eq!(roundoff(0b1001, 2, ROD), 0b11);
+    }
+
+    #[test]
+    fn widening_and_narrowing() {
+        let (mut regs, mut vector) = setup(2, E16);
+        fill(&mut vector, 1, 16, &[1000, 0xfffe]);
+        *regs.get_mut(Register::X5) = -3i64 as u64;
+
+        // vwmul.vx v2, v1, x5
+        math(op(0b111011, true, 1, 5, OPMVX, 2), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 2, 32, 2), [(-3000i32) as u32 as u64, 6]);
+        // vwmulu.vx v2, v1, x5
+        math(op(0b111000, true, 1, 5, OPMVX, 2), &mut regs, &mut vector).unwrap();
+        assert_eq!(
+            elements(&vector, 2, 32, 2),
+            [1000 * 0xfffd, 0xfffe * 0xfffd]
+        );
+        // vwadd.wv v4, v2, v1
+        fill(&mut vector, 2, 32, &[1, 2]);
+        math(op(0b110101, true, 2, 1, OPMVV, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 32, 2), [1001, 0]);
+        // vwmacc.vx v4, x5, v1
+        math(op(0b111101, true, 1, 5, OPMVX, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(
+            elements(&vector, 4, 32, 2),
+            [(1001 - 3000i32) as u32 as u64, 6]
+        );
+        // vnsra.wi v6, v4, 4
+        math(op(0b101101, true, 4, 4, OPIVI, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(
+            elements(&vector, 6, 16, 2),
+            [(-1999i32 >> 4) as u16 as u64, 0]
+        );
+
+        // vsext.vf2 v7, v1 on SEW=32, vzext.vf2
+        let (mut regs, mut vector) = setup(2, E32);
+        fill(&mut vector, 1, 16, &[0x8000, 5]);
+        math(
+            op(0b010010, true, 1, 0b00111, OPMVV, 7),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 7, 32, 2), [0xffff_8000, 5]);
+        math(
+            op(0b010010, true, 1, 0b00110, OPMVV, 7),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 7, 32, 2), [0x8000, 5]);
+        // vf8 needs 8 bit sources
+        assert_eq!(
+            math(
+                op(0b010010, true, 1, 0b00010, OPMVV, 7),
+                &mut regs,
+                &mut vector
+            ),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn integer_ops() {
+        let (mut regs, mut vector) = setup(4, E32);
+        fill(&mut vector, 1, 32, &[7, 0x8000_0000, 5, 9]);
+        fill(&mut vector, 2, 32, &[2, 0xffff_ffff, 0, 3]);
+
+        // vdiv.vv, vremu.vv
+        math(op(0b100001, true, 1, 2, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(
+            elements(&vector, 3, 32, 4),
+            [3, 0x8000_0000, 0xffff_ffff, 3]
+        );
+        math(op(0b100010, true, 1, 2, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [1, 0x8000_0000, 5, 0]);
+        // vmulh.vv, vmulhu.vv
+        math(op(0b100111, true, 1, 2, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 2), [0, 0]);
+        math(op(0b100100, true, 1, 2, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 2), [0, 0x7fff_ffff]);
+        // vmacc.vv v3, v1, v2
+        fill(&mut vector, 3, 32, &[1, 1, 1, 1]);
+        math(op(0b101101, true, 2, 1, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [15, 0x8000_0001, 1, 28]);
+        // vsra.vi, vmax.vv
+        math(op(0b101001, true, 1, 31, OPIVI, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 2), [0, 0xffff_ffff]);
+        math(op(0b000111, true, 1, 2, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [7, 0xffff_ffff, 5, 9]);
+
+        // vadc.vvm and vmadc.vim
+        vector.regs.set(0, 0, 8, 0b0010);
+        math(op(0b010000, false, 1, 2, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [9, 0x8000_0000, 5, 12]);
+        math(
+            op(0b010001, false, 1, 0b11111, OPIVI, 4),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(vector.regs.get(4, 0, 8) & 0b1111, 0b1111);
+        // vmerge.vxm and vmv.v.i
+        *regs.get_mut(Register::X5) = 42;
+        math(op(0b010111, false, 1, 5, OPIVX, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [7, 42, 5, 9]);
+        math(
+            op(0b010111, true, 0, 0b10000, OPIVI, 3),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [0xffff_fff0; 4]);
+
+        // the scalar is sign-extended from XLEN
+        let mut regs = Registers::<u32>::default();
+        let mut vector = Vector::default();
+        *regs.get_mut(Register::X1) = 2;
+        config(vsetvli(0, 1, E64), &mut regs, &mut vector).unwrap();
+        *regs.get_mut(Register::X5) = 0xffff_fffe;
+        math(op(0b000000, true, 1, 5, OPIVX, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 64, 2), [u64::MAX - 1; 2]);
+    }
+
+    #[test]
+    fn permutations() {
+        let (mut regs, mut vector) = setup(3, E32);
+        fill(&mut vector, 2, 32, &[10, 20, 30, 40]);
+        fill(&mut vector, 3, 32, &[7, 7, 7, 7]);
+        vector.regs.set(0, 0, 8, 0b101);
+
+        // vslideup.vi v3, v2, 1
+        math(op(0b001110, true, 2, 1, OPIVI, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [7, 10, 20, 7]);
+        // vslidedown.vi v4, v2, 2 reads up to VLMAX
+        math(op(0b001111, true, 2, 2, OPIVI, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 32, 3), [30, 40, 0]);
+        // vslide1up.vx, vslide1down.vx
+        *regs.get_mut(Register::X5) = 99;
+        math(op(0b001110, true, 2, 5, OPMVX, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 32, 3), [99, 10, 20]);
+        math(op(0b001111, true, 2, 5, OPMVX, 4), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 4, 32, 3), [20, 30, 99]);
+
+        // vrgather.vv, vrgather.vi
+        fill(&mut vector, 5, 32, &[3, 0, 9]);
+        math(op(0b001100, true, 2, 5, OPIVV, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 6, 32, 3), [40, 10, 0]);
+        math(op(0b001100, true, 2, 1, OPIVI, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 6, 32, 3), [20; 3]);
+
+        // vcompress.vm v7, v2, v0
+        math(op(0b010111, true, 2, 0, OPMVV, 7), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 7, 32, 2), [10, 30]);
+
+        // vmv.s.x and vmv.x.s
+        *regs.get_mut(Register::X5) = -5i64 as u64;
+        math(op(0b010000, true, 0, 5, OPMVX, 8), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(8, 0, 32), 0xffff_fffb);
+        math(op(0b010000, true, 8, 0, OPMVV, 6), &mut regs, &mut vector).unwrap();
+        assert_eq!(regs.get(Register::X6), -5i64 as u64);
+
+        // vmv2r.v copies whole registers
+        fill(&mut vector, 11, 64, &[1, 2]);
+        math(op(0b100111, true, 10, 1, OPIVI, 12), &mut regs, &mut vector).unwrap();
+        assert_eq!(elements(&vector, 13, 64, 2), [1, 2]);
+    }
+
+    #[test]
+    fn masks_and_reductions() {
+        let (mut regs, mut vector) = setup(3, E32);
+        fill(&mut vector, 2, 32, &[10, 20, 30, 40]);
+        fill(&mut vector, 1, 32, &[1, 0, 0, 0]);
+        vector.regs.set(0, 0, 8, 0b101);
+
+        // vredsum.vs v3, v2, v1
+        math(op(0b000000, true, 2, 1, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(3, 0, 32), 61);
+        // vredmaxu.vs masked
+        math(op(0b000110, false, 2, 1, OPMVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(3, 0, 32), 30);
+        // vwredsumu.vs
+        math(op(0b110000, true, 2, 1, OPIVV, 3), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(3, 0, 64), 61);
+
+        // vcpop.m, vfirst.m
+        math(
+            op(0b010000, true, 0, 0b10000, OPMVV, 6),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X6), 2);
+        vector.regs.set(4, 0, 8, 0b100);
+        math(
+            op(0b010000, true, 4, 0b10001, OPMVV, 6),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X6), 2);
+        vector.regs.set(4, 0, 8, 0b000);
+        math(
+            op(0b010000, true, 4, 0b10001, OPMVV, 6),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(regs.get(Register::X6), u64::MAX);
+
+        // vid.v, viota.m
+        math(
+            op(0b010100, true, 0, 0b10001, OPMVV, 8),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 8, 32, 3), [0, 1, 2]);
+        math(
+            op(0b010100, true, 0, 0b10000, OPMVV, 8),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 8, 32, 3), [0, 1, 1]);
+
+        // vmsbf.m, vmsif.m, vmsof.m
+        vector.regs.set(4, 0, 8, 0b110);
+        vector.regs.set(5, 0, 8, 0);
+        math(
+            op(0b010100, true, 4, 0b00001, OPMVV, 5),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(vector.regs.get(5, 0, 8) & 0b111, 0b001);
+        math(
+            op(0b010100, true, 4, 0b00011, OPMVV, 5),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(vector.regs.get(5, 0, 8) & 0b111, 0b011);
+        math(
+            op(0b010100, true, 4, 0b00010, OPMVV, 5),
+            &mut regs,
+            &mut vector,
+        )
+        .unwrap();
+        assert_eq!(vector.regs.get(5, 0, 8) & 0b111, 0b010);
+
+        // vmandn.mm v5, v4, v0
+        math(op(0b011000, true, 4, 0, OPMVV, 5), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(5, 0, 8) & 0b111, 0b010);
+        // vmxnor.mm
+        math(op(0b011111, true, 4, 0, OPMVV, 5), &mut regs, &mut vector).unwrap();
+        assert_eq!(vector.regs.get(5, 0, 8) & 0b111, 0b100);
+
+        // reductions require vstart=0
+        vector.vstart = 1;
+        assert_eq!(
+            math(op(0b000000, true, 2, 1, OPMVV, 3), &mut regs, &mut vector),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn loads_and_stores() {
+        let mut memory = [0u8; 0x240];
+        for i in 0..8u32 {
+            memory[0x100 + 4 * i as usize..][..4].copy_from_slice(&(i + 1).to_le_bytes());
+        }
+        let (mut regs, mut vector) = setup(4, E32);
+        *regs.get_mut(Register::X10) = 0x100;
+        *regs.get_mut(Register::X11) = 8;
+
+        // vle32.v v1, (x10)
+        load(
+            access(0, 0b00, true, 0, 10, 0b110, 1),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 1, 32, 4), [1, 2, 3, 4]);
+        // vlse32.v v2, (x10), x11
+        load(
+            access(0, 0b10, true, 11, 10, 0b110, 2),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 2, 32, 4), [1, 3, 5, 7]);
+        // vluxei8.v v3, (x10), v4
+        fill(&mut vector, 4, 8, &[12, 0, 4, 8]);
+        load(
+            access(0, 0b01, true, 4, 10, 0b000, 3),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 3, 32, 4), [4, 1, 2, 3]);
+        // vlseg2e32.v v5, (x10)
+        load(
+            access(1, 0b00, true, 0, 10, 0b110, 5),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 5, 32, 4), [1, 3, 5, 7]);
+        assert_eq!(elements(&vector, 6, 32, 4), [2, 4, 6, 8]);
+        // vle16.v v7, (x10) has EMUL=1/2
+        load(
+            access(0, 0b00, true, 0, 10, 0b101, 7),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 7, 16, 4), [1, 0, 2, 0]);
+
+        // vse32.v v2, (x12), v0.t
+        *regs.get_mut(Register::X12) = 0x200;
+        vector.regs.set(0, 0, 8, 0b1001);
+        store(
+            access(0, 0b00, false, 0, 12, 0b110, 2),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(
+            memory[0x200..0x210],
+            [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0]
+        );
+        // vsse8.v with a negative stride
+        *regs.get_mut(Register::X11) = -1i64 as u64;
+        *regs.get_mut(Register::X12) = 0x223;
+        store(
+            access(0, 0b10, true, 11, 12, 0b000, 1),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[0x220..0x224], [0, 0, 0, 1]);
+
+        // vlm.v, vl1re8.v and vs1r.v work on whole registers
+        load(
+            access(0, 0b00, true, 0b01011, 10, 0b000, 8),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(vector.regs.get(8, 0, 8), 1);
+        vector.vtype = VType::ILLEGAL;
+        load(
+            access(0, 0b00, true, 0b01000, 10, 0b000, 9),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!(elements(&vector, 9, 32, 4), [1, 2, 3, 4]);
+        *regs.get_mut(Register::X12) = 0x210;
+        store(
+            access(0, 0b00, true, 0b01000, 12, 0b000, 9),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &mut memory,
+        )
+        .unwrap();
+        assert_eq!(memory[0x210..0x220], memory[0x100..0x110]);
+        assert_eq!(
+            load(
+                access(0, 0b00, true, 0, 10, 0b110, 1),
+                &regs,
+                &mut vector,
+                &Mmu::BARE,
+                &memory
+            ),
+            Err(Error::InvalidOpCode)
+        );
+    }
+
+    #[test]
+    fn faults() {
+        let memory = [0xffu8; 0x40];
+        let (mut regs, mut vector) = setup(4, E32);
+        *regs.get_mut(Register::X10) = 0x38;
+        fill(&mut vector, 1, 32, &[0; 4]);
+
+        // element 2 is out of memory, vstart points at it
+        assert_eq!(
+            load(
+                access(0, 0b00, true, 0, 10, 0b110, 1),
+                &regs,
+                &mut vector,
+                &Mmu::BARE,
+                &memory
+            ),
+            Err(Error::LoadAccessFault(0x40))
+        );
+        assert_eq!(vector.vstart, 2);
+        assert_eq!(
+            elements(&vector, 1, 32, 4),
+            [0xffff_ffff, 0xffff_ffff, 0, 0]
+        );
+
+        // vle32ff.v trims vl instead
+        vector.vstart = 0;
+        fill(&mut vector, 1, 32, &[0; 4]);
+        load(
+            access(0, 0b00, true, 0b10000, 10, 0b110, 1),
+            &regs,
+            &mut vector,
+            &Mmu::BARE,
+            &memory,
+        )
+        .unwrap();
+        assert_eq!((vector.vl, vector.vstart), (2, 0));
+        assert_eq!(
+            elements(&vector, 1, 32, 4),
+            [0xffff_ffff, 0xffff_ffff, 0, 0]
+        );
+
+        // unless the first element faults
+        *regs.get_mut(Register::X10) = 0x40;
+        assert_eq!(
+            load(
+                access(0, 0b00, true, 0b10000, 10, 0b110, 1),
+                &regs,
+                &mut vector,
+                &Mmu::BARE,
+                &memory
+            ),
+            Err(Error::LoadAccessFault(0x40))
+        );
+        assert_eq!((vector.vl, vector.vstart), (2, 0));
+    }
+}